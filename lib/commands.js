@@ -10,6 +10,9 @@ const vscode = require('vscode');
 // hsl(90deg, .2, .5)
 const colorRegexp = /((rgb|hsl)a? *\( *(\d+(\.\d+)? *(%|deg|rad|grad|turn)?[,\/ ] *?){3}\d+(\.\d+)? *%?\))|(#[0-9A-F]{3,8})/i;
 
+// exit code the picker uses when it crashes, as opposed to a commit or abort
+const CRASH_EXIT_CODE = 70;
+
 exports.showPicker = function() {
   try {
     const textEditor = vscode.window.activeTextEditor;
@@ -28,16 +31,35 @@ exports.showPicker = function() {
     const font = config.get('editor.fontFamily');
     const fontSize = config.get('editor.fontSize');
 
-    console.log({pickerPath, color, font, fontSize});
-    const picker = spawn(pickerPath, [color, '--font', font, '--font-size', fontSize], {
-      stdio: ['ignore', 'pipe', 'ignore'],
+    const position = wordRange.start;
+    const context = `${vscode.workspace.asRelativePath(textEditor.document.uri)}:${position.line + 1}:${position.character + 1}`;
+
+    const ui = uiColors(config);
+
+    console.log({pickerPath, color, font, fontSize, context, ui});
+    const args = [color, '--font', font, '--font-size', fontSize, '--context', context, '--error-format', 'json', '--parent-pid', String(process.pid)];
+    if (ui) {
+      args.push('--ui-colors', JSON.stringify(ui));
+    }
+    const picker = spawn(pickerPath, args, {
+      stdio: ['ignore', 'pipe', 'pipe'],
     });
     let buf = '';
+    let errBuf = '';
     picker.stdout.on('data', chunk => buf += chunk.toString());
+    picker.stderr.on('data', chunk => errBuf += chunk.toString());
     picker.on('exit', code => {
       buf = buf.trim();
-      if (code === 0 && colorRegexp.test(buf)) {
-        textEditor.edit(edit => edit.replace(wordRange, buf.trim()));
+      // the picker prints nothing on abort, and a single line on commit, in
+      // formats this regexp may not know; more than that isn't a color
+      if (code === 0 && buf && !buf.includes('\n')) {
+        textEditor.edit(edit => edit.replace(wordRange, buf));
+      } else if (code === 0 && buf) {
+        vscode.window.showErrorMessage('Color picker printed more than a color: ' + buf);
+      } else if (code === CRASH_EXIT_CODE) {
+        vscode.window.showErrorMessage('Color picker crashed: ' + errorMessage(errBuf));
+      } else if (code !== 0) {
+        vscode.window.showErrorMessage('Color picker could not start: ' + errorMessage(errBuf));
       }
     })
   } catch (e) {
@@ -45,3 +67,39 @@ exports.showPicker = function() {
     console.error(e);
   }
 };
+
+// the default Light+ and Dark+ colors, as the API doesn't expose the theme's
+const THEME_COLORS = {
+  light: {background: '#ffffff', foreground: '#000000', accent: '#0090f1'},
+  dark: {background: '#1e1e1e', foreground: '#d4d4d4', accent: '#007fd4'},
+};
+
+// the editor's colors for the picker to match: the user's customizations, or
+// defaults for the kind of theme that's active. null on VS Code versions that
+// can't tell, leaving the picker's own theme setting
+function uiColors(config) {
+  const theme = vscode.window.activeColorTheme;
+  const custom = config.get('workbench.colorCustomizations') || {};
+  if (!theme && !custom['editor.background']) {
+    return null;
+  }
+  // 4 is HighContrastLight, which older typings don't have
+  const light = !theme || theme.kind === vscode.ColorThemeKind.Light || theme.kind === 4;
+  const defaults = light ? THEME_COLORS.light : THEME_COLORS.dark;
+  return {
+    background: custom['editor.background'] || defaults.background,
+    foreground: custom['editor.foreground'] || defaults.foreground,
+    accent: custom['focusBorder'] || defaults.accent,
+  };
+}
+
+// the picker reports panics and bad arguments as JSON on the last line of stderr
+function errorMessage(stderr) {
+  const lines = stderr.trim().split('\n');
+  try {
+    const {message, suggestion} = JSON.parse(lines[lines.length - 1]);
+    return suggestion ? `${message} (did you mean ${suggestion}?)` : message;
+  } catch (e) {
+    return stderr.trim();
+  }
+}
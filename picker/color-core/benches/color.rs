@@ -0,0 +1,31 @@
+//! Conversions and formatting, the per-color work behind every paint and
+//! every copy.
+//!
+//! Run with `cargo bench -p color-core`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use color_core::{parse, Color, Format, FormatOptions};
+
+fn conversions(c: &mut Criterion) {
+    c.bench_function("hsv to rgb", |b| b.iter(|| Color::from_hsva_f32(black_box(0.3), black_box(0.6), black_box(0.9), 1.0)));
+    c.bench_function("rgb to hsv", |b| b.iter(|| Color::from_rgba_f32(black_box(0.2), black_box(0.5), black_box(0.8), 1.0)));
+    let color = Color::from_rgba_f32(0.2, 0.5, 0.8, 1.0);
+    c.bench_function("pixel", |b| b.iter(|| black_box(&color).pixel()));
+    c.bench_function("to oklch", |b| b.iter(|| black_box(&color).to_oklch()));
+    c.bench_function("from oklch", |b| b.iter(|| Color::from_oklcha_f32(black_box(0.6), black_box(0.1), black_box(0.7), 1.0)));
+}
+
+fn formatting(c: &mut Criterion) {
+    let color = Color::from_rgba_f32(0.2, 0.5, 0.8, 0.75);
+    let options = FormatOptions::default();
+    let mut group = c.benchmark_group("format");
+    for format in Format::values(&options) {
+        group.bench_function(format.to_string(), |b| b.iter(|| format.format(black_box(&color), &options)));
+    }
+    group.finish();
+    c.bench_function("parse", |b| b.iter(|| parse(black_box("hsl(210deg, 60%, 50%)"))));
+}
+
+criterion_group!(benches, conversions, formatting);
+criterion_main!(benches);
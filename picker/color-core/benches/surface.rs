@@ -0,0 +1,45 @@
+//! Filling the picker surfaces' pixel buffers, at about the size of the
+//! saturation/value surface on a HiDPI display.
+//!
+//! Run with `cargo bench -p color-core --bench surface`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use color_core::{surface, Color};
+
+const SIZE: usize = 200;
+
+/// The saturation/value surface a pixel at a time, for comparison.
+fn sat_value_scalar(buf: &mut Vec<u8>, hue: f32) {
+    surface::draw(buf, SIZE, SIZE, |x, y| {
+        Color::from_hsva_f32(hue, x as f32 / SIZE as f32, 1.0 - y as f32 / SIZE as f32, 1.0).pixel()
+    });
+}
+
+fn buffers(c: &mut Criterion) {
+    c.bench_function("sat_value fresh", |b| b.iter(|| {
+        let mut buf = Vec::new();
+        surface::sat_value(&mut buf, SIZE, SIZE, black_box(0.3), 1.0);
+        buf
+    }));
+    let mut buf = Vec::new();
+    c.bench_function("sat_value reused", |b| b.iter(|| surface::sat_value(&mut buf, SIZE, SIZE, black_box(0.3), 1.0)));
+    c.bench_function("sat_value scalar", |b| b.iter(|| sat_value_scalar(&mut buf, black_box(0.3))));
+    c.bench_function("chroma_lightness reused", |b| b.iter(|| {
+        surface::chroma_lightness(&mut buf, SIZE, SIZE, black_box(0.75))
+    }));
+}
+
+/// The sliders, which are narrow but repaint on most changes.
+fn strips(c: &mut Criterion) {
+    let mut buf = Vec::new();
+    c.bench_function("hue strip", |b| b.iter(|| {
+        surface::draw(&mut buf, 20, SIZE, |_x, y| Color::from_hsva_f32(y as f32 / SIZE as f32, black_box(0.6), 0.9, 1.0).pixel())
+    }));
+    c.bench_function("chroma strip", |b| b.iter(|| {
+        surface::chroma_strip(&mut buf, 20, SIZE, black_box(0.6), 0.75)
+    }));
+}
+
+criterion_group!(benches, buffers, strips);
+criterion_main!(benches);
@@ -0,0 +1,79 @@
+use crate::color::Color;
+
+/// Channel levels of the xterm 6×6×6 color cube (indices 16–231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// RGB of an xterm 256-color index. The first 16 use xterm's defaults, though
+/// terminals commonly remap them.
+pub fn palette_256(index: u8) -> [u8; 3] {
+    const SYSTEM: [[u8; 3]; 16] = [
+        [0, 0, 0], [205, 0, 0], [0, 205, 0], [205, 205, 0],
+        [0, 0, 238], [205, 0, 205], [0, 205, 205], [229, 229, 229],
+        [127, 127, 127], [255, 0, 0], [0, 255, 0], [255, 255, 0],
+        [92, 92, 255], [255, 0, 255], [0, 255, 255], [255, 255, 255],
+    ];
+    match index {
+        0..=15 => SYSTEM[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            [CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize]]
+        }
+        _ => {
+            let v = 8 + 10 * (index - 232);
+            [v, v, v]
+        }
+    }
+}
+
+/// The perceptually closest index from the cube and grey ramp. The 16 system
+/// colors are skipped since terminals don't agree on them.
+pub fn nearest_256(color: &Color) -> u8 {
+    let opaque = Color::from_rgba_f32(color.red(), color.green(), color.blue(), 1.0);
+    (16..=255u8)
+        .map(|i| {
+            let [r, g, b] = palette_256(i);
+            let candidate = Color::from_rgba_f32(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0);
+            (i, opaque.delta_e(&candidate))
+        })
+        .fold((16, f32::MAX), |best, c| if c.1 < best.1 { c } else { best })
+        .0
+}
+
+/// The 24-bit foreground escape, written out as `\x1b[38;2;r;g;bm` so it can
+/// be pasted into source code.
+pub fn truecolor_escape(color: &Color) -> String {
+    let [r, g, b, _] = color.pixel();
+    format!("\\x1b[38;2;{};{};{}m", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{nearest_256, palette_256, truecolor_escape};
+    use crate::color::Color;
+
+    fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color::from_rgba_f32(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0)
+    }
+
+    #[test]
+    fn palette() {
+        assert_eq!(palette_256(16), [0, 0, 0]);
+        assert_eq!(palette_256(196), [255, 0, 0]);
+        assert_eq!(palette_256(110), [135, 175, 215]);
+        assert_eq!(palette_256(232), [8, 8, 8]);
+        assert_eq!(palette_256(255), [238, 238, 238]);
+    }
+
+    #[test]
+    fn nearest() {
+        assert_eq!(nearest_256(&rgb(255, 0, 0)), 196);
+        assert_eq!(nearest_256(&rgb(135, 175, 215)), 110);
+        assert_eq!(nearest_256(&rgb(130, 130, 130)), 244);
+        assert_eq!(nearest_256(&rgb(250, 2, 3)), 196);
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(truecolor_escape(&rgb(255, 128, 0)), "\\x1b[38;2;255;128;0m");
+    }
+}
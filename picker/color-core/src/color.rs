@@ -0,0 +1,1285 @@
+#![allow(clippy::many_single_char_names)]
+
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "druid")]
+use druid::Data;
+use once_cell::sync::OnceCell;
+
+/// Whether hex output may use the 3/4 digit shorthand (`#fc0`) when exact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "druid", derive(Data))]
+pub enum HexShort {
+    Auto,
+    Never,
+}
+impl FromStr for HexShort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(HexShort::Auto),
+            "never" => Ok(HexShort::Never),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for HexShort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Auto => "auto",
+            Self::Never => "never",
+        })
+    }
+}
+
+/// How the CSS-style formats write alpha: `50%`, or a `0.50` number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "druid", derive(Data))]
+pub enum AlphaRepr {
+    Percent,
+    Number,
+}
+impl FromStr for AlphaRepr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "percent" => Ok(AlphaRepr::Percent),
+            "number" => Ok(AlphaRepr::Number),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for AlphaRepr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Percent => "percent",
+            Self::Number => "number",
+        })
+    }
+}
+
+/// Precision of the numeric output: 8 or 16 bits per channel, or unquantized
+/// floats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "druid", derive(Data))]
+pub enum Depth {
+    Eight,
+    Sixteen,
+    Float,
+}
+impl Depth {
+    pub fn values() -> Vec<Depth> {
+        vec![Depth::Eight, Depth::Sixteen, Depth::Float]
+    }
+
+    /// Scales a 0..1 channel to this depth's integer range, or leaves it as
+    /// is for float.
+    fn quantize(self, x: f32) -> f32 {
+        match self {
+            Self::Eight => quantize(x, 255.0),
+            Self::Sixteen => quantize(x, 65535.0),
+            Self::Float => x,
+        }
+    }
+    /// Decimal places for a readout that uses `places` at 8 bits, so higher
+    /// depths don't lose precision in the output.
+    fn places(self, places: usize) -> usize {
+        match self {
+            Self::Eight => places,
+            Self::Sixteen => places + 3,
+            Self::Float => places + 4,
+        }
+    }
+    /// Formats a readout, keeping the fixed 8-bit style (`0.50`) at 8 bits.
+    fn number(self, x: f32, places: usize) -> String {
+        match self {
+            Self::Eight => format!("{:.*}", places, round_places(x, places)),
+            _ => trimmed(x, self.places(places)),
+        }
+    }
+}
+impl FromStr for Depth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "8" => Ok(Depth::Eight),
+            "16" => Ok(Depth::Sixteen),
+            "float" => Ok(Depth::Float),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for Depth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Eight => "8",
+            Self::Sixteen => "16",
+            Self::Float => "float",
+        })
+    }
+}
+
+/// How OKLCH edits that leave sRGB are brought back in gamut.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "druid", derive(Data))]
+pub enum GamutClamp {
+    /// Clip each RGB channel, which can shift hue and lightness.
+    Clip,
+    /// Reduce chroma until the color fits, keeping lightness and hue.
+    Chroma,
+}
+impl FromStr for GamutClamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clip" => Ok(GamutClamp::Clip),
+            "chroma" => Ok(GamutClamp::Chroma),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for GamutClamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Clip => "clip",
+            Self::Chroma => "chroma",
+        })
+    }
+}
+
+/// A reference white for chromatic adaptation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhitePoint {
+    /// Print and ICC profiles' white.
+    D50,
+    /// sRGB's white, average daylight.
+    D65,
+    /// Illuminant A, incandescent tungsten light.
+    Tungsten,
+}
+impl FromStr for WhitePoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "d50" => Ok(WhitePoint::D50),
+            "d65" => Ok(WhitePoint::D65),
+            "tungsten" => Ok(WhitePoint::Tungsten),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for WhitePoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::D50 => "d50",
+            Self::D65 => "d65",
+            Self::Tungsten => "tungsten",
+        })
+    }
+}
+impl WhitePoint {
+    /// CIE XYZ of the white, with Y = 1.
+    fn xyz(&self) -> (f32, f32, f32) {
+        match self {
+            Self::D50 => (0.964_22, 1.0, 0.825_21),
+            Self::D65 => (0.950_47, 1.0, 1.088_83),
+            Self::Tungsten => (1.098_5, 1.0, 0.355_85),
+        }
+    }
+}
+
+/// Top of the chroma scale, as in CSS (`oklch(l 100% h)` is chroma 0.4).
+pub const MAX_OKLCH_CHROMA: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy)]
+struct Rgb(f32, f32, f32);
+
+#[derive(Debug, Clone, Copy)]
+struct Hsv(f32, f32, f32);
+
+/// The model a color was last set in. Its coordinates are kept as given and
+/// the other model's are only ever derived from them, so edits don't round
+/// trip between the two and drift.
+#[derive(Debug, Clone, Copy)]
+enum Model {
+    Rgb(Rgb),
+    Hsv(Hsv),
+}
+
+#[derive(Debug, Clone)]
+pub struct Color {
+    model: Model,
+    /// The other model's coordinates, computed when first asked for.
+    derived: OnceCell<(f32, f32, f32)>,
+    a: f32,
+}
+
+impl Color {
+    pub fn from_hsva_f32(h: f32, s: f32, v: f32, a: f32) -> Self {
+        Self { model: Model::Hsv(Hsv(h, s, v)), derived: OnceCell::new(), a }
+    }
+    pub fn from_rgba_f32(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { model: Model::Rgb(Rgb(r, g, b)), derived: OnceCell::new(), a }
+    }
+
+    fn rgb(&self) -> Rgb {
+        match self.model {
+            Model::Rgb(rgb) => rgb,
+            Model::Hsv(Hsv(h, s, v)) => {
+                let (r, g, b) = *self.derived.get_or_init(|| hsv_to_rgb(h, s, v));
+                Rgb(r, g, b)
+            }
+        }
+    }
+    fn hsv(&self) -> Hsv {
+        match self.model {
+            Model::Hsv(hsv) => hsv,
+            Model::Rgb(Rgb(r, g, b)) => {
+                let (h, s, v) = *self.derived.get_or_init(|| rgb_to_hsv(r, g, b));
+                Hsv(h, s, v)
+            }
+        }
+    }
+    /// Makes HSV the model, if it isn't, and edits it.
+    fn edit_hsv(&mut self, edit: impl FnOnce(&mut Hsv)) {
+        let mut hsv = self.hsv();
+        edit(&mut hsv);
+        self.model = Model::Hsv(hsv);
+        self.derived = OnceCell::new();
+    }
+    /// Makes RGB the model, if it isn't, and edits it. A grey has no hue in
+    /// RGB, so one is kept in HSV with the hue it had.
+    fn edit_rgb(&mut self, edit: impl FnOnce(&mut Rgb)) {
+        let hue = self.hue();
+        let mut rgb = self.rgb();
+        edit(&mut rgb);
+        let Rgb(r, g, b) = rgb;
+        self.model = if r == g && g == b { Model::Hsv(Hsv(hue, 0.0, r)) } else { Model::Rgb(rgb) };
+        self.derived = OnceCell::new();
+    }
+
+    pub fn hue(&self) -> f32 {
+        self.hsv().0
+    }
+    pub fn saturation(&self) -> f32 {
+        self.hsv().1
+    }
+    pub fn value(&self) -> f32 {
+        self.hsv().2
+    }
+    pub fn alpha(&self) -> f32 {
+        self.a
+    }
+    pub fn red(&self) -> f32 {
+        self.rgb().0
+    }
+    pub fn green(&self) -> f32 {
+        self.rgb().1
+    }
+    pub fn blue(&self) -> f32 {
+        self.rgb().2
+    }
+    /// Whether any channel is brighter than SDR white, so the swatch shows a
+    /// clamped preview.
+    pub fn is_clipped(&self) -> bool {
+        let Rgb(r, g, b) = self.rgb();
+        [r, g, b].iter().any(|&c| c > 1.0 && !feq(c, 1.0))
+    }
+    /// Hue, saturation and lightness, each 0..1.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let Hsv(h, s, v) = self.hsv();
+        hsv_to_hsl(h, s, v)
+    }
+
+    pub fn set_hue(&mut self, h: f32) {
+        self.edit_hsv(|hsv| hsv.0 = h);
+    }
+    pub fn set_saturation(&mut self, s: f32) {
+        self.edit_hsv(|hsv| hsv.1 = s);
+    }
+    pub fn set_value(&mut self, v: f32) {
+        self.edit_hsv(|hsv| hsv.2 = v);
+    }
+    pub fn set_alpha(&mut self, a: f32) {
+        self.a = a;
+    }
+    pub fn set_red(&mut self, r: f32) {
+        self.edit_rgb(|rgb| rgb.0 = r);
+    }
+    pub fn set_green(&mut self, g: f32) {
+        self.edit_rgb(|rgb| rgb.1 = g);
+    }
+    pub fn set_blue(&mut self, b: f32) {
+        self.edit_rgb(|rgb| rgb.2 = b);
+    }
+    /// [`Color::set_red`] with an 8-bit channel.
+    pub fn set_red_u8(&mut self, r: u8) {
+        self.set_red(f(r));
+    }
+    /// [`Color::set_green`] with an 8-bit channel.
+    pub fn set_green_u8(&mut self, g: u8) {
+        self.set_green(f(g));
+    }
+    /// [`Color::set_blue`] with an 8-bit channel.
+    pub fn set_blue_u8(&mut self, b: u8) {
+        self.set_blue(f(b));
+    }
+
+    /// CIE L*a*b* (D65) coordinates of the color, ignoring alpha.
+    pub fn to_lab(&self) -> (f32, f32, f32) {
+        let Rgb(r, g, b) = self.rgb();
+        rgb_to_lab(r, g, b)
+    }
+
+    /// WCAG 2 relative luminance, 0 for black to 1 for white.
+    pub fn relative_luminance(&self) -> f32 {
+        let Rgb(r, g, b) = self.rgb();
+        let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// WCAG 2 contrast ratio against another color, 1 to 21.
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let (y1, y2) = (self.relative_luminance(), other.relative_luminance());
+        (y1.max(y2) + 0.05) / (y1.min(y2) + 0.05)
+    }
+
+    /// The color with its OKLCH lightness moved just far enough from
+    /// `other`'s to reach `ratio`, or as far as it goes if it can't.
+    pub fn with_contrast(&self, other: &Color, ratio: f32) -> Color {
+        if self.contrast_ratio(other) >= ratio {
+            return self.clone();
+        }
+        let (l, c, h) = self.to_oklch();
+        let at = |l: f32| Self::from_oklcha_clamped(l, c, h, self.a, GamutClamp::Chroma);
+        // go the way there's more room, i.e. away from the other color
+        let (mut near, mut far) = if other.relative_luminance() > 0.18 { (l, 0.0) } else { (l, 1.0) };
+        if at(far).contrast_ratio(other) < ratio {
+            return at(far);
+        }
+        for _ in 0..24 {
+            let mid = (near + far) / 2.0;
+            if at(mid).contrast_ratio(other) >= ratio {
+                far = mid;
+            } else {
+                near = mid;
+            }
+        }
+        at(far)
+    }
+
+    /// The color chromatically adapted from sRGB's D65 white to `white`
+    /// (Bradford), i.e. roughly how it shifts when that light is taken as
+    /// white. Channels that leave sRGB are clipped.
+    pub fn adapt(&self, white: WhitePoint) -> Color {
+        let Rgb(r, g, b) = self.rgb();
+        let (x, y, z) = rgb_to_xyz(r, g, b);
+        let (x, y, z) = bradford((x, y, z), WhitePoint::D65.xyz(), white.xyz());
+        let (r, g, b) = xyz_to_rgb(x, y, z);
+        Self::from_rgba_f32(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), self.a)
+    }
+
+    /// Builds a color from CIELAB (D65, like [`Color::to_lab`]), clipping
+    /// channels that fall outside sRGB.
+    pub fn from_laba_f32(l: f32, a: f32, b: f32, alpha: f32) -> Self {
+        let (r, g, b) = lab_to_rgb(l, a, b);
+        Self::from_rgba_f32(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), alpha)
+    }
+    /// Builds a color from CIELAB relative to `white`, e.g. the D50 of print
+    /// and ICC data, adapted to sRGB's D65 (Bradford) and clipped to sRGB.
+    pub fn from_laba_white_f32(l: f32, a: f32, b: f32, alpha: f32, white: WhitePoint) -> Self {
+        let xyz = bradford(lab_to_xyz(l, a, b, white.xyz()), white.xyz(), WhitePoint::D65.xyz());
+        let (r, g, b) = xyz_to_rgb(xyz.0, xyz.1, xyz.2);
+        Self::from_rgba_f32(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), alpha)
+    }
+
+    /// OKLab coordinates of the color, ignoring alpha. L is 0..1.
+    pub fn to_oklab(&self) -> (f32, f32, f32) {
+        let Rgb(r, g, b) = self.rgb();
+        rgb_to_oklab(r, g, b)
+    }
+
+    /// OKLCH lightness (0..1), chroma and hue (0..1, like [`Color::hue`]).
+    pub fn to_oklch(&self) -> (f32, f32, f32) {
+        let (l, a, b) = self.to_oklab();
+        let h = (b.atan2(a) / std::f32::consts::TAU).rem_euclid(1.0);
+        (l, (a * a + b * b).sqrt(), h)
+    }
+    /// Builds a color from OKLCH, clipping channels that fall outside sRGB.
+    pub fn from_oklcha_f32(l: f32, c: f32, h: f32, a: f32) -> Self {
+        let (r, g, b) = oklch_to_rgb(l, c, h);
+        Self::from_rgba_f32(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), a)
+    }
+
+    /// Like [`Color::from_oklcha_f32`], but with a choice of how to handle
+    /// colors outside sRGB.
+    pub fn from_oklcha_clamped(l: f32, c: f32, h: f32, a: f32, clamp: GamutClamp) -> Self {
+        match clamp {
+            GamutClamp::Clip => Self::from_oklcha_f32(l, c, h, a),
+            GamutClamp::Chroma => Self::from_oklcha_f32(l, c.min(max_oklch_chroma(l, h)), h, a),
+        }
+    }
+
+    /// The color `t` (0..1) of the way from this one to `other`, blended in
+    /// OKLab so the midpoints look even. Alpha is blended linearly.
+    pub fn mix(&self, other: &Color, t: f32) -> Color {
+        if t <= 0.0 {
+            return self.clone();
+        }
+        if t >= 1.0 {
+            return other.clone();
+        }
+        let lerp = |x: f32, y: f32| x + (y - x) * t;
+        let (l1, a1, b1) = self.to_oklab();
+        let (l2, a2, b2) = other.to_oklab();
+        let (r, g, b) = oklab_to_rgb(lerp(l1, l2), lerp(a1, a2), lerp(b1, b2));
+        Self::from_rgba_f32(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), lerp(self.a, other.a))
+    }
+
+    /// Applies a gamma to each of red, green and blue: `c' = c^(1/gamma)`.
+    /// Above 1 brightens the midtones, below 1 darkens them; black and white
+    /// stay put.
+    pub fn with_gamma(&self, gamma: [f32; 3]) -> Color {
+        let apply = |c: f32, g: f32| c.max(0.0).powf(1.0 / g);
+        let Rgb(r, g, b) = self.rgb();
+        Self::from_rgba_f32(apply(r, gamma[0]), apply(g, gamma[1]), apply(b, gamma[2]), self.a)
+    }
+
+    /// Moves OKLCH lightness by `amount` (0..1 scale), like SCSS `lighten`
+    /// with a negative amount for `darken`, but perceptually even. Chroma is
+    /// reduced where needed to stay in sRGB.
+    pub fn lighten_oklch(&self, amount: f32) -> Color {
+        let (l, c, h) = self.to_oklch();
+        Self::from_oklcha_clamped((l + amount).clamp(0.0, 1.0), c, h, self.a, GamutClamp::Chroma)
+    }
+
+    /// Moves OKLCH chroma by `amount` of [`MAX_OKLCH_CHROMA`], like SCSS
+    /// `saturate`/`desaturate`. Greys keep the HSV hue they carry.
+    pub fn saturate_oklch(&self, amount: f32) -> Color {
+        let (l, c, h) = self.to_oklch();
+        let h = if c <= 0.002 { Self::from_hsva_f32(self.hue(), 1.0, 1.0, 1.0).to_oklch().2 } else { h };
+        let c = (c + amount * MAX_OKLCH_CHROMA).clamp(0.0, max_oklch_chroma(l, h));
+        let mut next = Self::from_oklcha_f32(l, c, h, self.a);
+        if c <= 0.002 {
+            next.set_hue(self.hue());
+        }
+        next
+    }
+
+    /// CIE76 color difference; values under ~2.3 are generally indistinguishable.
+    pub fn delta_e(&self, other: &Color) -> f32 {
+        let (l1, a1, b1) = self.to_lab();
+        let (l2, a2, b2) = other.to_lab();
+        ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+    }
+
+    pub fn pixel(&self) -> [u8; 4] {
+        let Rgb(r, g, b) = self.rgb();
+        [u(r), u(g), u(b), u(self.a)]
+    }
+    pub fn pixel16(&self) -> [u16; 4] {
+        let q = |x: f32| Depth::Sixteen.quantize(x) as u16;
+        let Rgb(r, g, b) = self.rgb();
+        [q(r), q(g), q(b), q(self.a)]
+    }
+
+    /// A channel as printed at `depth`: an integer, or a 0..1 float.
+    fn channel(&self, x: f32, depth: Depth) -> String {
+        match depth {
+            Depth::Float => trimmed(x, 6),
+            _ => format!("{}", depth.quantize(x)),
+        }
+    }
+    fn channels(&self, depth: Depth) -> [String; 3] {
+        let Rgb(r, g, b) = self.rgb();
+        [self.channel(r, depth), self.channel(g, depth), self.channel(b, depth)]
+    }
+    fn quantized_alpha(&self, depth: Depth) -> f32 {
+        depth.quantize(self.a) / depth.quantize(1.0)
+    }
+    fn opaque(&self, depth: Depth) -> bool {
+        feq(self.quantized_alpha(depth), 1.0)
+    }
+    /// Alpha as `alpha` asks, with `percent` its percentage.
+    fn alpha_string(&self, depth: Depth, alpha: AlphaRepr, percent: String) -> String {
+        match alpha {
+            AlphaRepr::Percent => format!("{}%", percent),
+            AlphaRepr::Number => depth.number(self.quantized_alpha(depth), 2),
+        }
+    }
+
+    pub fn to_hex_string(&self, short: HexShort) -> String {
+        let [r, g, b, a] = self.pixel();
+        let nibbles = |c: u8| c >> 4 == c & 0xf;
+        if short == HexShort::Auto && nibbles(r) && nibbles(g) && nibbles(b) && nibbles(a) {
+            if a == 255 {
+                format!("#{:x}{:x}{:x}", r & 0xf, g & 0xf, b & 0xf)
+            } else {
+                format!("#{:x}{:x}{:x}{:x}", r & 0xf, g & 0xf, b & 0xf, a & 0xf)
+            }
+        } else if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+    /// `#rrrrggggbbbb[aaaa]`, for depths beyond 8 bits.
+    pub fn to_hex16_string(&self) -> String {
+        let [r, g, b, a] = self.pixel16();
+        if a == 0xffff {
+            format!("#{:04x}{:04x}{:04x}", r, g, b)
+        } else {
+            format!("#{:04x}{:04x}{:04x}{:04x}", r, g, b, a)
+        }
+    }
+    pub fn to_rgb_string(&self, depth: Depth, alpha: AlphaRepr) -> String {
+        let [r, g, b] = self.channels(depth);
+        if self.opaque(depth) {
+            format!("rgb({}, {}, {})", r, g, b)
+        } else {
+            let a = self.alpha_string(depth, alpha, depth.number(self.quantized_alpha(depth) * 100.0, 0));
+            format!("rgba({}, {}, {}, {})", r, g, b, a)
+        }
+    }
+    /// CSS Color 4 percentage syntax, e.g. `rgb(100% 0% 50.2%)`, computed from
+    /// the quantized channels so it agrees with the other formats.
+    pub fn to_rgb_percent_string(&self, depth: Depth, alpha: AlphaRepr) -> String {
+        let n = |x: f32| trimmed(depth.quantize(x) / depth.quantize(1.0) * 100.0, depth.places(1));
+        let p = |x: f32| format!("{}%", n(x));
+        let Rgb(r, g, b) = self.rgb();
+        if self.opaque(depth) {
+            format!("rgb({} {} {})", p(r), p(g), p(b))
+        } else {
+            format!("rgb({} {} {} / {})", p(r), p(g), p(b), self.alpha_string(depth, alpha, n(self.a)))
+        }
+    }
+    /// Win32 COLORREF, `0x00BBGGRR`. It has no alpha, so as with the ANSI
+    /// formats a translucent color's is dropped; a top byte for it couldn't
+    /// tell transparent from a plain COLORREF's zero.
+    pub fn to_colorref_string(&self) -> String {
+        let [r, g, b, _] = self.pixel();
+        format!("0x00{:02X}{:02X}{:02X}", b, g, r)
+    }
+    /// OpenCV-style channel tuple, `(b, g, r)` or `(b, g, r, a)`, with 8-bit,
+    /// 16-bit or float channels to match `CV_8U`, `CV_16U` and `CV_32F`.
+    pub fn to_bgr_string(&self, depth: Depth) -> String {
+        let [r, g, b] = self.channels(depth);
+        if self.opaque(depth) {
+            format!("({}, {}, {})", b, g, r)
+        } else {
+            format!("({}, {}, {}, {})", b, g, r, self.channel(self.a, depth))
+        }
+    }
+
+    /// CSS Color 4 `color(srgb r g b)`, unclamped so HDR values above 1.0
+    /// survive.
+    pub fn to_srgb_string(&self) -> String {
+        let Rgb(r, g, b) = self.rgb();
+        let (r, g, b) = (trimmed(r, 4), trimmed(g, 4), trimmed(b, 4));
+        if self.opaque(Depth::Float) {
+            format!("color(srgb {} {} {})", r, g, b)
+        } else {
+            format!("color(srgb {} {} {} / {})", r, g, b, trimmed(self.a, 4))
+        }
+    }
+
+    /// Parses `color(srgb r g b [/ a])` with plain numbers, keeping values
+    /// outside 0..1.
+    pub fn from_srgb_str(s: &str) -> Option<Self> {
+        let inner = s.trim().strip_prefix("color(")?.strip_suffix(')')?.trim().strip_prefix("srgb")?;
+        let (channels, alpha) = match inner.find('/') {
+            Some(ix) => (&inner[..ix], Some(inner[ix + 1..].trim().parse::<f32>().ok()?)),
+            None => (inner, None),
+        };
+        let channels = channels.split_whitespace()
+            .map(|c| c.parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match channels[..] {
+            [r, g, b] => Some(Self::from_rgba_f32(r, g, b, alpha.unwrap_or(1.0).clamp(0.0, 1.0))),
+            _ => None,
+        }
+    }
+
+    /// Parses the output of [`Color::to_colorref_string`], as opaque. Any
+    /// other top byte isn't a COLORREF.
+    pub fn from_colorref_str(s: &str) -> Option<Self> {
+        let hex = s.trim().strip_prefix("0x").or_else(|| s.trim().strip_prefix("0X"))?;
+        if hex.len() != 8 {
+            return None;
+        }
+        let v = u32::from_str_radix(hex, 16).ok()?;
+        match v.to_be_bytes() {
+            [0, b, g, r] => Some(Self::from_rgba_f32(f(r), f(g), f(b), 1.0)),
+            _ => None,
+        }
+    }
+    /// Parses the output of [`Color::to_bgr_string`].
+    pub fn from_bgr_str(s: &str) -> Option<Self> {
+        let inner = s.trim().strip_prefix('(')?.strip_suffix(')')?;
+        let channels = inner.split(',')
+            .map(|c| c.trim().parse::<u8>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        match channels[..] {
+            [b, g, r] => Some(Self::from_rgba_f32(f(r), f(g), f(b), 1.0)),
+            [b, g, r, a] => Some(Self::from_rgba_f32(f(r), f(g), f(b), f(a))),
+            _ => None,
+        }
+    }
+
+    pub fn to_hsv_string(&self, depth: Depth, alpha: AlphaRepr) -> String {
+        let n = |x: f32| depth.number(x, 0);
+        let Hsv(h, s, v) = self.hsv();
+        let (h, s, v) = (n(h * 360.0), n(s * 100.0), n(v * 100.0));
+        let a = self.quantized_alpha(depth) * 100.0;
+        if self.opaque(depth) {
+            format!("hsv({}deg, {}%, {}%)", h, s, v)
+        } else {
+            format!("hsva({}deg, {}%, {}%, {})", h, s, v, self.alpha_string(depth, alpha, n(a)))
+        }
+    }
+
+    pub fn to_hsl_string(&self, depth: Depth, alpha: AlphaRepr) -> String {
+        let n = |x: f32| depth.number(x, 0);
+        let (h, s, l) = self.to_hsl();
+        let h = n(h * 360.0);
+        let s = n(s * 100.0);
+        let l = n(l * 100.0);
+        let a = self.quantized_alpha(depth) * 100.0;
+        if self.opaque(depth) {
+            format!("hsl({}deg, {}%, {}%)", h, s, l)
+        } else {
+            format!("hsla({}deg, {}%, {}%, {})", h, s, l, self.alpha_string(depth, alpha, n(a)))
+        }
+    }
+
+    pub fn to_vec_string(&self, depth: Depth) -> String {
+        let n = |x: f32| depth.number(x, 2);
+        let Rgb(r, g, b) = self.rgb();
+        let (r, g, b) = (n(r), n(g), n(b));
+        if self.opaque(depth) {
+            format!("vec3({}, {}, {})", r, g, b)
+        } else {
+            format!("vec4({}, {}, {}, {})", r, g, b, n(self.quantized_alpha(depth)))
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(|c: css_color::Rgba| Color::from_rgba_f32(c.red, c.green, c.blue, c.alpha))
+            .or_else(|e| {
+                Color::from_colorref_str(s)
+                    .or_else(|| Color::from_bgr_str(s))
+                    .or_else(|| Color::from_srgb_str(s))
+                    .ok_or_else(|| format!("{:?}", e))
+            })
+    }
+}
+
+/// Colors are the same when no channel differs by a 16-bit step, so float
+/// noise doesn't count as a change. HSV is compared as well as RGB, as the
+/// sliders show a hue and saturation that RGB loses, e.g. at black.
+#[cfg(feature = "druid")]
+impl Data for Color {
+    fn same(&self, other: &Self) -> bool {
+        // unclamped so HDR values above 1.0 still differ
+        let q = |c: &Color| {
+            let q = |x: f32| (x * 65535.0).round() as i32;
+            let (Rgb(r, g, b), Hsv(h, s, v)) = (c.rgb(), c.hsv());
+            [q(r), q(g), q(b), q(h), q(s), q(v), q(c.a)]
+        };
+        q(self) == q(other)
+    }
+}
+
+fn u(x: f32) -> u8 {
+    quantize(x, 255.0) as u8
+}
+
+fn f(x: u8) -> f32 {
+    x as f32 / 255.0
+}
+
+// Rounding: every integer channel, whether in a pixel, hex or a readout, goes
+// through `quantize`, and every decimal through `round_places`. Both round
+// halves away from zero, so a color prints the same in every format.
+
+/// A 0..1 channel as an integer step out of `max`, clamped, with NaN as 0.
+fn quantize(x: f32, max: f32) -> f32 {
+    if x.is_nan() {
+        return 0.0;
+    }
+    (x.clamp(0.0, 1.0) * max).round()
+}
+
+/// `x` rounded to `places` decimals. `format!` rounds halves to even, so
+/// numbers are rounded with this first. The scaling is exact in f64.
+fn round_places(x: f32, places: usize) -> f64 {
+    let scale = 10f64.powi(places as i32);
+    (x as f64 * scale).round() / scale
+}
+
+/// Formats `x` with at most `places` decimals, dropping trailing zeros.
+fn trimmed(x: f32, places: usize) -> String {
+    let s = format!("{:.*}", places, round_places(x, places));
+    let s = if s.contains('.') { s.trim_end_matches('0').trim_end_matches('.') } else { &s };
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+/// Relative tolerance of [`feq`]: well above the error a few conversions
+/// pile up, and below a 16-bit step.
+const TOLERANCE: f32 = 1e-5;
+
+/// Whether `x` and `y` are equal but for float error. The tolerance scales
+/// with their magnitude, and is absolute below 1, so an alpha of 0.999999
+/// counts as 1 and 99.9999% as 100%.
+fn feq(x: f32, y: f32) -> bool {
+    (x - y).abs() <= TOLERANCE * x.abs().max(y.abs()).max(1.0)
+}
+
+// https://en.wikipedia.org/wiki/HSL_and_HSV#HSV_to_RGB
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
+    let c = v * s;
+    let h = h * 6.0;
+    let x = c * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+    
+    let (r, g, b) = if h <= 1.0 {
+        (c, x, 0.0)
+    } else if h <= 2.0 {
+        (x, c, 0.0)
+    } else if h <= 3.0 {
+        (0.0, c, x)
+    } else if h <= 4.0 {
+        (0.0, x, c)
+    } else if h <= 5.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+    
+    let m = v - c;
+    (r+m, g+m, b+m)
+}
+
+// https://en.wikipedia.org/wiki/HSL_and_HSV#From_RGB
+fn rgb_to_hsv(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let v = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let c = v - min;
+
+    let h = if feq(c, 0.0) {
+        0.0
+    } else if feq(v, r) {
+        (g - b) / c
+    } else if feq(v, g) {
+        (b - r) / c + 2.0
+    } else {
+        (r - g) / c + 4.0
+    };
+    // h is in sextants (-1..6); the rest of the code works in turns
+    let h = (h / 6.0).rem_euclid(1.0);
+
+    let s = if feq(v, 0.0) {
+        0.0
+    } else {
+        c / v
+    };
+
+    (h, s, v)
+}
+
+// https://en.wikipedia.org/wiki/HSL_and_HSV#Interconversion
+fn hsv_to_hsl(h: f32, s: f32, v: f32) -> (f32, f32, f32) {
+    let l = v * (1.0 - s/2.0);
+    let s = if feq(l, 0.0) || feq(l, 1.0) {
+        0.0
+    } else {
+        (v - l) / l.min(1.0 - l)
+    };
+    (h, s, l)
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// https://bottosson.github.io/posts/oklab/
+fn rgb_to_oklab(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+    (
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    )
+}
+
+pub fn oklch_in_gamut(l: f32, c: f32, h: f32) -> bool {
+    let (r, g, b) = oklch_to_rgb(l, c, h);
+    rgb_in_gamut(r, g, b)
+}
+
+pub fn rgb_in_gamut(r: f32, g: f32, b: f32) -> bool {
+    let ok = |x: f32| (-1e-4..=1.0 + 1e-4).contains(&x);
+    ok(r) && ok(g) && ok(b)
+}
+
+/// The most chroma sRGB can show at this OKLCH lightness and hue.
+pub fn max_oklch_chroma(l: f32, h: f32) -> f32 {
+    let (mut lo, mut hi) = (0.0, MAX_OKLCH_CHROMA);
+    if oklch_in_gamut(l, hi, h) {
+        return hi;
+    }
+    for _ in 0..16 {
+        let mid = (lo + hi) / 2.0;
+        if oklch_in_gamut(l, mid, h) { lo = mid } else { hi = mid }
+    }
+    lo
+}
+
+/// Unclamped sRGB channels of an OKLCH color.
+pub fn oklch_to_rgb(l: f32, c: f32, h: f32) -> (f32, f32, f32) {
+    oklab_to_rgb(l, c * (h * std::f32::consts::TAU).cos(), c * (h * std::f32::consts::TAU).sin())
+}
+
+fn oklab_to_rgb(l: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    (
+        linear_to_srgb(4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_),
+        linear_to_srgb(-1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_),
+        linear_to_srgb(-0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_),
+    )
+}
+
+// https://en.wikipedia.org/wiki/CIELAB_color_space#From_CIEXYZ_to_CIELAB
+fn rgb_to_lab(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let (x, y, z) = rgb_to_xyz(r, g, b);
+
+    let f = |t: f32| {
+        let d = 6.0 / 29.0;
+        if t > d * d * d {
+            t.cbrt()
+        } else {
+            t / (3.0 * d * d) + 4.0 / 29.0
+        }
+    };
+    let (fx, fy, fz) = (f(x / 0.950_47), f(y), f(z / 1.088_83));
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+fn lab_to_rgb(l: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    let (x, y, z) = lab_to_xyz(l, a, b, WhitePoint::D65.xyz());
+    xyz_to_rgb(x, y, z)
+}
+
+fn lab_to_xyz(l: f32, a: f32, b: f32, (wx, wy, wz): (f32, f32, f32)) -> (f32, f32, f32) {
+    let finv = |t: f32| {
+        let d = 6.0 / 29.0;
+        if t > d {
+            t * t * t
+        } else {
+            3.0 * d * d * (t - 4.0 / 29.0)
+        }
+    };
+    let fy = (l + 16.0) / 116.0;
+    (finv(fy + a / 500.0) * wx, finv(fy) * wy, finv(fy - b / 200.0) * wz)
+}
+
+fn rgb_to_xyz(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+    (
+        0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b,
+        0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b,
+        0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b,
+    )
+}
+
+fn xyz_to_rgb(x: f32, y: f32, z: f32) -> (f32, f32, f32) {
+    let r = 3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z;
+    let g = -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z;
+    let b = 0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z;
+    (linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+}
+
+// http://www.brucelindbloom.com/index.html?Eqn_ChromAdapt.html
+fn bradford(xyz: (f32, f32, f32), from: (f32, f32, f32), to: (f32, f32, f32)) -> (f32, f32, f32) {
+    let cone = |(x, y, z): (f32, f32, f32)| (
+        0.895_1 * x + 0.266_4 * y - 0.161_4 * z,
+        -0.750_2 * x + 1.713_5 * y + 0.036_7 * z,
+        0.038_9 * x - 0.068_5 * y + 1.029_6 * z,
+    );
+    let ((l, m, s), (l1, m1, s1), (l2, m2, s2)) = (cone(xyz), cone(from), cone(to));
+    let (l, m, s) = (l * l2 / l1, m * m2 / m1, s * s2 / s1);
+    (
+        0.986_993 * l - 0.147_054 * m + 0.159_963 * s,
+        0.432_305 * l + 0.518_360 * m + 0.049_291 * s,
+        -0.008_529 * l + 0.040_043 * m + 0.968_487 * s,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn hsv_to_rgb() {
+        assert_eq!(super::hsv_to_rgb(30.0/360.0, 1.0, 1.0), (1.0, 0.5, 0.0));
+        assert_eq!(super::hsv_to_rgb(60.0/360.0, 0.5, 0.75), (0.75, 0.75, 0.375));
+    }
+
+    #[test]
+    fn rgb_to_hsv() {
+        assert_eq!(super::rgb_to_hsv(1.0, 0.5, 0.0), (30.0/360.0, 1.0, 1.0));
+        assert_eq!(super::rgb_to_hsv(0.2, 0.4, 1.0), (225.0/360.0, 0.8, 1.0));
+        assert_eq!(super::rgb_to_hsv(1.0, 0.0, 0.5), (330.0/360.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn rounding() {
+        use super::{AlphaRepr, Color, Depth, HexShort};
+        for k in 0..=255u8 {
+            let c = Color::from_rgba_f32(k as f32 / 255.0, 0.0, 0.0, 1.0);
+            assert_eq!(c.pixel()[0], k);
+            assert_eq!(c.pixel16()[0], k as u16 * 257);
+            assert_eq!(c.to_hex_string(HexShort::Never), format!("#{:02x}0000", k));
+            assert_eq!(c.to_rgb_string(Depth::Eight, AlphaRepr::Percent), format!("rgb({}, 0, 0)", k));
+            // percentages read back as the same step
+            let percent = c.to_rgb_percent_string(Depth::Eight, AlphaRepr::Percent);
+            assert_eq!(percent.parse::<Color>().unwrap().pixel()[0], k, "{}", percent);
+
+            // halfway to the next step, every format takes the same side
+            let half = Color::from_rgba_f32((k as f32 + 0.5) / 255.0, 0.0, 0.0, 1.0);
+            let [r, ..] = half.pixel();
+            assert_eq!(half.to_hex_string(HexShort::Never), format!("#{:02x}0000", r));
+            assert_eq!(half.to_rgb_string(Depth::Eight, AlphaRepr::Percent), format!("rgb({}, 0, 0)", r));
+            assert_eq!(half.to_rgb_percent_string(Depth::Eight, AlphaRepr::Percent), Color::from_rgba_f32(r as f32 / 255.0, 0.0, 0.0, 1.0).to_rgb_percent_string(Depth::Eight, AlphaRepr::Percent));
+        }
+        // out of range and NaN clamp
+        assert_eq!(Color::from_rgba_f32(1.5, -0.5, f32::NAN, 1.0).pixel(), [255, 0, 0, 255]);
+        // decimal halves round up, not to even
+        assert_eq!(Color::from_hsva_f32(0.0, 0.125, 1.0, 1.0).to_hsv_string(Depth::Eight, AlphaRepr::Percent), "hsv(0deg, 13%, 100%)");
+        assert_eq!(super::trimmed(0.125, 2), "0.13");
+    }
+
+    #[test]
+    fn near_opaque() {
+        use super::{feq, AlphaRepr, Color, Depth};
+        assert!(feq(0.999999, 1.0) && feq(99.9999, 100.0) && feq(0.0, 1e-7));
+        assert!(!feq(65534.0 / 65535.0, 1.0) && !feq(0.5, 0.5001));
+
+        let c = Color::from_rgba_f32(0.2, 0.4, 0.6, 0.999999);
+        assert_eq!(c.to_hsl_string(Depth::Eight, AlphaRepr::Percent), "hsl(210deg, 50%, 40%)");
+        assert_eq!(c.to_hsv_string(Depth::Float, AlphaRepr::Percent), "hsv(210deg, 66.6667%, 60%)");
+        assert_eq!(c.to_vec_string(Depth::Eight), "vec3(0.20, 0.40, 0.60)");
+        assert_eq!(c.to_srgb_string(), "color(srgb 0.2 0.4 0.6)");
+
+        // opaque once quantized, in every format alike
+        let c = Color::from_rgba_f32(0.2, 0.4, 0.6, 0.999);
+        assert_eq!(c.to_rgb_string(Depth::Eight, AlphaRepr::Percent), "rgb(51, 102, 153)");
+        assert_eq!(c.to_hsl_string(Depth::Eight, AlphaRepr::Percent), "hsl(210deg, 50%, 40%)");
+        assert_eq!(c.to_vec_string(Depth::Eight), "vec3(0.20, 0.40, 0.60)");
+        assert_eq!(c.to_hsl_string(Depth::Float, AlphaRepr::Percent), "hsla(210deg, 50%, 40%, 99.9%)");
+        assert!(!Color::from_rgba_f32(1.000001, 0.0, 0.0, 1.0).is_clipped());
+    }
+
+    #[cfg(feature = "druid")]
+    #[test]
+    fn same() {
+        use super::Color;
+        use druid::Data;
+        let c = Color::from_rgba_f32(0.2, 0.4, 0.6, 1.0);
+        assert!(c.same(&Color::from_rgba_f32(0.2 + 1e-6, 0.4, 0.6, 1.0)));
+        assert!(!c.same(&Color::from_rgba_f32(0.21, 0.4, 0.6, 1.0)));
+        // black at another hue still moves the hue slider
+        assert!(!Color::from_hsva_f32(0.1, 1.0, 0.0, 1.0).same(&Color::from_hsva_f32(0.6, 1.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn round_trips_dont_drift() {
+        use super::Color;
+        let bits = |c: &Color| [c.red(), c.green(), c.blue(), c.hue(), c.saturation(), c.value()].map(f32::to_bits);
+
+        // HSV edits never go back through RGB, however often it's read
+        let mut c = Color::from_hsva_f32(0.3, 0.7, 0.9, 1.0);
+        for ix in 0..10_000 {
+            c.set_hue(if ix % 2 == 0 { 0.61 } else { 0.3 });
+            c.set_saturation(0.7);
+            c.pixel();
+        }
+        assert_eq!(bits(&c), bits(&Color::from_hsva_f32(0.3, 0.7, 0.9, 1.0)));
+
+        // nor do colors given in RGB, until they're edited in HSV
+        let c = Color::from_rgba_f32(0.2, 0.4, 0.6, 1.0);
+        c.hue();
+        assert_eq!([c.red(), c.green(), c.blue()], [0.2, 0.4, 0.6]);
+
+        // once converted, a back-and-forth lands where the first pass did
+        let mut once = Color::from_rgba_f32(0.2, 0.4, 0.6, 1.0);
+        once.set_hue(0.9);
+        once.set_hue(210.0 / 360.0);
+        let mut many = once.clone();
+        for _ in 0..10_000 {
+            many.set_hue(0.9);
+            many.set_hue(210.0 / 360.0);
+        }
+        assert_eq!(bits(&many), bits(&once));
+        assert_eq!(once.pixel16(), Color::from_rgba_f32(0.2, 0.4, 0.6, 1.0).pixel16());
+    }
+
+    #[test]
+    fn set_channels() {
+        use super::Color;
+        let mut c = Color::from_rgba_f32(0.2, 0.4, 0.6, 0.5);
+        c.set_red_u8(153);
+        assert_eq!(c.pixel(), [153, 102, 153, 128]);
+        assert_eq!((c.hue() * 360.0).round(), 300.0);
+        assert!((c.saturation() - 1.0 / 3.0).abs() < 1e-6);
+
+        // evening the channels out to a grey keeps the hue
+        let mut c = Color::from_rgba_f32(0.8, 0.8, 0.4, 1.0);
+        c.set_blue(0.8);
+        assert_eq!(c.hue(), 1.0 / 6.0);
+        assert_eq!((c.saturation(), c.value()), (0.0, 0.8));
+        c.set_blue_u8(255);
+        assert_eq!(c.pixel(), [204, 204, 255, 255]);
+    }
+
+    #[test]
+    fn adapt() {
+        use super::{Color, WhitePoint};
+        let sky = Color::from_rgba_f32(0.4, 0.6, 0.9, 0.5);
+        assert_eq!(sky.adapt(WhitePoint::D65).pixel(), sky.pixel());
+        // white takes on the new white's tint
+        let white = Color::from_rgba_f32(1.0, 1.0, 1.0, 1.0);
+        let [r, _, b, _] = white.adapt(WhitePoint::Tungsten).pixel();
+        assert!(r == 255 && b < 150);
+        let [r, _, b, _] = white.adapt(WhitePoint::D50).pixel();
+        assert!(r == 255 && b < 255 && b > 200);
+        assert_eq!(sky.adapt(WhitePoint::Tungsten).alpha(), 0.5);
+
+        // D50 white is white once adapted, where taken as D65 it's warm
+        assert_eq!(Color::from_laba_white_f32(100.0, 0.0, 0.0, 1.0, WhitePoint::D50).pixel(), [255; 4]);
+        assert_eq!(Color::from_laba_white_f32(50.0, 20.0, -30.0, 1.0, WhitePoint::D65).pixel(), Color::from_laba_f32(50.0, 20.0, -30.0, 1.0).pixel());
+    }
+
+    #[test]
+    fn rgb_to_lab() {
+        let close = |(l, a, b): (f32, f32, f32), (el, ea, eb): (f32, f32, f32)| {
+            (l - el).abs() < 0.05 && (a - ea).abs() < 0.05 && (b - eb).abs() < 0.05
+        };
+        assert!(close(super::rgb_to_lab(1.0, 1.0, 1.0), (100.0, 0.0, 0.0)));
+        assert!(close(super::rgb_to_lab(0.0, 0.0, 0.0), (0.0, 0.0, 0.0)));
+        assert!(close(super::rgb_to_lab(1.0, 0.0, 0.0), (53.24, 80.09, 67.20)));
+        let (r, g, b) = super::lab_to_rgb(53.24, 80.09, 67.20);
+        assert!((r - 1.0).abs() < 0.001 && g.abs() < 0.001 && b.abs() < 0.001);
+    }
+
+    #[test]
+    fn luminance_and_oklab() {
+        use super::Color;
+        let close = |x: f32, y: f32| (x - y).abs() < 0.001;
+        assert!(close(Color::from_rgba_f32(1.0, 1.0, 1.0, 1.0).relative_luminance(), 1.0));
+        assert!(close(Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0).relative_luminance(), 0.2126));
+        assert!(close(Color::from_rgba_f32(0.5, 0.5, 0.5, 1.0).relative_luminance(), 0.2140));
+
+        let (l, a, b) = Color::from_rgba_f32(1.0, 1.0, 1.0, 1.0).to_oklab();
+        assert!(close(l, 1.0) && close(a, 0.0) && close(b, 0.0));
+        let (l, a, b) = Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0).to_oklab();
+        assert!(close(l, 0.6280) && close(a, 0.2249) && close(b, 0.1258));
+
+        let c = Color::from_rgba_f32(0.2, 0.4, 1.0, 0.5);
+        let (l, ch, h) = c.to_oklch();
+        let back = Color::from_oklcha_f32(l, ch, h, c.alpha());
+        assert_eq!(back.pixel(), c.pixel());
+    }
+
+    #[test]
+    fn gamut_clamp() {
+        use super::{Color, GamutClamp, max_oklch_chroma, oklch_in_gamut};
+        // sRGB red sits right on the boundary
+        let (l, c, h) = Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0).to_oklch();
+        assert!((max_oklch_chroma(l, h) - c).abs() < 0.001);
+        assert!(!oklch_in_gamut(l, c + 0.01, h));
+        assert!(max_oklch_chroma(1.0, h) < 0.001);
+
+        let chroma = Color::from_oklcha_clamped(l, 0.4, h, 1.0, GamutClamp::Chroma);
+        assert_eq!(chroma.pixel(), [255, 0, 0, 255]);
+        let (cl, _, ch) = Color::from_oklcha_clamped(0.5, 0.4, 0.8, 1.0, GamutClamp::Chroma).to_oklch();
+        assert!((cl - 0.5).abs() < 0.001 && (ch - 0.8).abs() < 0.001);
+    }
+
+    #[test]
+    fn to_hex_string() {
+        use super::{Color, HexShort};
+        let c = |r: u8, g: u8, b: u8, a: u8| Color::from_rgba_f32(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0);
+
+        assert_eq!(c(0xff, 0xcc, 0x00, 0xff).to_hex_string(HexShort::Auto), "#fc0");
+        assert_eq!(c(0xff, 0xcc, 0x00, 0xff).to_hex_string(HexShort::Never), "#ffcc00");
+        assert_eq!(c(0xff, 0xcc, 0x00, 0x88).to_hex_string(HexShort::Auto), "#fc08");
+        assert_eq!(c(0xff, 0xcc, 0x00, 0x80).to_hex_string(HexShort::Auto), "#ffcc0080");
+        assert_eq!(c(0xff, 0xcd, 0x00, 0xff).to_hex_string(HexShort::Auto), "#ffcd00");
+    }
+
+    #[test]
+    fn to_rgb_percent_string() {
+        use super::{AlphaRepr, Color, Depth};
+        let c = |r: u8, g: u8, b: u8, a: u8| Color::from_rgba_f32(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0);
+
+        assert_eq!(c(255, 0, 128, 255).to_rgb_percent_string(Depth::Eight, AlphaRepr::Percent), "rgb(100% 0% 50.2%)");
+        assert_eq!(c(255, 0, 128, 128).to_rgb_percent_string(Depth::Eight, AlphaRepr::Percent), "rgb(100% 0% 50.2% / 50.2%)");
+        assert_eq!(c(51, 102, 153, 0).to_rgb_percent_string(Depth::Eight, AlphaRepr::Percent), "rgb(20% 40% 60% / 0%)");
+        assert_eq!(c(255, 0, 128, 128).to_rgb_percent_string(Depth::Eight, AlphaRepr::Number), "rgb(100% 0% 50.2% / 0.50)");
+        assert_eq!(c(255, 0, 128, 128).to_rgb_string(Depth::Eight, AlphaRepr::Number), "rgba(255, 0, 128, 0.50)");
+        assert_eq!(c(255, 0, 128, 128).to_hsl_string(Depth::Float, AlphaRepr::Number), "hsla(329.8824deg, 100%, 50%, 0.501961)");
+    }
+
+    #[test]
+    fn depth() {
+        use super::{AlphaRepr, Color, Depth};
+        let c = Color::from_rgba_f32(1.0, 0.0, 0.3, 1.0);
+        let translucent = Color::from_rgba_f32(1.0, 0.0, 0.3, 0.5);
+
+        assert_eq!(c.to_rgb_string(Depth::Eight, AlphaRepr::Percent), "rgb(255, 0, 77)");
+        assert_eq!(c.to_rgb_string(Depth::Sixteen, AlphaRepr::Percent), "rgb(65535, 0, 19661)");
+        assert_eq!(c.to_rgb_string(Depth::Float, AlphaRepr::Percent), "rgb(1, 0, 0.3)");
+        assert_eq!(translucent.to_rgb_string(Depth::Eight, AlphaRepr::Percent), "rgba(255, 0, 77, 50%)");
+        assert_eq!(translucent.to_rgb_string(Depth::Sixteen, AlphaRepr::Percent), "rgba(65535, 0, 19661, 50.001%)");
+        assert_eq!(c.to_hex16_string(), "#ffff00004ccd");
+        assert_eq!(translucent.to_hex16_string(), "#ffff00004ccd8000");
+        assert_eq!(c.to_rgb_percent_string(Depth::Sixteen, AlphaRepr::Percent), "rgb(100% 0% 30.0008%)");
+        assert_eq!(c.to_vec_string(Depth::Eight), "vec3(1.00, 0.00, 0.30)");
+        assert_eq!(c.to_vec_string(Depth::Float), "vec3(1, 0, 0.3)");
+        assert_eq!(c.to_hsv_string(Depth::Eight, AlphaRepr::Percent), "hsv(342deg, 100%, 100%)");
+        assert_eq!(c.to_hsv_string(Depth::Sixteen, AlphaRepr::Percent), "hsv(342deg, 100%, 100%)");
+        assert_eq!(translucent.to_bgr_string(Depth::Float), "(0.3, 0, 1, 0.5)");
+    }
+
+    #[test]
+    fn bgr_round_trip() {
+        use super::{Color, Depth};
+        let opaque = Color::from_rgba_f32(0x11 as f32 / 255.0, 0x22 as f32 / 255.0, 0x33 as f32 / 255.0, 1.0);
+        let translucent = Color::from_rgba_f32(0x11 as f32 / 255.0, 0x22 as f32 / 255.0, 0x33 as f32 / 255.0, 0x80 as f32 / 255.0);
+
+        assert_eq!(opaque.to_colorref_string(), "0x00332211");
+        // COLORREF has no alpha
+        assert_eq!(translucent.to_colorref_string(), "0x00332211");
+        assert_eq!(Color::from_rgba_f32(0.0, 0.0, 0.0, 0.0).to_colorref_string(), "0x00000000");
+        assert_eq!(opaque.to_bgr_string(Depth::Eight), "(51, 34, 17)");
+        assert_eq!(translucent.to_bgr_string(Depth::Eight), "(51, 34, 17, 128)");
+
+        assert_eq!(Color::from_colorref_str(&opaque.to_colorref_string()).unwrap().pixel(), opaque.pixel());
+        for c in &[opaque, translucent] {
+            assert_eq!(Color::from_bgr_str(&c.to_bgr_string(Depth::Eight)).unwrap().pixel(), c.pixel());
+            assert_eq!(c.to_bgr_string(Depth::Eight).parse::<Color>().unwrap().pixel(), c.pixel());
+        }
+        assert!(Color::from_bgr_str("(1, 2)").is_none());
+        assert!(Color::from_bgr_str("(1, 2, 300)").is_none());
+        assert!(Color::from_colorref_str("0x332211").is_none());
+        assert!(Color::from_colorref_str("0x80332211").is_none());
+    }
+
+    #[test]
+    fn contrast() {
+        use super::Color;
+        let (white, black) = (Color::from_rgba_f32(1.0, 1.0, 1.0, 1.0), Color::from_rgba_f32(0.0, 0.0, 0.0, 1.0));
+        assert!((white.contrast_ratio(&black) - 21.0).abs() < 0.001);
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 0.001);
+
+        let blue = Color::from_rgba_f32(0.4, 0.6, 1.0, 1.0);
+        let on_white = blue.with_contrast(&white, 4.5);
+        assert!(on_white.contrast_ratio(&white) >= 4.5);
+        assert!(on_white.contrast_ratio(&white) < 4.6);
+        assert!(on_white.relative_luminance() < blue.relative_luminance());
+        let on_black = blue.with_contrast(&black, 4.5);
+        assert_eq!(on_black.pixel(), blue.pixel());
+        assert_eq!(blue.with_contrast(&white, 30.0).pixel(), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn mix() {
+        use super::Color;
+        let red = Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0);
+        let blue = Color::from_rgba_f32(0.0, 0.0, 1.0, 0.0);
+        assert_eq!(red.mix(&blue, 0.0).pixel(), red.pixel());
+        assert_eq!(red.mix(&blue, 1.0).pixel(), blue.pixel());
+        let mid = red.mix(&blue, 0.5);
+        assert_eq!(mid.alpha(), 0.5);
+        let (l, _, _) = mid.to_oklab();
+        let (l1, _, _) = red.to_oklab();
+        let (l2, _, _) = blue.to_oklab();
+        assert!((l - (l1 + l2) / 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn gamma() {
+        use super::Color;
+        let grey = Color::from_rgba_f32(0.5, 0.5, 0.5, 0.25);
+        assert_eq!(grey.with_gamma([1.0; 3]).pixel(), grey.pixel());
+        let c = grey.with_gamma([2.2, 1.0, 1.0 / 2.2]);
+        assert!((c.red() - 0.5f32.powf(1.0 / 2.2)).abs() < 1e-6);
+        assert_eq!(c.green(), 0.5);
+        assert!((c.blue() - 0.5f32.powf(2.2)).abs() < 1e-6);
+        assert_eq!(c.alpha(), 0.25);
+        let white = Color::from_rgba_f32(1.0, 1.0, 1.0, 1.0);
+        assert_eq!(white.with_gamma([2.2; 3]).pixel(), white.pixel());
+    }
+
+    #[test]
+    fn oklch_steps() {
+        use super::Color;
+        let close = |x: f32, y: f32| (x - y).abs() < 0.005;
+        let teal = Color::from_rgba_f32(0.2, 0.5, 0.5, 1.0);
+        let (l, c, h) = teal.to_oklch();
+
+        let (l2, c2, h2) = teal.lighten_oklch(0.05).to_oklch();
+        assert!(close(l2, l + 0.05) && close(c2, c) && close(h2, h));
+        let (l2, _, _) = teal.lighten_oklch(-0.05).to_oklch();
+        assert!(close(l2, l - 0.05));
+        assert_eq!(teal.lighten_oklch(2.0).pixel(), [255, 255, 255, 255]);
+
+        let (l2, c2, h2) = teal.saturate_oklch(0.05).to_oklch();
+        assert!(close(l2, l) && close(c2, c + 0.02) && close(h2, h));
+        let grey = teal.saturate_oklch(-1.0);
+        assert!(grey.to_oklch().1 < 0.002);
+        assert_eq!(grey.hue(), teal.hue());
+        let (_, c3, h3) = grey.saturate_oklch(0.05).to_oklch();
+        assert!(close(c3, 0.02) && (h3 - h).abs() < 0.05);
+    }
+
+    #[test]
+    fn srgb_extended_range() {
+        use super::Color;
+        let hdr = Color::from_srgb_str("color(srgb 1.2 0.3 0.1)").unwrap();
+        assert!(hdr.is_clipped());
+        assert_eq!(hdr.pixel(), [255, 77, 26, 255]);
+        assert_eq!(hdr.to_srgb_string(), "color(srgb 1.2 0.3 0.1)");
+        assert_eq!(hdr.value(), 1.2);
+
+        let sdr = Color::from_srgb_str("color(srgb 0 0.5 1 / 0.25)").unwrap();
+        assert!(!sdr.is_clipped());
+        assert_eq!(sdr.to_srgb_string(), "color(srgb 0 0.5 1 / 0.25)");
+        assert!(Color::from_srgb_str("color(display-p3 1 0 0)").is_none());
+        assert!(Color::from_srgb_str("color(srgb 1 0)").is_none());
+    }
+}
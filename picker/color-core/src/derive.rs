@@ -0,0 +1,122 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::color::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeriveOp {
+    Lighten,
+    Darken,
+    Saturate,
+    Desaturate,
+    /// Hue rotation in degrees.
+    Spin,
+    /// Sets the alpha.
+    Alpha,
+}
+impl FromStr for DeriveOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lighten" => Ok(DeriveOp::Lighten),
+            "darken" => Ok(DeriveOp::Darken),
+            "saturate" => Ok(DeriveOp::Saturate),
+            "desaturate" => Ok(DeriveOp::Desaturate),
+            "spin" => Ok(DeriveOp::Spin),
+            "alpha" => Ok(DeriveOp::Alpha),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for DeriveOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Lighten => "lighten",
+            Self::Darken => "darken",
+            Self::Saturate => "saturate",
+            Self::Desaturate => "desaturate",
+            Self::Spin => "spin",
+            Self::Alpha => "alpha",
+        })
+    }
+}
+
+/// A color derived from the one being edited, like Sass's `lighten($c, 10%)`,
+/// parsed from `op:amount`, e.g. `lighten:10%`, `spin:-30` or `alpha:0.5`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Derivation {
+    pub op: DeriveOp,
+    /// Percent for lighten/darken/saturate/desaturate, degrees for spin and
+    /// 0..1 for alpha.
+    pub amount: f32,
+}
+impl FromStr for Derivation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.splitn(2, ':');
+        let op = split.next().unwrap_or("").parse()?;
+        let amount = split.next().ok_or_else(|| format!("Missing amount, expected {}:<amount>", op))?;
+        let amount = amount.trim_end_matches('%').parse::<f32>().map_err(|_| format!("Invalid amount: {}", amount))?;
+        Ok(Derivation { op, amount })
+    }
+}
+impl Display for Derivation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.op {
+            DeriveOp::Spin | DeriveOp::Alpha => write!(f, "{} {}", self.op, self.amount),
+            _ => write!(f, "{} {}%", self.op, self.amount),
+        }
+    }
+}
+
+impl Derivation {
+    pub fn apply(&self, color: &Color) -> Color {
+        let (h, s, l) = color.to_hsl();
+        let a = color.alpha();
+        let d = self.amount / 100.0;
+        match self.op {
+            DeriveOp::Lighten => from_hsla(h, s, (l + d).clamp(0.0, 1.0), a),
+            DeriveOp::Darken => from_hsla(h, s, (l - d).clamp(0.0, 1.0), a),
+            DeriveOp::Saturate => from_hsla(h, (s + d).clamp(0.0, 1.0), l, a),
+            DeriveOp::Desaturate => from_hsla(h, (s - d).clamp(0.0, 1.0), l, a),
+            DeriveOp::Spin => from_hsla((h + self.amount / 360.0).rem_euclid(1.0), s, l, a),
+            DeriveOp::Alpha => from_hsla(h, s, l, self.amount.clamp(0.0, 1.0)),
+        }
+    }
+}
+
+// https://en.wikipedia.org/wiki/HSL_and_HSV#HSL_to_HSV
+fn from_hsla(h: f32, s: f32, l: f32, a: f32) -> Color {
+    let v = l + s * l.min(1.0 - l);
+    let s = if v <= 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+    Color::from_hsva_f32(h, s, v, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Derivation, DeriveOp};
+    use crate::color::{Color, HexShort};
+
+    #[test]
+    fn parse() {
+        assert_eq!("lighten:10%".parse(), Ok(Derivation { op: DeriveOp::Lighten, amount: 10.0 }));
+        assert_eq!("spin:-30".parse(), Ok(Derivation { op: DeriveOp::Spin, amount: -30.0 }));
+        assert!("lighten".parse::<Derivation>().is_err());
+        assert!("blur:3".parse::<Derivation>().is_err());
+        assert!("darken:x".parse::<Derivation>().is_err());
+        assert_eq!("darken:5%".parse::<Derivation>().unwrap().to_string(), "darken 5%");
+    }
+
+    #[test]
+    fn apply() {
+        // hsl(210deg, 50%, 40%)
+        let base = Color::from_rgba_f32(0.2, 0.4, 0.6, 1.0);
+        let hex = |d: &str| d.parse::<Derivation>().unwrap().apply(&base).to_hex_string(HexShort::Never);
+        assert_eq!(hex("lighten:10%"), "#4080bf");
+        assert_eq!(hex("darken:40%"), "#000000");
+        assert_eq!(hex("desaturate:50%"), "#666666");
+        assert_eq!(hex("spin:180"), "#996633");
+        assert_eq!(hex("alpha:0.5"), "#33669980");
+    }
+}
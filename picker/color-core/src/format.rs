@@ -0,0 +1,266 @@
+use std::{str::FromStr, sync::Arc};
+
+#[cfg(feature = "druid")]
+use druid::Data;
+use log::debug;
+
+use crate::{ansi, names};
+use crate::color::{AlphaRepr, Color, Depth, HexShort};
+use crate::relative::Relative;
+use crate::snap::SnapSet;
+use crate::template::Template;
+use crate::vars::VarSyntax;
+
+/// An output format; each has a button in the picker. Built-ins are
+/// variants, user formats are looked up by label in
+/// [`FormatOptions::user_formats`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "druid", derive(Data))]
+pub enum Format {
+    Rgb,
+    Hex,
+    Hsl,
+    Hsv,
+    Vec,
+    Colorref,
+    Bgr,
+    Srgb,
+    Ansi256,
+    Truecolor,
+    Var(VarSyntax),
+    Relative,
+    /// A user format, by label.
+    User(Arc<str>),
+}
+impl Format {
+    pub fn format(&self, color: &Color, options: &FormatOptions) -> String {
+        let color = &options.snapped(color);
+        match self {
+            Self::Rgb if options.rgb_percent => color.to_rgb_percent_string(options.depth, options.alpha),
+            Self::Rgb => color.to_rgb_string(options.depth, options.alpha),
+            Self::Hex if options.depth != Depth::Eight => color.to_hex16_string(),
+            Self::Hex => color.to_hex_string(options.hex_short),
+            Self::Hsl => color.to_hsl_string(options.depth, options.alpha),
+            Self::Hsv => color.to_hsv_string(options.depth, options.alpha),
+            Self::Vec => color.to_vec_string(options.depth),
+            Self::Colorref => color.to_colorref_string(),
+            Self::Bgr => color.to_bgr_string(options.depth),
+            Self::Srgb => color.to_srgb_string(),
+            Self::Ansi256 => ansi::nearest_256(color).to_string(),
+            Self::Truecolor => ansi::truecolor_escape(color),
+            Self::Var(syntax) => syntax.declare(&options.var_name(color), &color.to_hex_string(options.hex_short)),
+            Self::Relative => match &options.relative {
+                Some(relative) => relative.express(color),
+                None => color.to_hex_string(options.hex_short),
+            },
+            Self::User(label) => match options.user_format(label) {
+                Some(user) => user.template.render(color),
+                None => color.to_hex_string(options.hex_short),
+            },
+        }
+    }
+
+    /// Whether the output is always 8 bits per channel, whatever the depth.
+    pub fn eight_bit_only(&self) -> bool {
+        matches!(self, Self::Colorref | Self::Srgb | Self::Ansi256 | Self::Truecolor)
+    }
+    /// The formats to offer with these options: the built-ins they allow,
+    /// then the user formats.
+    pub fn values(options: &FormatOptions) -> Vec<Format> {
+        let mut values = vec![
+            Self::Rgb, Self::Hex, Self::Hsl, Self::Hsv, Self::Vec, Self::Colorref, Self::Bgr, Self::Ansi256, Self::Truecolor,
+        ];
+        if options.hdr {
+            values.push(Self::Srgb);
+        }
+        if options.var_syntax != VarSyntax::Css {
+            values.push(Self::Var(options.var_syntax));
+        }
+        if options.relative.is_some() {
+            values.push(Self::Relative);
+        }
+        values.extend(options.user_formats.iter().map(|user| Self::User(user.label.as_str().into())));
+        values
+    }
+
+    /// Parses a label as [`FromStr`] does, in any case, also knowing the user
+    /// formats.
+    pub fn named(s: &str, options: &FormatOptions) -> Option<Format> {
+        s.to_lowercase().parse().ok().or_else(|| {
+            options.user_formats.iter()
+                .find(|user| user.label.eq_ignore_ascii_case(s))
+                .map(|user| Self::User(user.label.as_str().into()))
+        })
+    }
+}
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rgb => write!(f, "RGB"),
+            Self::Hex => write!(f, "HEX"),
+            Self::Hsl => write!(f, "HSL"),
+            Self::Hsv => write!(f, "HSV"),
+            Self::Vec => write!(f, "VEC"),
+            Self::Colorref => write!(f, "REF"),
+            Self::Bgr => write!(f, "BGR"),
+            Self::Srgb => write!(f, "SRGB"),
+            Self::Ansi256 => write!(f, "256"),
+            Self::Truecolor => write!(f, "ESC"),
+            Self::Var(syntax) => write!(f, "{}", syntax.to_string().to_uppercase()),
+            Self::Relative => write!(f, "REL"),
+            Self::User(label) => write!(f, "{}", label),
+        }
+    }
+}
+
+/// The lowercase button labels, as in `--json` output.
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rgb" => Ok(Format::Rgb),
+            "hex" => Ok(Format::Hex),
+            "hsl" => Ok(Format::Hsl),
+            "hsv" => Ok(Format::Hsv),
+            "vec" => Ok(Format::Vec),
+            "ref" => Ok(Format::Colorref),
+            "bgr" => Ok(Format::Bgr),
+            "srgb" => Ok(Format::Srgb),
+            "256" => Ok(Format::Ansi256),
+            "esc" => Ok(Format::Truecolor),
+            "rel" => Ok(Format::Relative),
+            s => s.parse().map(Format::Var).map_err(|_| format!("Invalid value: {}", s)),
+        }
+    }
+}
+
+/// An output format defined by a template, shown as a button labelled
+/// `label`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserFormat {
+    pub label: String,
+    pub template: Template,
+}
+
+/// Output settings that apply across formats, fixed for the whole session.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "druid", derive(Data))]
+pub struct FormatOptions {
+    pub hex_short: HexShort,
+    pub rgb_percent: bool,
+    pub alpha: AlphaRepr,
+    pub depth: Depth,
+    pub hdr: bool,
+    pub var_name: Option<String>,
+    pub var_syntax: VarSyntax,
+    pub relative: Option<Arc<Relative>>,
+    /// Formats beyond the built-ins, in button order.
+    pub user_formats: Arc<Vec<UserFormat>>,
+    /// Output is the nearest color in this set.
+    pub snap: Option<SnapSet>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            hex_short: HexShort::Never,
+            rgb_percent: false,
+            alpha: AlphaRepr::Percent,
+            depth: Depth::Eight,
+            hdr: false,
+            var_name: None,
+            var_syntax: VarSyntax::Css,
+            relative: None,
+            user_formats: Arc::new(Vec::new()),
+            snap: None,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// The color as output, i.e. snapped if there's a snap set.
+    pub fn snapped(&self, color: &Color) -> Color {
+        match self.snap {
+            Some(set) => set.nearest(color),
+            None => color.clone(),
+        }
+    }
+
+    pub fn user_format(&self, label: &str) -> Option<&UserFormat> {
+        self.user_formats.iter().find(|user| user.label == label)
+    }
+
+    /// `var_name`, or the nearest named color.
+    pub fn var_name(&self, color: &Color) -> String {
+        self.var_name.clone().unwrap_or_else(|| names::nearest(color).0.to_string())
+    }
+}
+
+/// Parses any supported input, along with the format it was written in:
+/// COLORREF, BGR and `color(srgb ...)` are recognized, anything else CSS
+/// understands counts as hex.
+pub fn parse(s: &str) -> Result<(Color, Format), String> {
+    if let Some(c) = Color::from_colorref_str(s) {
+        debug!("{:?} parsed as COLORREF: 0x and 8 hex digits, the top byte 0", s);
+        return Ok((c, Format::Colorref));
+    }
+    if let Some(c) = Color::from_bgr_str(s) {
+        debug!("{:?} parsed as BGR: 3 or 4 bytes in parentheses", s);
+        return Ok((c, Format::Bgr));
+    }
+    if let Some(c) = Color::from_srgb_str(s) {
+        debug!("{:?} parsed as color(srgb)", s);
+        return Ok((c, Format::Srgb));
+    }
+    match s.parse() {
+        Ok(c) => {
+            debug!("{:?} parsed as CSS, which is output as hex", s);
+            Ok((c, Format::Hex))
+        }
+        Err(e) => {
+            debug!("{:?} isn't COLORREF, BGR, color(srgb) or CSS: {}", s, e);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{parse, Format, FormatOptions, UserFormat};
+    use crate::color::Color;
+    use crate::vars::VarSyntax;
+
+    #[test]
+    fn parse_input() {
+        assert_eq!(parse("#ff8000").unwrap().1, Format::Hex);
+        assert_eq!(parse("0x000080FF").unwrap().1, Format::Colorref);
+        assert_eq!(parse("color(srgb 1 0.5 0)").unwrap().1, Format::Srgb);
+        assert!(parse("nope").is_err());
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let options = FormatOptions { var_syntax: VarSyntax::Scss, ..FormatOptions::default() };
+        for format in Format::values(&options) {
+            assert_eq!(format.to_string().to_lowercase().parse(), Ok(format));
+        }
+        assert!("cmyk".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn user_formats() {
+        let user = UserFormat { label: "KT".to_string(), template: "Color(0x{a*255|hex}{r*255|hex}{g*255|hex}{b*255|hex})".parse().unwrap() };
+        let options = FormatOptions { user_formats: Arc::new(vec![user]), ..FormatOptions::default() };
+        let formats = Format::values(&options);
+        let kt = formats.last().unwrap();
+        assert_eq!(kt.to_string(), "KT");
+        assert_eq!(Format::named("kt", &options).as_ref(), Some(kt));
+        assert_eq!(Format::named("HEX", &options), Some(Format::Hex));
+        assert_eq!(kt.format(&Color::from_rgba_f32(1.0, 0.4, 0.2, 1.0), &options), "Color(0xffff6633)");
+        // without its definition it falls back to hex
+        assert_eq!(kt.format(&Color::from_rgba_f32(1.0, 0.4, 0.2, 1.0), &FormatOptions::default()), "#ff6633");
+    }
+}
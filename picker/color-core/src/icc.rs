@@ -0,0 +1,323 @@
+//! Display profile support for the preview.
+//!
+//! Only matrix/TRC RGB profiles are understood, which covers what monitor
+//! calibration tools produce. The picker's colors are sRGB; with a profile
+//! installed, everything drawn on screen is converted into the display's
+//! color space so it looks the way sRGB consumers will see it.
+
+use std::{fs, path::Path};
+
+use once_cell::sync::OnceCell;
+
+use crate::color::srgb_to_linear;
+
+static DISPLAY: OnceCell<DisplayProfile> = OnceCell::new();
+
+/// sRGB to XYZ, chromatically adapted to the D50 ICC connection space.
+const SRGB_TO_XYZ_D50: [[f32; 3]; 3] = [
+    [0.436_074_7, 0.385_064_9, 0.143_080_4],
+    [0.222_504_5, 0.716_878_6, 0.060_616_9],
+    [0.013_932_2, 0.097_104_5, 0.714_173_3],
+];
+
+/// Resolution of the inverse tone curves.
+const LUT_SIZE: usize = 4096;
+
+#[derive(Debug)]
+pub struct DisplayProfile {
+    /// Linear sRGB to linear display RGB.
+    matrix: [[f32; 3]; 3],
+    /// Linear display RGB to encoded 8-bit values, per channel.
+    inverse_trc: [Vec<u8>; 3],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Curve {
+    Gamma(f32),
+    Table(Vec<u16>),
+    /// ICC parametric curve: function type and its parameters.
+    Parametric(u16, Vec<f32>),
+}
+
+impl Curve {
+    /// Encoded device value to linear light.
+    fn eval(&self, x: f32) -> f32 {
+        match self {
+            Curve::Gamma(g) => x.powf(*g),
+            Curve::Table(t) => {
+                let pos = x * (t.len() - 1) as f32;
+                let i = (pos.floor() as usize).min(t.len() - 2);
+                let frac = pos - i as f32;
+                (t[i] as f32 * (1.0 - frac) + t[i + 1] as f32 * frac) / 65535.0
+            }
+            Curve::Parametric(kind, p) => {
+                let p = |i: usize| p.get(i).copied().unwrap_or(0.0);
+                let (g, a, b, c, d, e, f) = (p(0), p(1), p(2), p(3), p(4), p(5), p(6));
+                match kind {
+                    0 => x.powf(g),
+                    1 => if x >= -b / a { (a * x + b).powf(g) } else { 0.0 },
+                    2 => if x >= -b / a { (a * x + b).powf(g) + c } else { c },
+                    3 => if x >= d { (a * x + b).powf(g) } else { c * x },
+                    _ => if x >= d { (a * x + b).powf(g) + e } else { c * x + f },
+                }
+            }
+        }
+    }
+
+    /// Samples the inverse by bisection; tone curves are monotonic.
+    fn inverse_lut(&self) -> Vec<u8> {
+        (0..LUT_SIZE)
+            .map(|i| {
+                let target = i as f32 / (LUT_SIZE - 1) as f32;
+                let (mut lo, mut hi) = (0.0f32, 1.0f32);
+                for _ in 0..20 {
+                    let mid = (lo + hi) / 2.0;
+                    if self.eval(mid) < target { lo = mid } else { hi = mid }
+                }
+                ((lo + hi) / 2.0 * 255.0).round() as u8
+            })
+            .collect()
+    }
+}
+
+impl DisplayProfile {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 132 || &bytes[36..40] != b"acsp" {
+            return Err("not an ICC profile".to_string());
+        }
+        if &bytes[16..20] != b"RGB " {
+            return Err("not an RGB profile".to_string());
+        }
+        // a corrupt count mustn't walk past the table that's there
+        let count = (be_u32(bytes, 128).unwrap_or(0) as usize).min((bytes.len() - 132) / 12);
+        let tag = |sig: &[u8; 4]| -> Option<&[u8]> {
+            (0..count).find_map(|i| {
+                let entry = 132 + i * 12;
+                if bytes.get(entry..entry + 4)? != sig {
+                    return None;
+                }
+                let offset = be_u32(bytes, entry + 4)? as usize;
+                let size = be_u32(bytes, entry + 8)? as usize;
+                bytes.get(offset..offset.checked_add(size)?)
+            })
+        };
+        let missing = |sig: &str| format!("missing {} tag; only matrix/TRC profiles are supported", sig);
+
+        let mut primaries = [[0.0f32; 3]; 3];
+        for (col, sig) in [b"rXYZ", b"gXYZ", b"bXYZ"].iter().enumerate() {
+            let xyz = parse_xyz(tag(sig).ok_or_else(|| missing(&String::from_utf8_lossy(*sig)))?)?;
+            for (row, v) in xyz.iter().enumerate() {
+                primaries[row][col] = *v;
+            }
+        }
+        let mut inverse_trc = [Vec::new(), Vec::new(), Vec::new()];
+        for (ix, sig) in [b"rTRC", b"gTRC", b"bTRC"].iter().enumerate() {
+            inverse_trc[ix] = parse_curve(tag(sig).ok_or_else(|| missing(&String::from_utf8_lossy(*sig)))?)?.inverse_lut();
+        }
+
+        let to_display = invert(&primaries).ok_or("profile primaries are degenerate")?;
+        Ok(Self { matrix: multiply(&to_display, &SRGB_TO_XYZ_D50), inverse_trc })
+    }
+
+    /// Converts an sRGB pixel to the display's encoding.
+    pub fn transform(&self, [r, g, b, a]: [u8; 4]) -> [u8; 4] {
+        let linear = [r, g, b].iter().map(|&c| srgb_to_linear(c as f32 / 255.0)).collect::<Vec<_>>();
+        let channel = |ch: usize| {
+            let m = &self.matrix[ch];
+            let v = (m[0] * linear[0] + m[1] * linear[1] + m[2] * linear[2]).clamp(0.0, 1.0);
+            self.inverse_trc[ch][(v * (LUT_SIZE - 1) as f32).round() as usize]
+        };
+        [channel(0), channel(1), channel(2), a]
+    }
+}
+
+/// Installs the profile used by [`to_display`] for the rest of the session.
+pub fn install(profile: DisplayProfile) {
+    if DISPLAY.set(profile).is_err() {
+        log::warn!("display profile already installed");
+    }
+}
+
+/// Converts an sRGB pixel for drawing on screen; a no-op without a profile.
+pub fn to_display(px: [u8; 4]) -> [u8; 4] {
+    match DISPLAY.get() {
+        Some(profile) => profile.transform(px),
+        None => px,
+    }
+}
+
+fn be_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 4)?;
+    Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn s15fixed16(bytes: &[u8], at: usize) -> Option<f32> {
+    be_u32(bytes, at).map(|v| v as i32 as f32 / 65536.0)
+}
+
+fn parse_xyz(data: &[u8]) -> Result<[f32; 3], String> {
+    if data.get(0..4) != Some(b"XYZ ") {
+        return Err("malformed XYZ tag".to_string());
+    }
+    match (s15fixed16(data, 8), s15fixed16(data, 12), s15fixed16(data, 16)) {
+        (Some(x), Some(y), Some(z)) => Ok([x, y, z]),
+        _ => Err("truncated XYZ tag".to_string()),
+    }
+}
+
+fn parse_curve(data: &[u8]) -> Result<Curve, String> {
+    let truncated = || "truncated curve tag".to_string();
+    match data.get(0..4) {
+        Some(b"curv") => {
+            let count = be_u32(data, 8).ok_or_else(truncated)? as usize;
+            let entries = (0..count)
+                .map(|i| data.get(12 + i * 2..14 + i * 2).map(|b| u16::from_be_bytes([b[0], b[1]])))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(truncated)?;
+            Ok(match entries[..] {
+                [] => Curve::Gamma(1.0),
+                [g] => Curve::Gamma(g as f32 / 256.0),
+                _ => Curve::Table(entries),
+            })
+        }
+        Some(b"para") => {
+            let kind = data.get(8..10).map(|b| u16::from_be_bytes([b[0], b[1]])).ok_or_else(truncated)?;
+            let count = match kind {
+                0 => 1,
+                1 => 3,
+                2 => 4,
+                3 => 5,
+                4 => 7,
+                _ => return Err(format!("unknown parametric curve type {}", kind)),
+            };
+            let params = (0..count)
+                .map(|i| s15fixed16(data, 12 + i * 4))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(truncated)?;
+            Ok(Curve::Parametric(kind, params))
+        }
+        _ => Err("unsupported curve tag type".to_string()),
+    }
+}
+
+fn multiply(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut out = [[0.0; 3]; 3];
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, v) in row.iter_mut().enumerate() {
+            *v = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+    out
+}
+
+fn invert(m: &[[f32; 3]; 3]) -> Option<[[f32; 3]; 3]> {
+    let cof = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let det = m[0][0] * cof(1, 2, 1, 2) - m[0][1] * cof(1, 2, 0, 2) + m[0][2] * cof(1, 2, 0, 1);
+    if det.abs() < 1e-9 {
+        return None;
+    }
+    Some([
+        [cof(1, 2, 1, 2) / det, -cof(0, 2, 1, 2) / det, cof(0, 1, 1, 2) / det],
+        [-cof(1, 2, 0, 2) / det, cof(0, 2, 0, 2) / det, -cof(0, 1, 0, 2) / det],
+        [cof(1, 2, 0, 1) / det, -cof(0, 2, 0, 1) / det, cof(0, 1, 0, 1) / det],
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DisplayProfile;
+
+    fn fixed(v: f32) -> [u8; 4] {
+        ((v * 65536.0).round() as i32).to_be_bytes()
+    }
+
+    /// A minimal matrix/TRC profile with the given primaries and one curve for
+    /// all channels.
+    fn profile(primaries: [[f32; 3]; 3], curve: Vec<u8>) -> Vec<u8> {
+        let mut tags: Vec<(&[u8; 4], Vec<u8>)> = Vec::new();
+        for (sig, xyz) in [b"rXYZ", b"gXYZ", b"bXYZ"].iter().zip(primaries.iter()) {
+            let mut data = b"XYZ \0\0\0\0".to_vec();
+            for v in xyz {
+                data.extend_from_slice(&fixed(*v));
+            }
+            tags.push((sig, data));
+        }
+        for sig in [b"rTRC", b"gTRC", b"bTRC"].iter() {
+            tags.push((sig, curve.clone()));
+        }
+
+        let mut bytes = vec![0u8; 128];
+        bytes[16..20].copy_from_slice(b"RGB ");
+        bytes[36..40].copy_from_slice(b"acsp");
+        bytes.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+        let mut offset = 132 + tags.len() * 12;
+        let mut data = Vec::new();
+        for (sig, tag) in &tags {
+            bytes.extend_from_slice(*sig);
+            bytes.extend_from_slice(&(offset as u32).to_be_bytes());
+            bytes.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+            offset += tag.len();
+            data.extend_from_slice(tag);
+        }
+        bytes.extend(data);
+        bytes
+    }
+
+    fn srgb_curve() -> Vec<u8> {
+        let mut curve = b"para\0\0\0\0\0\x03\0\0".to_vec();
+        for v in &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045] {
+            curve.extend_from_slice(&fixed(*v));
+        }
+        curve
+    }
+
+    const SRGB_PRIMARIES: [[f32; 3]; 3] = [
+        [0.436_1, 0.222_5, 0.013_9],
+        [0.385_1, 0.716_9, 0.097_1],
+        [0.143_1, 0.060_6, 0.714_1],
+    ];
+
+    #[test]
+    fn srgb_profile_is_identity() {
+        let p = DisplayProfile::parse(&profile(SRGB_PRIMARIES, srgb_curve())).unwrap();
+        for px in &[[255, 0, 0, 255], [0, 128, 255, 128], [17, 34, 51, 255], [255, 255, 255, 0]] {
+            let out = p.transform(*px);
+            for ch in 0..4 {
+                assert!((out[ch] as i32 - px[ch] as i32).abs() <= 1, "{:?} -> {:?}", px, out);
+            }
+        }
+    }
+
+    #[test]
+    fn gamma_curve() {
+        // a linear (gamma 1.0) display shows sRGB mid-grey at ~21% drive
+        let p = DisplayProfile::parse(&profile(SRGB_PRIMARIES, b"curv\0\0\0\0\0\0\0\x01\x01\x00".to_vec())).unwrap();
+        assert_eq!(p.transform([128, 128, 128, 255]), [55, 55, 55, 255]);
+    }
+
+    #[test]
+    fn rejects_unsupported_profiles() {
+        assert!(DisplayProfile::parse(b"not a profile").is_err());
+        let mut bytes = profile(SRGB_PRIMARIES, srgb_curve());
+        bytes[16..20].copy_from_slice(b"CMYK");
+        assert!(DisplayProfile::parse(&bytes).is_err());
+        let lut_only = profile(SRGB_PRIMARIES, b"mft2\0\0\0\0".to_vec());
+        assert!(DisplayProfile::parse(&lut_only).is_err());
+    }
+
+    #[test]
+    fn corrupt_tag_table() {
+        // a count past the end of the table only reads what's there
+        let mut bytes = profile(SRGB_PRIMARIES, srgb_curve());
+        bytes[128..132].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(DisplayProfile::parse(&bytes).is_ok());
+        // as does a tag reaching past the end of the file
+        bytes[140..144].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(DisplayProfile::parse(&bytes).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+//! Color parsing, conversion and formatting for the picker, without the GUI.
+//!
+//! ```
+//! use color_core::{parse, Format, FormatOptions};
+//!
+//! let (color, format) = parse("rgb(255, 128, 0)").unwrap();
+//! assert_eq!(format, Format::Hex);
+//! assert_eq!(Format::Hsl.format(&color, &FormatOptions::default()), "hsl(30deg, 100%, 50%)");
+//! ```
+//!
+//! Enable the `druid` feature to use the types as `druid::Data`. The C and
+//! JavaScript bindings are the color-ffi and color-wasm crates.
+
+// `map_or` over `is_some_and` and friends, as the toolchains we still build
+// with (rust-version) predate some of them
+#![allow(clippy::unnecessary_map_or)]
+
+pub mod ansi;
+pub mod color;
+pub mod derive;
+pub mod format;
+pub mod icc;
+pub mod names;
+pub mod relative;
+pub mod snap;
+pub mod surface;
+pub mod template;
+pub mod vars;
+
+pub use color::Color;
+pub use format::{parse, Format, FormatOptions};
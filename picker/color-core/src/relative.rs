@@ -0,0 +1,134 @@
+//! Colors written relative to a base, for stylesheets that derive colors
+//! instead of repeating literals.
+
+use crate::color::Color;
+
+/// The base expression (e.g. `var(--brand)`) and the color it stands for.
+#[derive(Debug, Clone)]
+pub struct Relative {
+    pub base: String,
+    pub from: Color,
+}
+
+impl PartialEq for Relative {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base && self.from.pixel16() == other.from.pixel16()
+    }
+}
+
+impl Relative {
+    pub fn new(base: String, from: Color) -> Self {
+        Self { base, from }
+    }
+
+    /// `color` as `color-mix()` with white or black when it's a tint or shade
+    /// of the base, else as relative `hsl(from ...)`.
+    pub fn express(&self, color: &Color) -> String {
+        if self.from.pixel() == color.pixel() {
+            return self.base.clone();
+        }
+        self.color_mix(color).unwrap_or_else(|| self.relative_hsl(color))
+    }
+
+    /// `color-mix(in srgb, base p%, white)`, if some whole percentage gives
+    /// `color` to within 8-bit rounding.
+    pub fn color_mix(&self, color: &Color) -> Option<String> {
+        if (self.from.alpha() - color.alpha()).abs() > 0.5 / 255.0 {
+            return None;
+        }
+        let from = [self.from.red(), self.from.green(), self.from.blue()];
+        let to = [color.red(), color.green(), color.blue()];
+        for &(name, target) in &[("white", 1.0f32), ("black", 0.0)] {
+            // the channel furthest from the target gives the best estimate
+            let ix = (0..3).max_by(|&a, &b| (from[a] - target).abs().total_cmp(&(from[b] - target).abs()))?;
+            if (from[ix] - target).abs() < 1.0 / 255.0 {
+                continue;
+            }
+            let p = ((to[ix] - target) / (from[ix] - target) * 100.0).round();
+            if p <= 0.0 || p >= 100.0 {
+                continue;
+            }
+            let mixed = |c: f32| c * p / 100.0 + target * (1.0 - p / 100.0);
+            if (0..3).all(|i| (mixed(from[i]) - to[i]).abs() <= 1.0 / 255.0) {
+                return Some(format!("color-mix(in srgb, {} {}%, {})", self.base, p, name));
+            }
+        }
+        None
+    }
+
+    /// CSS relative color syntax, e.g. `hsl(from var(--x) h s calc(l + 10))`.
+    /// In it `s` and `l` are plain numbers, so the offsets have no `%`.
+    pub fn relative_hsl(&self, color: &Color) -> String {
+        let (h0, s0, l0) = self.from.to_hsl();
+        let (h1, s1, l1) = color.to_hsl();
+        let hue = if s1 < 1e-4 {
+            // greys have no hue to keep
+            "h".to_string()
+        } else if s0 < 1e-4 {
+            number(h1 * 360.0)
+        } else {
+            offset("h", ((h1 - h0) * 360.0 + 180.0).rem_euclid(360.0) - 180.0)
+        };
+        let mut out = format!(
+            "hsl(from {} {} {} {}",
+            self.base,
+            hue,
+            offset("s", (s1 - s0) * 100.0),
+            offset("l", (l1 - l0) * 100.0),
+        );
+        let da = color.alpha() - self.from.alpha();
+        if da.abs() >= 0.0005 {
+            out.push_str(&format!(" / {}", number(color.alpha())));
+        }
+        out.push(')');
+        out
+    }
+}
+
+/// `l`, `calc(l + 10)` or `calc(l - 2.5)`.
+fn offset(channel: &str, delta: f32) -> String {
+    let rounded = (delta * 10.0).round() / 10.0;
+    if rounded == 0.0 {
+        channel.to_string()
+    } else if rounded > 0.0 {
+        format!("calc({} + {})", channel, number(rounded))
+    } else {
+        format!("calc({} - {})", channel, number(-rounded))
+    }
+}
+
+fn number(x: f32) -> String {
+    let s = format!("{:.3}", x);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Relative;
+    use crate::color::Color;
+
+    fn base() -> Relative {
+        // hsl(210deg, 50%, 40%)
+        Relative::new("var(--x)".to_string(), Color::from_rgba_f32(0.2, 0.4, 0.6, 1.0))
+    }
+
+    #[test]
+    fn mix() {
+        let r = base();
+        assert_eq!(r.express(&Color::from_rgba_f32(0.2, 0.4, 0.6, 1.0)), "var(--x)");
+        assert_eq!(r.express(&Color::from_rgba_f32(0.6, 0.7, 0.8, 1.0)), "color-mix(in srgb, var(--x) 50%, white)");
+        assert_eq!(r.express(&Color::from_rgba_f32(0.1, 0.2, 0.3, 1.0)), "color-mix(in srgb, var(--x) 50%, black)");
+    }
+
+    #[test]
+    fn hsl() {
+        let r = base();
+        let black = Color::from_rgba_f32(0.0, 0.0, 0.0, 1.0);
+        assert_eq!(r.express(&black), "hsl(from var(--x) h calc(s - 50) calc(l - 40))");
+        // hsl(240deg, 50%, 40%) at half alpha
+        let bluer = Color::from_rgba_f32(0.2, 0.2, 0.6, 0.5);
+        assert_eq!(r.express(&bluer), "hsl(from var(--x) calc(h + 30) s l / 0.5)");
+        let grey = Relative::new("var(--g)".to_string(), Color::from_rgba_f32(0.5, 0.5, 0.5, 1.0));
+        assert_eq!(grey.relative_hsl(&bluer), "hsl(from var(--g) 240 calc(s + 50) calc(l - 10) / 0.5)");
+    }
+}
@@ -0,0 +1,192 @@
+//! Fixed sets of colors output can be snapped to, for targets that only
+//! allow certain colors.
+
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "druid")]
+use druid::Data;
+
+use crate::color::Color;
+use crate::names::NAMED_COLORS;
+
+/// The 16 color VGA text mode palette, in index order.
+const VGA: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00], [0x00, 0x00, 0xaa], [0x00, 0xaa, 0x00], [0x00, 0xaa, 0xaa],
+    [0xaa, 0x00, 0x00], [0xaa, 0x00, 0xaa], [0xaa, 0x55, 0x00], [0xaa, 0xaa, 0xaa],
+    [0x55, 0x55, 0x55], [0x55, 0x55, 0xff], [0x55, 0xff, 0x55], [0x55, 0xff, 0xff],
+    [0xff, 0x55, 0x55], [0xff, 0x55, 0xff], [0xff, 0xff, 0x55], [0xff, 0xff, 0xff],
+];
+
+/// The NES (2C02) palette, in index order, with its unused entries as black.
+/// Of the blacks only [`NES_BLACK`] is picked.
+const NES: [[u8; 3]; 64] = [
+    [0x7c, 0x7c, 0x7c], [0x00, 0x00, 0xfc], [0x00, 0x00, 0xbc], [0x44, 0x28, 0xbc],
+    [0x94, 0x00, 0x84], [0xa8, 0x00, 0x20], [0xa8, 0x10, 0x00], [0x88, 0x14, 0x00],
+    [0x50, 0x30, 0x00], [0x00, 0x78, 0x00], [0x00, 0x68, 0x00], [0x00, 0x58, 0x00],
+    [0x00, 0x40, 0x58], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+    [0xbc, 0xbc, 0xbc], [0x00, 0x78, 0xf8], [0x00, 0x58, 0xf8], [0x68, 0x44, 0xfc],
+    [0xd8, 0x00, 0xcc], [0xe4, 0x00, 0x58], [0xf8, 0x38, 0x00], [0xe4, 0x5c, 0x10],
+    [0xac, 0x7c, 0x00], [0x00, 0xb8, 0x00], [0x00, 0xa8, 0x00], [0x00, 0xa8, 0x44],
+    [0x00, 0x88, 0x88], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+    [0xf8, 0xf8, 0xf8], [0x3c, 0xbc, 0xfc], [0x68, 0x88, 0xfc], [0x98, 0x78, 0xf8],
+    [0xf8, 0x78, 0xf8], [0xf8, 0x58, 0x98], [0xf8, 0x78, 0x58], [0xfc, 0xa0, 0x44],
+    [0xf8, 0xb8, 0x00], [0xb8, 0xf8, 0x18], [0x58, 0xd8, 0x54], [0x58, 0xf8, 0x98],
+    [0x00, 0xe8, 0xd8], [0x78, 0x78, 0x78], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+    [0xfc, 0xfc, 0xfc], [0xa4, 0xe4, 0xfc], [0xb8, 0xb8, 0xf8], [0xd8, 0xb8, 0xf8],
+    [0xf8, 0xb8, 0xf8], [0xf8, 0xa4, 0xc0], [0xf0, 0xd0, 0xb0], [0xfc, 0xe0, 0xa8],
+    [0xf8, 0xd8, 0x78], [0xd8, 0xf8, 0x78], [0xb8, 0xf8, 0xb8], [0xb8, 0xf8, 0xd8],
+    [0x00, 0xfc, 0xfc], [0xf8, 0xd8, 0xf8], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+];
+
+/// The black NES games use; `$0D` is blacker than black and upsets some TVs,
+/// and the other blacks are unused entries.
+const NES_BLACK: usize = 0x0f;
+
+/// The original Game Boy's four greens, lightest (shade 0) first.
+const GAMEBOY: [[u8; 3]; 4] = [
+    [0x9b, 0xbc, 0x0f], [0x8b, 0xac, 0x0f], [0x30, 0x62, 0x30], [0x0f, 0x38, 0x0f],
+];
+
+/// A 5 bit SNES channel widened to 8 bits.
+fn widen5(v: u16) -> u8 {
+    ((v << 3) | (v >> 2)) as u8
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "druid", derive(Data))]
+pub enum SnapSet {
+    /// The 216 web-safe colors, each channel a multiple of 0x33.
+    Web,
+    /// The 16 VGA colors.
+    Vga,
+    /// The CSS named colors.
+    Css,
+    /// The NES's 64 entry palette.
+    Nes,
+    /// Any 15 bit SNES color, 5 bits per channel.
+    Snes,
+    /// The original Game Boy's four shades of green.
+    Gameboy,
+}
+impl FromStr for SnapSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "web" => Ok(SnapSet::Web),
+            "vga" => Ok(SnapSet::Vga),
+            "css" => Ok(SnapSet::Css),
+            "nes" => Ok(SnapSet::Nes),
+            "snes" => Ok(SnapSet::Snes),
+            "gameboy" => Ok(SnapSet::Gameboy),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for SnapSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Web => "web",
+            Self::Vga => "vga",
+            Self::Css => "css",
+            Self::Nes => "nes",
+            Self::Snes => "snes",
+            Self::Gameboy => "gameboy",
+        })
+    }
+}
+
+impl SnapSet {
+    pub fn values() -> Vec<SnapSet> {
+        vec![SnapSet::Web, SnapSet::Vga, SnapSet::Css, SnapSet::Nes, SnapSet::Snes, SnapSet::Gameboy]
+    }
+
+    /// The set's colors, in their usual order; for the SNES that's by 15 bit
+    /// BGR value.
+    pub fn colors(&self) -> Vec<[u8; 3]> {
+        match self {
+            Self::Web => {
+                let levels = (0..6).map(|i| i * 0x33);
+                levels.clone()
+                    .flat_map(|r| levels.clone().flat_map(move |g| (0..6).map(move |b| [r, g, b * 0x33])))
+                    .collect()
+            }
+            Self::Vga => VGA.to_vec(),
+            Self::Css => NAMED_COLORS.iter().map(|(_, rgb)| *rgb).collect(),
+            Self::Nes => NES.to_vec(),
+            Self::Snes => (0..0x8000u16).map(|bgr| [widen5(bgr & 0x1f), widen5((bgr >> 5) & 0x1f), widen5(bgr >> 10)]).collect(),
+            Self::Gameboy => GAMEBOY.to_vec(),
+        }
+    }
+
+    /// The index into [`colors`](Self::colors) of the entry closest to
+    /// `color` (CIE76); the first of equally close ones, and `$0F` for any
+    /// NES black.
+    pub fn index(&self, color: &Color) -> usize {
+        if let Self::Snes = self {
+            // every combination is legal, so round each channel
+            let [r, g, b, _] = color.pixel16();
+            let bits = |c: u16| ((c as u32 * 31 + 32767) / 65535) as usize;
+            return bits(b) << 10 | bits(g) << 5 | bits(r);
+        }
+        let usable = |(i, rgb): &(usize, [u8; 3])| *self != Self::Nes || *rgb != [0; 3] || *i == NES_BLACK;
+        self.colors().into_iter()
+            .enumerate()
+            .filter(usable)
+            .map(|(i, rgb)| (i, color.delta_e(&opaque(rgb))))
+            .fold((0, f32::INFINITY), |best, (i, d)| if d < best.1 { (i, d) } else { best })
+            .0
+    }
+
+    /// The entry closest to `color`, with `color`'s alpha.
+    pub fn nearest(&self, color: &Color) -> Color {
+        let index = self.index(color);
+        let [r, g, b] = match self {
+            Self::Snes => {
+                let bgr = index as u16;
+                [widen5(bgr & 0x1f), widen5((bgr >> 5) & 0x1f), widen5(bgr >> 10)]
+            }
+            _ => self.colors()[index],
+        };
+        Color::from_rgba_f32(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, color.alpha())
+    }
+}
+
+fn opaque([r, g, b]: [u8; 3]) -> Color {
+    Color::from_rgba_f32(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapSet;
+    use crate::color::{Color, HexShort};
+
+    #[test]
+    fn sets() {
+        assert_eq!(SnapSet::Web.colors().len(), 216);
+        assert_eq!(SnapSet::Web.colors()[1], [0, 0, 0x33]);
+        assert_eq!(SnapSet::Vga.colors().len(), 16);
+        assert!(SnapSet::Css.colors().contains(&[0xff, 0x63, 0x47]));
+        assert_eq!("vga".parse(), Ok(SnapSet::Vga));
+        assert!("ega".parse::<SnapSet>().is_err());
+        assert_eq!(SnapSet::Nes.colors().len(), 64);
+        assert_eq!(SnapSet::Snes.colors().len(), 0x8000);
+        assert_eq!(SnapSet::Snes.colors()[0x7fff], [0xff, 0xff, 0xff]);
+        assert_eq!(SnapSet::Snes.colors()[0x001f], [0xff, 0, 0]);
+    }
+
+    #[test]
+    fn nearest() {
+        let c = Color::from_rgba_f32(0.95, 0.42, 0.05, 0.5);
+        assert_eq!(SnapSet::Web.nearest(&c).to_hex_string(HexShort::Never), "#ff660080");
+        assert_eq!(SnapSet::Vga.nearest(&c).to_hex_string(HexShort::Never), "#aa550080");
+        assert_eq!(SnapSet::Snes.nearest(&c).pixel(), [0xef, 0x6b, 0x10, 0x80]);
+        assert_eq!(SnapSet::Gameboy.nearest(&c).pixel(), [0x8b, 0xac, 0x0f, 0x80]);
+        assert_eq!(SnapSet::Vga.index(&c), 6);
+        assert_eq!(SnapSet::Snes.index(&c), 2 << 10 | 13 << 5 | 29);
+        // not the unused or blacker than black entries before it
+        assert_eq!(SnapSet::Nes.index(&Color::from_rgba_f32(0.0, 0.0, 0.0, 1.0)), 0x0f);
+        let tomato = Color::from_rgba_f32(1.0, 99.0 / 255.0, 71.0 / 255.0, 1.0);
+        assert_eq!(SnapSet::Css.nearest(&tomato).pixel(), tomato.pixel());
+    }
+}
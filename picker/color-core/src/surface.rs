@@ -0,0 +1,201 @@
+//! Pixel buffers behind the picker surfaces and sliders, as RGBA rows
+//! converted for the display.
+//!
+//! Where part of a surface is out of sRGB gamut and masked in grey, the
+//! boundary is anti-aliased: edge pixels blend the two by how much of the
+//! pixel is in gamut, so it doesn't step at HiDPI scales.
+
+use wide::f32x8;
+
+use crate::color::{Color, MAX_OKLCH_CHROMA, max_oklch_chroma};
+
+/// Vertical samples per pixel when measuring how much of one is in gamut.
+const EDGE_SAMPLES: usize = 4;
+
+/// Pixels computed at once by [`sat_value`].
+const LANES: usize = 8;
+
+/// Fills `buf` with `width` by `height` pixels from `get_px`. The buffer is
+/// resized in place, so one kept across paints is only reallocated when the
+/// surface grows.
+pub fn draw(buf: &mut Vec<u8>, width: usize, height: usize, get_px: impl Fn(usize, usize) -> [u8; 4]) {
+    buf.resize(width * height * 4, 0);
+    for (y, row) in buf.chunks_exact_mut(width * 4).enumerate() {
+        for (x, px) in row.chunks_exact_mut(4).enumerate() {
+            px.copy_from_slice(&crate::icc::to_display(get_px(x, y)));
+        }
+    }
+}
+
+/// What a channel holds in HSV to RGB at a given hue: the chroma, the
+/// intermediate component, or nothing, each on top of the minimum.
+#[derive(Clone, Copy)]
+enum Part {
+    Chroma,
+    Between,
+    Zero,
+}
+
+/// The parts red, green and blue take at `hue`, by the sector it falls in,
+/// as `Color::from_hsva_f32` picks them.
+fn parts(hue: f32) -> [Part; 3] {
+    use Part::*;
+    let h = hue * 6.0;
+    if h <= 1.0 {
+        [Chroma, Between, Zero]
+    } else if h <= 2.0 {
+        [Between, Chroma, Zero]
+    } else if h <= 3.0 {
+        [Zero, Chroma, Between]
+    } else if h <= 4.0 {
+        [Zero, Between, Chroma]
+    } else if h <= 5.0 {
+        [Between, Zero, Chroma]
+    } else {
+        [Chroma, Zero, Between]
+    }
+}
+
+/// HSV saturation across and value down at `hue`, with `headroom` the value
+/// at the top. The hue is the same everywhere, so the channels' parts are
+/// too and each row is converted [`LANES`] pixels at a time, with the same
+/// operations per pixel as `Color::from_hsva_f32` so they come out alike.
+pub fn sat_value(buf: &mut Vec<u8>, width: usize, height: usize, hue: f32, headroom: f32) {
+    buf.resize(width * height * 4, 0);
+    let parts = parts(hue);
+    let between = f32x8::splat(1.0 - ((hue * 6.0).rem_euclid(2.0) - 1.0).abs());
+    let offsets = f32x8::from([0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+    for (y, row) in buf.chunks_exact_mut(width * 4).enumerate() {
+        let value = f32x8::splat((1.0 - y as f32 / height as f32) * headroom);
+        for (chunk, pxs) in row.chunks_mut(LANES * 4).enumerate() {
+            let x = offsets + f32x8::splat((chunk * LANES) as f32);
+            let chroma = value * (x / f32x8::splat(width as f32));
+            let min = value - chroma;
+            let [r, g, b] = parts.map(|part| match part {
+                Part::Chroma => chroma + min,
+                Part::Between => chroma * between + min,
+                Part::Zero => min,
+            }.to_array());
+            for (i, px) in pxs.chunks_exact_mut(4).enumerate() {
+                let u = |c: f32| (c * 255.0).round() as u8;
+                px.copy_from_slice(&crate::icc::to_display([u(r[i]), u(g[i]), u(b[i]), 255]));
+            }
+        }
+    }
+}
+
+/// OKLCH chroma across and lightness down at `hue`, with what sRGB can't
+/// show masked in grey.
+pub fn chroma_lightness(buf: &mut Vec<u8>, width: usize, height: usize, hue: f32) {
+    let step = MAX_OKLCH_CHROMA / width as f32;
+    // the gamut edge at sub-rows of each row, so it's smooth where it runs
+    // nearly horizontal as well as vertical
+    let edges: Vec<Vec<f32>> = (0..height)
+        .map(|y| (0..EDGE_SAMPLES)
+            .map(|s| {
+                let l = 1.0 - (y as f32 + s as f32 / EDGE_SAMPLES as f32) / height as f32;
+                max_oklch_chroma(l, hue)
+            })
+            .collect())
+        .collect();
+    draw(buf, width, height, |x, y| {
+        let chroma = x as f32 / width as f32 * MAX_OKLCH_CHROMA;
+        let lightness = 1.0 - y as f32 / height as f32;
+        let grey = ((0.85 + lightness * 0.1) * 255.0) as u8;
+        let mask = [grey, grey, grey, 255];
+        let covered = edges[y].iter().map(|&max| coverage(max, chroma, step)).sum::<f32>() / EDGE_SAMPLES as f32;
+        if covered <= 0.0 {
+            return mask;
+        }
+        let color = Color::from_oklcha_f32(lightness, chroma.min(edges[y][0]), hue, 1.0).pixel();
+        blend(mask, color, covered)
+    })
+}
+
+/// OKLCH chroma up a slider at `lightness` and `hue`, with what sRGB can't
+/// show as flat grey.
+pub fn chroma_strip(buf: &mut Vec<u8>, width: usize, height: usize, lightness: f32, hue: f32) {
+    let step = MAX_OKLCH_CHROMA / height as f32;
+    let max = max_oklch_chroma(lightness, hue);
+    let grey = Color::from_oklcha_f32(lightness, 0.0, hue, 1.0).pixel();
+    draw(buf, width, height, |_x, y| {
+        // the top of the pixel, so it spans down to a step less
+        let chroma = (1.0 - y as f32 / height as f32) * MAX_OKLCH_CHROMA;
+        let color = Color::from_oklcha_f32(lightness, chroma.min(max), hue, 1.0).pixel();
+        blend(grey, color, coverage(max, chroma - step, step))
+    })
+}
+
+/// How much of a pixel spanning `step` chroma from `chroma` is below `max`.
+fn coverage(max: f32, chroma: f32, step: f32) -> f32 {
+    ((max - chroma) / step).clamp(0.0, 1.0)
+}
+
+/// `from` blended `t` (0..1) of the way to `to`.
+fn blend(from: [u8; 4], to: [u8; 4], t: f32) -> [u8; 4] {
+    [0, 1, 2, 3].map(|i| (from[i] as f32 + (to[i] as f32 - from[i] as f32) * t).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::{chroma_lightness, chroma_strip, draw, sat_value};
+    use crate::color::Color;
+
+    /// Compares an RGBA buffer to a binary PPM in `src/golden`, allowing a
+    /// step either way per channel for float differences. Run with
+    /// `UPDATE_GOLDEN=1` to write the files instead.
+    fn golden(name: &str, width: usize, height: usize, buf: &[u8]) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/golden").join(name);
+        let rgb: Vec<u8> = buf.chunks(4).flat_map(|px| px[..3].to_vec()).collect();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+            ppm.extend(&rgb);
+            fs::write(&path, ppm).unwrap();
+            return;
+        }
+        let ppm = fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let header = format!("P6\n{} {}\n255\n", width, height);
+        assert!(ppm.starts_with(header.as_bytes()), "{}: size differs", name);
+        let expected = &ppm[header.len()..];
+        assert_eq!(expected.len(), rgb.len());
+        for (ix, (a, b)) in expected.iter().zip(&rgb).enumerate() {
+            let px = ix / 3;
+            assert!((*a as i16 - *b as i16).abs() <= 1, "{}: pixel ({}, {}) differs", name, px % width, px / width);
+        }
+    }
+
+    #[test]
+    fn gamut_edges() {
+        let mut buf = Vec::new();
+        chroma_lightness(&mut buf, 48, 48, 0.75);
+        golden("chroma_lightness.ppm", 48, 48, &buf);
+        chroma_strip(&mut buf, 4, 48, 0.6, 0.75);
+        golden("chroma_strip.ppm", 4, 48, &buf);
+    }
+
+    #[test]
+    fn sat_value_matches_scalar() {
+        let (mut simd, mut scalar) = (Vec::new(), Vec::new());
+        // widths off the lane count, and hues at and between sector edges
+        for &(width, height) in &[(37, 20), (64, 64)] {
+            for &hue in &[0.0, 0.1, 1.0 / 6.0, 0.5, 0.83, 0.99] {
+                sat_value(&mut simd, width, height, hue, 1.5);
+                draw(&mut scalar, width, height, |x, y| {
+                    let sat = x as f32 / width as f32;
+                    let value = (1.0 - y as f32 / height as f32) * 1.5;
+                    Color::from_hsva_f32(hue, sat, value, 1.0).pixel()
+                });
+                assert!(simd == scalar, "{}x{} at hue {}", width, height, hue);
+            }
+        }
+    }
+
+    #[test]
+    fn coverage() {
+        assert_eq!(super::coverage(0.1, 0.2, 0.01), 0.0);
+        assert_eq!(super::coverage(0.1, 0.05, 0.01), 1.0);
+        assert!((super::coverage(0.1, 0.0975, 0.01) - 0.25).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,349 @@
+//! User-defined output templates.
+//!
+//! Text outside braces is copied verbatim (`{{` and `}}` escape literal
+//! braces). Inside braces is an arithmetic expression over the color's
+//! channels, optionally followed by `|filter` steps:
+//!
+//! ```text
+//! 0x{b*255|hex}{g*255|hex}{r*255|hex}     -> 0xff6633 (#3366ff as BGR)
+//! {r*65535|round}, {g*65535|round}         -> 13107, 26214
+//! hue {h|deg|round}deg                     -> hue 225deg
+//! ```
+//!
+//! Variables (all 0..1): `r g b a` (RGB + alpha), `h s v` (HSV), `l sl`
+//! (HSL lightness and saturation).
+//!
+//! Filters: `round`, `floor`, `ceil`, `deg` (×360), `pct` (×100), and the
+//! final formatting filters `hex[:width]` (rounded, zero-padded, default 2)
+//! and `fixed:n` (n decimal places).
+
+use std::str::FromStr;
+
+use crate::color::Color;
+
+const VARIABLES: &[&str] = &["r", "g", "b", "a", "h", "s", "v", "l", "sl"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Expr(Expr, Vec<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Num(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Bin(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Filter {
+    Round,
+    Floor,
+    Ceil,
+    Deg,
+    Pct,
+    Hex(usize),
+    Fixed(usize),
+}
+
+impl Template {
+    pub fn render(&self, color: &Color) -> String {
+        let (_, hsl_s, l) = color.to_hsl();
+        let lookup = |name: &str| -> f64 {
+            (match name {
+                "r" => color.red(),
+                "g" => color.green(),
+                "b" => color.blue(),
+                "a" => color.alpha(),
+                "h" => color.hue(),
+                "s" => color.saturation(),
+                "v" => color.value(),
+                "l" => l,
+                "sl" => hsl_s,
+                _ => 0.0,
+            }) as f64
+        };
+
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => out.push_str(s),
+                Part::Expr(expr, filters) => out.push_str(&apply(expr.eval(&lookup), filters)),
+            }
+        }
+        out
+    }
+}
+
+impl Expr {
+    fn eval(&self, lookup: &impl Fn(&str) -> f64) -> f64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Var(name) => lookup(name),
+            Expr::Neg(e) => -e.eval(lookup),
+            Expr::Bin(l, op, r) => {
+                let (l, r) = (l.eval(lookup), r.eval(lookup));
+                match op {
+                    Op::Add => l + r,
+                    Op::Sub => l - r,
+                    Op::Mul => l * r,
+                    Op::Div => l / r,
+                }
+            }
+        }
+    }
+}
+
+fn apply(mut x: f64, filters: &[Filter]) -> String {
+    for filter in filters {
+        match filter {
+            Filter::Round => x = x.round(),
+            Filter::Floor => x = x.floor(),
+            Filter::Ceil => x = x.ceil(),
+            Filter::Deg => x *= 360.0,
+            Filter::Pct => x *= 100.0,
+            Filter::Hex(width) => return format!("{:0width$x}", x.round().max(0.0) as u64, width = width),
+            Filter::Fixed(places) => return format!("{:.places$}", x, places = places),
+        }
+    }
+    // plain numbers print without float noise: at most 4 decimals, trimmed
+    let s = format!("{:.4}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_string() } else { s.to_string() }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().map(|&(_, c)| c) == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let end = s[i..].find('}')
+                        .map(|e| i + e)
+                        .ok_or_else(|| format!("unclosed '{{' at {}", i))?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(parse_placeholder(&s[i + 1..end])?);
+                    while chars.peek().map_or(false, |&(j, _)| j <= end) {
+                        chars.next();
+                    }
+                }
+                '}' => return Err(format!("unmatched '}}' at {}", i)),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+}
+
+fn parse_placeholder(s: &str) -> Result<Part, String> {
+    let mut pieces = s.split('|');
+    let expr = pieces.next().unwrap_or("");
+    let mut parser = Parser { tokens: tokenize(expr)?, pos: 0 };
+    let expr = parser.expr()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(format!("unexpected {:?} in {{{}}}", parser.tokens[parser.pos], s));
+    }
+
+    let filters = pieces.map(parse_filter).collect::<Result<Vec<_>, _>>()?;
+    if let Some(ix) = filters.iter().position(|f| matches!(f, Filter::Hex(_) | Filter::Fixed(_))) {
+        if ix != filters.len() - 1 {
+            return Err(format!("hex and fixed must be the last filter in {{{}}}", s));
+        }
+    }
+    Ok(Part::Expr(expr, filters))
+}
+
+fn parse_filter(s: &str) -> Result<Filter, String> {
+    let mut split = s.trim().splitn(2, ':');
+    let name = split.next().unwrap_or("");
+    let arg = split.next()
+        .map(|a| a.trim().parse::<usize>().map_err(|_| format!("invalid argument to {}: {}", name, a)))
+        .transpose()?;
+    match (name, arg) {
+        ("round", None) => Ok(Filter::Round),
+        ("floor", None) => Ok(Filter::Floor),
+        ("ceil", None) => Ok(Filter::Ceil),
+        ("deg", None) => Ok(Filter::Deg),
+        ("pct", None) => Ok(Filter::Pct),
+        ("hex", width) => Ok(Filter::Hex(width.unwrap_or(2))),
+        ("fixed", Some(places)) => Ok(Filter::Fixed(places)),
+        ("fixed", None) => Err("fixed needs a number of places, e.g. fixed:3".to_string()),
+        (name, _) => Err(format!("unknown filter: {}", name)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Sym(char),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut num = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                num.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Num(num.parse().map_err(|_| format!("invalid number: {}", num))?));
+        } else if c.is_ascii_alphabetic() {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphanumeric()) {
+                ident.push(c);
+                chars.next();
+            }
+            if !VARIABLES.contains(&ident.as_str()) {
+                return Err(format!("unknown variable: {}", ident));
+            }
+            tokens.push(Token::Ident(ident));
+        } else if "+-*/()".contains(c) {
+            tokens.push(Token::Sym(c));
+            chars.next();
+        } else {
+            return Err(format!("unexpected character: {}", c));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_sym(&self) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Sym(c)) => Some(*c),
+            _ => None,
+        }
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        while let Some(op) = self.peek_sym().and_then(|c| match c {
+            '+' => Some(Op::Add),
+            '-' => Some(Op::Sub),
+            _ => None,
+        }) {
+            self.pos += 1;
+            lhs = Expr::Bin(Box::new(lhs), op, Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    // term := factor (('*' | '/') factor)*
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.factor()?;
+        while let Some(op) = self.peek_sym().and_then(|c| match c {
+            '*' => Some(Op::Mul),
+            '/' => Some(Op::Div),
+            _ => None,
+        }) {
+            self.pos += 1;
+            lhs = Expr::Bin(Box::new(lhs), op, Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    // factor := '-' factor | number | variable | '(' expr ')'
+    fn factor(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Ident(name)) => Ok(Expr::Var(name)),
+            Some(Token::Sym('-')) => Ok(Expr::Neg(Box::new(self.factor()?))),
+            Some(Token::Sym('(')) => {
+                let inner = self.expr()?;
+                if self.peek_sym() != Some(')') {
+                    return Err("expected ')'".to_string());
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(t) => Err(format!("unexpected {:?}", t)),
+            None => Err("expected an expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+    use crate::color::Color;
+
+    fn render(template: &str, color: &Color) -> String {
+        template.parse::<Template>().unwrap().render(color)
+    }
+
+    #[test]
+    fn channels_and_filters() {
+        let c = Color::from_rgba_f32(0.2, 0.4, 1.0, 0.5);
+        assert_eq!(render("0x{b*255|hex}{g*255|hex}{r*255|hex}", &c), "0xff6633");
+        assert_eq!(render("{r*65535|round}", &c), "13107");
+        assert_eq!(render("{h|deg|round}deg", &c), "225deg");
+        assert_eq!(render("{a|pct}%", &c), "50%");
+        assert_eq!(render("{r|fixed:3} {(r + g) / 2}", &c), "0.200 0.3");
+        assert_eq!(render("{-(1 - r*2)}", &c), "-0.6");
+        assert_eq!(render("{r*255|hex:4}", &c), "0033");
+    }
+
+    #[test]
+    fn literals_and_escapes() {
+        let c = Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0);
+        assert_eq!(render("Color({{ {r} }})", &c), "Color({ 1 })");
+        assert_eq!(render("plain", &c), "plain");
+    }
+
+    #[test]
+    fn errors() {
+        assert!("{x}".parse::<Template>().is_err());
+        assert!("{r".parse::<Template>().is_err());
+        assert!("r}".parse::<Template>().is_err());
+        assert!("{r|bogus}".parse::<Template>().is_err());
+        assert!("{r|hex|round}".parse::<Template>().is_err());
+        assert!("{(r}".parse::<Template>().is_err());
+        assert!("{r r}".parse::<Template>().is_err());
+        assert!("{r|fixed}".parse::<Template>().is_err());
+    }
+}
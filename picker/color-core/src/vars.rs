@@ -0,0 +1,75 @@
+use std::{fmt::Display, str::FromStr};
+
+#[cfg(feature = "druid")]
+use druid::Data;
+
+/// Which stylesheet language variable declarations are written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "druid", derive(Data))]
+pub enum VarSyntax {
+    Css,
+    Scss,
+    Less,
+}
+impl VarSyntax {
+    /// `--name: value;`, `$name: value;` or `@name: value;`.
+    pub fn declare(self, name: &str, value: &str) -> String {
+        let sigil = match self {
+            Self::Css => "--",
+            Self::Scss => "$",
+            Self::Less => "@",
+        };
+        format!("{}{}: {};", sigil, slug(name), value)
+    }
+}
+impl FromStr for VarSyntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "css" => Ok(VarSyntax::Css),
+            "scss" => Ok(VarSyntax::Scss),
+            "less" => Ok(VarSyntax::Less),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for VarSyntax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Css => "css",
+            Self::Scss => "scss",
+            Self::Less => "less",
+        })
+    }
+}
+
+/// Turns a free-form name into a stylesheet identifier: `Brand Blue`,
+/// `--brand-blue` and `$brand_blue` all become `brand-blue`.
+pub fn slug(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VarSyntax;
+
+    #[test]
+    fn slug() {
+        assert_eq!(super::slug("Brand Blue"), "brand-blue");
+        assert_eq!(super::slug("--brand-blue"), "brand-blue");
+        assert_eq!(super::slug("$brand_blue"), "brand-blue");
+        assert_eq!(super::slug("  accent (dark) "), "accent-dark");
+        assert_eq!(VarSyntax::Css.declare("Cornflower Blue", "#6495ed"), "--cornflower-blue: #6495ed;");
+        assert_eq!(VarSyntax::Scss.declare("brand", "#aabbcc"), "$brand: #aabbcc;");
+        assert_eq!(VarSyntax::Less.declare("@brand", "#aabbcc"), "@brand: #aabbcc;");
+    }
+}
@@ -0,0 +1,34 @@
+/* C API of the color-core conversions; see src/lib.rs. */
+#ifndef COLOR_FFI_H
+#define COLOR_FFI_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Channels 0..1; r, g and b may exceed 1 for extended-range input. */
+typedef struct {
+    float r;
+    float g;
+    float b;
+    float a;
+} FfiColor;
+
+/* Returns 1 and fills `out` if `input` parses, else 0. */
+int parse_color(const char *input, FfiColor *out);
+
+/* `color` in `format` (rgb, hex, hsl, hsv, vec, ref, bgr, srgb, 256, esc,
+ * css, scss or less), or NULL for an unknown format. */
+char *format_color(const FfiColor *color, const char *format);
+
+/* parse_color then format_color; NULL if either fails. */
+char *convert(const char *input, const char *format);
+
+/* Frees a string returned by format_color or convert. */
+void free_color_string(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,140 @@
+//! A C API over color-core, so other processes' conversions can run in
+//! process instead of spawning the picker. The declarations are in
+//! `color_ffi.h`; strings returned here must go back to `free_color_string`.
+
+use std::{ffi::{CStr, CString}, os::raw::{c_char, c_int}, ptr};
+
+use color_core::{Color, Format, FormatOptions};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FfiColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl From<&Color> for FfiColor {
+    fn from(c: &Color) -> Self {
+        FfiColor { r: c.red(), g: c.green(), b: c.blue(), a: c.alpha() }
+    }
+}
+
+impl From<&FfiColor> for Color {
+    fn from(c: &FfiColor) -> Self {
+        Color::from_rgba_f32(c.r, c.g, c.b, c.a)
+    }
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+fn into_raw(s: String) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Returns 1 and fills `out` if `input` parses, else 0.
+///
+/// # Safety
+///
+/// `input` must be null or a NUL-terminated string, and `out` null or valid
+/// for writes.
+#[no_mangle]
+pub unsafe extern "C" fn parse_color(input: *const c_char, out: *mut FfiColor) -> c_int {
+    match to_str(input).map(color_core::parse) {
+        Some(Ok((color, _))) if !out.is_null() => {
+            *out = FfiColor::from(&color);
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// `color` in `format` (a lowercase button label), or null if the format is
+/// unknown.
+///
+/// # Safety
+///
+/// `color` must be null or point to a color, and `format` null or a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn format_color(color: *const FfiColor, format: *const c_char) -> *mut c_char {
+    let format = match to_str(format).map(str::parse::<Format>) {
+        Some(Ok(format)) => format,
+        _ => return ptr::null_mut(),
+    };
+    match color.as_ref() {
+        Some(color) => into_raw(format.format(&Color::from(color), &FormatOptions::default())),
+        None => ptr::null_mut(),
+    }
+}
+
+/// [`parse_color`] then [`format_color`]; null if either fails.
+///
+/// # Safety
+///
+/// Both arguments must be null or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn convert(input: *const c_char, format: *const c_char) -> *mut c_char {
+    let mut color = FfiColor { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
+    if parse_color(input, &mut color) == 0 {
+        return ptr::null_mut();
+    }
+    format_color(&color, format)
+}
+
+/// # Safety
+///
+/// `s` must be null or a string from [`format_color`] or [`convert`] that
+/// hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn free_color_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::{CStr, CString}, ptr};
+
+    use super::{convert, format_color, free_color_string, parse_color, FfiColor};
+
+    fn take(s: *mut std::os::raw::c_char) -> Option<String> {
+        if s.is_null() {
+            return None;
+        }
+        let out = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+        unsafe { free_color_string(s) };
+        Some(out)
+    }
+
+    #[test]
+    fn round_trip() {
+        let input = CString::new("#ff8000").unwrap();
+        let mut color = FfiColor { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
+        assert_eq!(unsafe { parse_color(input.as_ptr(), &mut color) }, 1);
+        assert_eq!(color, FfiColor { r: 1.0, g: 128.0 / 255.0, b: 0.0, a: 1.0 });
+
+        let rgb = CString::new("rgb").unwrap();
+        assert_eq!(take(unsafe { format_color(&color, rgb.as_ptr()) }).as_deref(), Some("rgb(255, 128, 0)"));
+        assert_eq!(take(unsafe { convert(input.as_ptr(), rgb.as_ptr()) }).as_deref(), Some("rgb(255, 128, 0)"));
+    }
+
+    #[test]
+    fn failures() {
+        let bad = CString::new("nope").unwrap();
+        let hex = CString::new("hex").unwrap();
+        let mut color = FfiColor { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
+        assert_eq!(unsafe { parse_color(bad.as_ptr(), &mut color) }, 0);
+        assert_eq!(unsafe { parse_color(ptr::null(), &mut color) }, 0);
+        assert_eq!(take(unsafe { convert(bad.as_ptr(), hex.as_ptr()) }), None);
+        assert_eq!(take(unsafe { format_color(&color, bad.as_ptr()) }), None);
+        assert_eq!(take(unsafe { format_color(ptr::null(), hex.as_ptr()) }), None);
+    }
+}
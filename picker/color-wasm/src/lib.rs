@@ -0,0 +1,29 @@
+//! color-core's conversions for JavaScript, mirroring the C API in
+//! color-ffi. Build with `wasm-pack build --target web`.
+
+use wasm_bindgen::prelude::*;
+
+use color_core::{parse, Color, Format, FormatOptions};
+
+/// `[r, g, b, a]`, each 0..1, or `undefined` if `input` doesn't parse.
+#[wasm_bindgen(js_name = parseColor)]
+pub fn parse_color(input: &str) -> Option<Vec<f32>> {
+    let (c, _) = parse(input).ok()?;
+    Some(vec![c.red(), c.green(), c.blue(), c.alpha()])
+}
+
+/// The color in `format` (a lowercase button label such as `rgb` or `hex`),
+/// or `undefined` for an unknown format.
+#[wasm_bindgen(js_name = formatColor)]
+pub fn format_color(r: f32, g: f32, b: f32, a: f32, format: &str) -> Option<String> {
+    let format: Format = format.parse().ok()?;
+    Some(format.format(&Color::from_rgba_f32(r, g, b, a), &FormatOptions::default()))
+}
+
+/// `parseColor` then `formatColor`.
+#[wasm_bindgen]
+pub fn convert(input: &str, format: &str) -> Option<String> {
+    let format: Format = format.parse().ok()?;
+    let (color, _) = parse(input).ok()?;
+    Some(format.format(&color, &FormatOptions::default()))
+}
@@ -0,0 +1,90 @@
+use std::{fmt::Display, str::FromStr};
+
+use log::{info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Auto,
+    X11,
+    Wayland,
+    /// An egui window instead of druid's, see `egui_ui`.
+    #[cfg(feature = "egui")]
+    Egui,
+}
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Backend::Auto),
+            "x11" => Ok(Backend::X11),
+            "wayland" => Ok(Backend::Wayland),
+            #[cfg(feature = "egui")]
+            "egui" => Ok(Backend::Egui),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Auto => "auto",
+            Self::X11 => "x11",
+            Self::Wayland => "wayland",
+            #[cfg(feature = "egui")]
+            Self::Egui => "egui",
+        })
+    }
+}
+impl Backend {
+    /// The backends this platform can choose between.
+    pub fn values() -> Vec<Backend> {
+        if cfg!(target_os = "linux") {
+            vec![Backend::Auto, Backend::X11, Backend::Wayland, #[cfg(feature = "egui")] Backend::Egui]
+        } else {
+            vec![Backend::Auto, #[cfg(feature = "egui")] Backend::Egui]
+        }
+    }
+}
+
+/// Picks the windowing backend before the toolkit initializes, and reports
+/// whether the window may be placed at absolute screen coordinates.
+///
+/// Wayland does not let clients position their own windows, so under `auto`
+/// we prefer XWayland when it's available and otherwise let the compositor
+/// place (usually center) the window.
+#[cfg(target_os = "linux")]
+pub fn select(backend: Backend) -> bool {
+    let has_x11 = std::env::var_os("DISPLAY").is_some();
+    let has_wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let forced = std::env::var("GDK_BACKEND").ok();
+
+    let chosen = match (backend, forced.as_deref()) {
+        (Backend::X11, _) => Backend::X11,
+        (Backend::Wayland, _) => Backend::Wayland,
+        (Backend::Auto, Some(b)) if b.starts_with("x11") => Backend::X11,
+        (Backend::Auto, Some(b)) if b.starts_with("wayland") => Backend::Wayland,
+        (Backend::Auto, _) if has_x11 => Backend::X11,
+        (Backend::Auto, _) if has_wayland => Backend::Wayland,
+        (Backend::Auto, _) => Backend::X11,
+        // winit picks for itself
+        #[cfg(feature = "egui")]
+        (Backend::Egui, _) => return true,
+    };
+
+    if chosen == Backend::X11 && !has_x11 {
+        warn!("x11 backend requested but DISPLAY is not set");
+    }
+    std::env::set_var("GDK_BACKEND", chosen.to_string());
+    info!("using {} backend", chosen);
+
+    chosen == Backend::X11
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn select(backend: Backend) -> bool {
+    if backend != Backend::Auto {
+        warn!("--backend {} is only meaningful on linux, ignoring", backend);
+    }
+    true
+}
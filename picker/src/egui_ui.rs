@@ -0,0 +1,221 @@
+//! The picker in an egui window, for `--backend egui` where druid has
+//! trouble on a platform, e.g. under Wayland or on macOS ARM.
+//!
+//! It keeps the contract with the extension: Enter or a click on the swatch
+//! prints the color in its format and exits, Escape exits without, and
+//! `--json` and `--continuous` print as usual. Clicking the initial swatch
+//! resets to it. Only the surface, hue and alpha sliders, swatches and
+//! format buttons are here; the panels, strips, `--indexed` and history
+//! are druid's.
+
+use eframe::egui::{self, Align2, Color32, ColorImage, FontId, Key, Pos2, Rect, Sense, Stroke, TextureHandle, TextureOptions, Ui, Vec2};
+use log::{debug, info, warn};
+
+use color_core::surface;
+
+use crate::color::Color;
+use crate::{Args, ColorFormat, Format};
+
+pub fn run(args: &Args, color: ColorFormat) -> Result<(), String> {
+    let scale = args.scale.clamp(0.5, 4.0) as f32;
+    let size = Vec2::new(PICKER + SLIDER * 2.0 + PADDING * 4.0, PICKER + 150.0) * scale;
+    // under the anchor, centered; egui can't tell whether it fits there
+    let (x, y) = args.anchor.as_ref().map_or((args.x, args.y), |anchor| (anchor.0.center().x, anchor.0.y1));
+    let options = eframe::NativeOptions {
+        initial_window_size: Some(size),
+        initial_window_pos: Some(Pos2::new(x as f32 - size.x / 2.0, y as f32)),
+        decorated: false,
+        resizable: false,
+        ..Default::default()
+    };
+    let picker = Picker {
+        formats: Format::values(&color.options),
+        // as with druid, only changes are printed
+        last_printed: color.to_string(),
+        initial: color.clone(),
+        current: color,
+        json: args.json,
+        continuous: args.continuous,
+        scale,
+        buf: Vec::new(),
+        textures: [None, None, None],
+    };
+    info!("using egui backend");
+    eframe::run_native("Color Picker", options, Box::new(|_cc| Box::new(picker)))
+        .map_err(|e| e.to_string())
+}
+
+const PICKER: f32 = 198.0;
+const SLIDER: f32 = 18.0;
+const PADDING: f32 = 10.0;
+
+struct Picker {
+    initial: ColorFormat,
+    current: ColorFormat,
+    formats: Vec<Format>,
+    json: bool,
+    continuous: bool,
+    last_printed: String,
+    scale: f32,
+    /// Kept across frames, as druid's widgets keep theirs.
+    buf: Vec<u8>,
+    /// The surface's, hue slider's and alpha slider's.
+    textures: [Option<TextureHandle>; 3],
+}
+
+impl eframe::App for Picker {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let (commit, abort) = ctx.input(|i| (i.key_pressed(Key::Enter), i.key_pressed(Key::Escape)));
+        let mut commit_clicked = false;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.spacing_mut().item_spacing = Vec2::splat(PADDING * self.scale);
+            ui.horizontal(|ui| {
+                self.surface(ui);
+                self.hue_slider(ui);
+                self.alpha_slider(ui);
+            });
+            commit_clicked = self.swatches(ui);
+            ui.horizontal_wrapped(|ui| {
+                for format in &self.formats {
+                    ui.selectable_value(&mut self.current.format, format.clone(), format.to_string());
+                }
+            });
+        });
+
+        if self.continuous {
+            let line = self.current.to_string();
+            if self.last_printed != line {
+                self.current.emit(self.json);
+                self.last_printed = line;
+            }
+        }
+        if commit || commit_clicked {
+            info!("commit {}", self.current);
+            self.current.emit(self.json);
+            frame.close();
+        } else if abort {
+            info!("abort");
+            frame.close();
+        }
+    }
+}
+
+impl Picker {
+    /// Saturation across and value down, at the hue.
+    fn surface(&mut self, ui: &mut Ui) {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(PICKER * self.scale), Sense::click_and_drag());
+        let color = &mut self.current.color;
+        let (width, height) = pixels(ui, rect);
+        surface::sat_value(&mut self.buf, width, height, color.hue(), 1.0);
+        paint_buffer(ui, &mut self.textures[0], rect, width, height, &self.buf);
+        if let Some(pos) = response.interact_pointer_pos() {
+            color.set_saturation(((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0));
+            color.set_value(1.0 - ((pos.y - rect.top()) / rect.height()).clamp(0.0, 1.0));
+        }
+        let marker = Pos2::new(rect.left() + color.saturation() * rect.width(), rect.top() + (1.0 - color.value()) * rect.height());
+        ui.painter().circle_stroke(marker, 4.0 * self.scale, Stroke::new(1.5, contrasting(color)));
+    }
+
+    fn hue_slider(&mut self, ui: &mut Ui) {
+        let (rect, response) = ui.allocate_exact_size(Vec2::new(SLIDER, PICKER) * self.scale, Sense::click_and_drag());
+        let color = &mut self.current.color;
+        let (width, height) = pixels(ui, rect);
+        let (s, v) = (color.saturation(), color.value());
+        surface::draw(&mut self.buf, width, height, |_x, y| Color::from_hsva_f32(y as f32 / height as f32, s, v, 1.0).pixel());
+        paint_buffer(ui, &mut self.textures[1], rect, width, height, &self.buf);
+        if let Some(pos) = response.interact_pointer_pos() {
+            color.set_hue(((pos.y - rect.top()) / rect.height()).clamp(0.0, 1.0));
+        }
+        mark_row(ui, rect, color.hue());
+    }
+
+    /// Opaque at the top, over checkers.
+    fn alpha_slider(&mut self, ui: &mut Ui) {
+        let (rect, response) = ui.allocate_exact_size(Vec2::new(SLIDER, PICKER) * self.scale, Sense::click_and_drag());
+        let color = &mut self.current.color;
+        let (width, height) = pixels(ui, rect);
+        let [r, g, b, _] = color.pixel();
+        let checker = (SLIDER / 4.0 * ui.ctx().pixels_per_point()).max(1.0) as usize;
+        surface::draw(&mut self.buf, width, height, |x, y| {
+            let alpha = 1.0 - y as f32 / height as f32;
+            let bg = if (x / checker + y / checker) % 2 == 0 { 255.0 } else { 204.0 };
+            let over = |c: u8| (bg + (c as f32 - bg) * alpha).round() as u8;
+            [over(r), over(g), over(b), 255]
+        });
+        paint_buffer(ui, &mut self.textures[2], rect, width, height, &self.buf);
+        if let Some(pos) = response.interact_pointer_pos() {
+            color.set_alpha(1.0 - ((pos.y - rect.top()) / rect.height()).clamp(0.0, 1.0));
+        }
+        mark_row(ui, rect, 1.0 - color.alpha());
+    }
+
+    /// The current and initial colors, labelled with their output. Returns
+    /// whether the current one was clicked, to commit.
+    fn swatches(&mut self, ui: &mut Ui) -> bool {
+        let width = ui.available_width();
+        let (current, clicked) = ui.allocate_exact_size(Vec2::new(width, 64.0 * self.scale), Sense::click());
+        swatch(ui, current, &self.current, 16.0 * self.scale);
+        let (initial, reset) = ui.allocate_exact_size(Vec2::new(width, 26.0 * self.scale), Sense::click());
+        let initial_format = ColorFormat { format: self.current.format.clone(), ..self.initial.clone() };
+        swatch(ui, initial, &initial_format, 12.0 * self.scale);
+        if reset.clicked() {
+            debug!("reset to {}", self.initial);
+            self.current.color = self.initial.color.clone();
+        }
+        clicked.clicked()
+    }
+}
+
+/// The size of `rect` in physical pixels, for a buffer that's drawn 1:1.
+fn pixels(ui: &Ui, rect: Rect) -> (usize, usize) {
+    let ppp = ui.ctx().pixels_per_point();
+    ((rect.width() * ppp).round().max(1.0) as usize, (rect.height() * ppp).round().max(1.0) as usize)
+}
+
+/// Paints a buffer from [`surface`] over `rect`, reusing `slot`'s texture.
+fn paint_buffer(ui: &Ui, slot: &mut Option<TextureHandle>, rect: Rect, width: usize, height: usize, buf: &[u8]) {
+    let image = ColorImage::from_rgba_unmultiplied([width, height], buf);
+    match slot {
+        Some(texture) => texture.set(image, TextureOptions::NEAREST),
+        None => *slot = Some(ui.ctx().load_texture("picker", image, TextureOptions::NEAREST)),
+    }
+    if let Some(texture) = slot {
+        let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+        ui.painter().image(texture.id(), rect, uv, Color32::WHITE);
+    }
+}
+
+/// A line across a slider `t` (0..1) of the way down.
+fn mark_row(ui: &Ui, rect: Rect, t: f32) {
+    let y = rect.top() + t * rect.height();
+    ui.painter().hline(rect.x_range(), y, Stroke::new(2.0, Color32::WHITE));
+}
+
+fn swatch(ui: &Ui, rect: Rect, color: &ColorFormat, text_size: f32) {
+    let [r, g, b, a] = color.output().pixel();
+    ui.painter().rect_filled(rect, 0.0, Color32::from_rgba_unmultiplied(r, g, b, a));
+    let text = color.to_string();
+    ui.painter().text(rect.center(), Align2::CENTER_CENTER, text, FontId::monospace(text_size), contrasting(&color.color));
+}
+
+/// Black or white, whichever reads on `color`.
+fn contrasting(color: &Color) -> Color32 {
+    if color.relative_luminance() > 0.4 { Color32::BLACK } else { Color32::WHITE }
+}
+
+/// Says which options only the druid window has, so they aren't silently
+/// dropped.
+pub fn warn_unsupported(args: &Args) {
+    let ignored = [
+        ("--indexed", args.indexed),
+        ("--standalone", args.standalone),
+        ("--stats", args.stats),
+        ("--derive", !args.derive.is_empty()),
+        ("--white-points", args.white_points),
+    ];
+    for (flag, set) in ignored.iter() {
+        if *set {
+            warn!("{} isn't supported with --backend egui, ignoring", flag);
+        }
+    }
+}
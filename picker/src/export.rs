@@ -0,0 +1,122 @@
+pub mod preview;
+pub mod terminal;
+
+use std::{error::Error, path::{Path, PathBuf}, str::FromStr};
+#[cfg(feature = "image")]
+use std::{fs::File, io::BufWriter};
+
+use crate::color::{Color, Depth, HexShort};
+
+/// Where `--export-swatch` writes, parsed from `path.png[:size]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwatchTarget {
+    pub path: PathBuf,
+    /// Width and height in pixels.
+    pub size: u32,
+}
+
+impl FromStr for SwatchTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // only a trailing number counts as the size, so `C:\a.png` is a path
+        let (path, size) = match s.rfind(':') {
+            Some(ix) if s[ix + 1..].chars().all(|c| c.is_ascii_digit()) && ix + 1 < s.len() => {
+                let size = s[ix + 1..].parse::<u32>().map_err(|e| format!("Invalid size: {}", e))?;
+                (&s[..ix], size)
+            }
+            _ => (s, 1),
+        };
+        if path.is_empty() {
+            return Err("Missing path".to_string());
+        }
+        if size == 0 || size > 4096 {
+            return Err(format!("Invalid size: {} (1 to 4096)", size));
+        }
+        Ok(SwatchTarget { path: PathBuf::from(path), size })
+    }
+}
+
+impl SwatchTarget {
+    /// `rrggbb.png` in the user's pictures folder, for exports without
+    /// `--export-swatch`.
+    pub fn default_for(color: &Color) -> Self {
+        let name = format!("{}.png", color.to_hex_string(HexShort::Never).trim_start_matches('#'));
+        let dir = dirs::picture_dir().unwrap_or_else(|| PathBuf::from("."));
+        SwatchTarget { path: dir.join(name), size: 1 }
+    }
+
+    pub fn write(&self, color: &Color, depth: Depth) -> Result<(), Box<dyn Error>> {
+        write_swatch(&self.path, self.size, color, depth)
+    }
+}
+
+/// Writes a solid `size`×`size` PNG, with 16-bit channels beyond `--depth 8`.
+#[cfg(feature = "image")]
+pub fn write_swatch(path: &Path, size: u32, color: &Color, depth: Depth) -> Result<(), Box<dyn Error>> {
+    let pixels = (size * size) as usize;
+    let (bit_depth, data) = match depth {
+        Depth::Eight => (png::BitDepth::Eight, color.pixel().repeat(pixels)),
+        _ => {
+            let px: Vec<u8> = color.pixel16().iter().flat_map(|c| c.to_be_bytes().to_vec()).collect();
+            (png::BitDepth::Sixteen, px.repeat(pixels))
+        }
+    };
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), size, size);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(bit_depth);
+    encoder.write_header()?.write_image_data(&data)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "image"))]
+pub fn write_swatch(_path: &Path, _size: u32, _color: &Color, _depth: Depth) -> Result<(), Box<dyn Error>> {
+    Err("built without the image feature".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::SwatchTarget;
+
+    #[test]
+    fn parse_target() {
+        let t = |path: &str, size| SwatchTarget { path: PathBuf::from(path), size };
+        assert_eq!("a.png".parse(), Ok(t("a.png", 1)));
+        assert_eq!("out/a.png:64".parse(), Ok(t("out/a.png", 64)));
+        assert_eq!("C:\\a.png".parse(), Ok(t("C:\\a.png", 1)));
+        assert_eq!("C:\\a.png:8".parse(), Ok(t("C:\\a.png", 8)));
+        assert!("a.png:0".parse::<SwatchTarget>().is_err());
+        assert!(":8".parse::<SwatchTarget>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn writes_solid_png() {
+        use std::fs::File;
+
+        use super::write_swatch;
+        use crate::color::{Color, Depth};
+
+        let color = Color::from_rgba_f32(1.0, 0.5, 0.0, 0.5);
+        let path = std::env::temp_dir().join(format!("swatch-test-{}.png", std::process::id()));
+
+        for (depth, expected) in [
+            (Depth::Eight, vec![255, 128, 0, 128]),
+            (Depth::Sixteen, vec![0xff, 0xff, 0x80, 0x00, 0, 0, 0x80, 0x00]),
+        ].iter() {
+            write_swatch(&path, 3, &color, *depth).unwrap();
+            let mut decoder = png::Decoder::new(File::open(&path).unwrap());
+            decoder.set_transformations(png::Transformations::IDENTITY);
+            let (info, mut reader) = decoder.read_info().unwrap();
+            let mut buf = vec![0; info.buffer_size()];
+            reader.next_frame(&mut buf).unwrap();
+            assert_eq!((info.width, info.height), (3, 3));
+            assert_eq!(buf.len(), expected.len() * 9);
+            assert!(buf.chunks(expected.len()).all(|px| px == &expected[..]));
+        }
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,90 @@
+//! `--preview-frames`: a small PNG of the color as it's edited, for callers
+//! that show it inline rather than read stdout. Each frame replaces
+//! `preview.png` in the directory whole, so a reader never sees half of one.
+
+use std::{error::Error, fs, path::{Path, PathBuf}, sync::{Arc, Condvar, Mutex}, thread, time::Duration};
+
+use log::warn;
+
+use super::write_swatch;
+use crate::color::{Color, Depth};
+
+pub const FILE_NAME: &str = "preview.png";
+
+/// Width and height of a frame in pixels.
+const SIZE: u32 = 16;
+
+/// Least time between frames, however fast the color changes.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// The color waiting for the next frame, if it's changed since the last.
+type Pending = Arc<(Mutex<Option<Color>>, Condvar)>;
+
+pub struct PreviewFrames {
+    pending: Pending,
+}
+
+impl PreviewFrames {
+    /// Creates `dir` if need be, and writes frames into it from a background
+    /// thread, starting with `color`.
+    pub fn start(dir: PathBuf, color: &Color) -> Result<Self, String> {
+        fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        let pending: Pending = Arc::new((Mutex::new(Some(color.clone())), Condvar::new()));
+        let frames = PreviewFrames { pending: pending.clone() };
+        thread::spawn(move || loop {
+            let color = {
+                let (lock, changed) = &*pending;
+                let mut next = lock.lock().unwrap();
+                while next.is_none() {
+                    next = changed.wait(next).unwrap();
+                }
+                next.take().unwrap()
+            };
+            if let Err(e) = write_frame(&dir, &color) {
+                warn!("could not write preview frame: {}", e);
+            }
+            thread::sleep(INTERVAL);
+        });
+        Ok(frames)
+    }
+
+    /// Queues `color` for the next frame, in place of one not yet written.
+    pub fn show(&self, color: &Color) {
+        let (lock, changed) = &*self.pending;
+        *lock.lock().unwrap() = Some(color.clone());
+        changed.notify_one();
+    }
+}
+
+/// Writes a frame beside `preview.png` and renames it over.
+fn write_frame(dir: &Path, color: &Color) -> Result<(), Box<dyn Error>> {
+    let partial = dir.join(format!(".{}.{}", FILE_NAME, std::process::id()));
+    write_swatch(&partial, SIZE, color, Depth::Eight)?;
+    fs::rename(&partial, dir.join(FILE_NAME))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    #[cfg(feature = "image")]
+    fn replaces_frame() {
+        use super::{write_frame, FILE_NAME};
+        use crate::color::Color;
+
+        let dir = std::env::temp_dir().join(format!("preview-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_frame(&dir, &Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0)).unwrap();
+        write_frame(&dir, &Color::from_rgba_f32(0.0, 0.0, 1.0, 1.0)).unwrap();
+
+        let mut decoder = png::Decoder::new(std::fs::File::open(dir.join(FILE_NAME)).unwrap());
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let (info, mut reader) = decoder.read_info().unwrap();
+        let mut buf = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buf).unwrap();
+        assert_eq!(&buf[..4], &[0, 0, 255, 255]);
+        // only the frame is left
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
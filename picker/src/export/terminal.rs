@@ -0,0 +1,139 @@
+//! Terminal color scheme fragments. Colors fill the ANSI slots in order
+//! (black, red, green, yellow, blue, magenta, cyan, white, then the bright
+//! variants); slots without a color are left out.
+
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use serde_json::{Map, Value};
+
+use crate::color::{Color, HexShort};
+
+/// Windows Terminal's names for the 16 ANSI slots.
+const SLOT_NAMES: [&str; 16] = [
+    "black", "red", "green", "yellow", "blue", "purple", "cyan", "white",
+    "brightBlack", "brightRed", "brightGreen", "brightYellow", "brightBlue", "brightPurple", "brightCyan", "brightWhite",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemeFormat {
+    Xresources,
+    Iterm2,
+    WindowsTerminal,
+}
+impl FromStr for SchemeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xresources" => Ok(SchemeFormat::Xresources),
+            "iterm2" => Ok(SchemeFormat::Iterm2),
+            "windows-terminal" => Ok(SchemeFormat::WindowsTerminal),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for SchemeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Xresources => "xresources",
+            Self::Iterm2 => "iterm2",
+            Self::WindowsTerminal => "windows-terminal",
+        })
+    }
+}
+
+/// Where `--export-terminal` writes, parsed from `format:path`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemeTarget {
+    pub format: SchemeFormat,
+    pub path: PathBuf,
+}
+impl FromStr for SchemeTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.splitn(2, ':');
+        let format = split.next().unwrap_or("").parse()?;
+        match split.next() {
+            Some(path) if !path.is_empty() => Ok(SchemeTarget { format, path: PathBuf::from(path) }),
+            _ => Err(format!("Missing path, expected {}:<path>", format)),
+        }
+    }
+}
+
+pub fn render(format: SchemeFormat, colors: &[Color]) -> String {
+    let colors = &colors[..colors.len().min(16)];
+    let hex = |c: &Color| c.to_hex_string(HexShort::Never)[..7].to_string();
+    match format {
+        SchemeFormat::Xresources => colors.iter()
+            .enumerate()
+            .map(|(i, c)| format!("*.color{}: {}\n", i, hex(c)))
+            .collect(),
+        SchemeFormat::Iterm2 => {
+            let mut out = String::from("<dict>\n");
+            for (i, c) in colors.iter().enumerate() {
+                out.push_str(&format!("\t<key>Ansi {} Color</key>\n\t<dict>\n", i));
+                out.push_str("\t\t<key>Color Space</key>\n\t\t<string>sRGB</string>\n");
+                for (name, v) in &[("Red", c.red()), ("Green", c.green()), ("Blue", c.blue())] {
+                    out.push_str(&format!("\t\t<key>{} Component</key>\n\t\t<real>{}</real>\n", name, v.clamp(0.0, 1.0)));
+                }
+                out.push_str("\t</dict>\n");
+            }
+            out.push_str("</dict>\n");
+            out
+        }
+        SchemeFormat::WindowsTerminal => {
+            let mut scheme = Map::new();
+            scheme.insert("name".to_string(), Value::from("Color Picker"));
+            for (name, c) in SLOT_NAMES.iter().zip(colors) {
+                scheme.insert(name.to_string(), Value::from(hex(c)));
+            }
+            let mut out = serde_json::to_string_pretty(&Value::Object(scheme)).unwrap_or_default();
+            out.push('\n');
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, SchemeFormat, SchemeTarget};
+    use crate::color::Color;
+
+    fn palette() -> Vec<Color> {
+        vec![
+            Color::from_rgba_f32(0.0, 0.0, 0.0, 1.0),
+            Color::from_rgba_f32(1.0, 0.0, 0.0, 0.5),
+        ]
+    }
+
+    #[test]
+    fn xresources() {
+        assert_eq!(render(SchemeFormat::Xresources, &palette()), "*.color0: #000000\n*.color1: #ff0000\n");
+    }
+
+    #[test]
+    fn iterm2() {
+        let out = render(SchemeFormat::Iterm2, &palette());
+        assert!(out.starts_with("<dict>\n\t<key>Ansi 0 Color</key>"));
+        assert!(out.contains("<key>Ansi 1 Color</key>\n\t<dict>\n\t\t<key>Color Space</key>\n\t\t<string>sRGB</string>\n\t\t<key>Red Component</key>\n\t\t<real>1</real>"));
+        assert!(!out.contains("Ansi 2 Color"));
+    }
+
+    #[test]
+    fn windows_terminal() {
+        let out: serde_json::Value = serde_json::from_str(&render(SchemeFormat::WindowsTerminal, &palette())).unwrap();
+        assert_eq!(out["black"], "#000000");
+        assert_eq!(out["red"], "#ff0000");
+        assert!(out.get("green").is_none());
+    }
+
+    #[test]
+    fn parse_target() {
+        let target: SchemeTarget = "iterm2:out/scheme.xml".parse().unwrap();
+        assert_eq!(target.format, SchemeFormat::Iterm2);
+        assert_eq!(target.path.to_str(), Some("out/scheme.xml"));
+        assert!("iterm2".parse::<SchemeTarget>().is_err());
+        assert!("kitty:a.conf".parse::<SchemeTarget>().is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use std::{fs, io, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+
+use druid::Data;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::color::{Color, HexShort};
+
+/// How many commits are kept in the history file.
+const MAX_ENTRIES: usize = 200;
+
+/// Colors closer than this (CIE76) are treated as the same color.
+const SAME_COLOR_DELTA_E: f32 = 2.3;
+
+#[derive(Clone, Debug, Data, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// The committed color, as a hex string.
+    pub color: String,
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    /// Where the color was committed, as supplied by the caller via `--context`.
+    #[serde(default)]
+    pub context: Option<String>,
+    /// Set on entries returned from [`History::recent`] for pinned colors.
+    #[serde(skip)]
+    pub pinned: bool,
+}
+
+impl HistoryEntry {
+    pub fn parsed_color(&self) -> Option<Color> {
+        self.color.parse().ok()
+    }
+}
+
+/// Committed colors, oldest first, plus the user's pinned colors.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    #[serde(default)]
+    pinned: Vec<String>,
+}
+
+impl History {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|d| d.join("colorpicker").join("history.json"))
+    }
+
+    /// Loads the history file, treating a missing or unreadable file as empty.
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                warn!("ignoring malformed history file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(e) => {
+                debug!("no history loaded from {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    pub fn record(&mut self, color: &Color, context: Option<String>, timestamp: u64) {
+        self.entries.push(HistoryEntry {
+            color: color.to_hex_string(HexShort::Never),
+            timestamp,
+            context,
+            pinned: false,
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            let excess = self.entries.len() - MAX_ENTRIES;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// All entries, newest first.
+    pub fn newest_first(&self) -> Vec<HistoryEntry> {
+        self.entries.iter().rev().cloned().collect()
+    }
+
+    /// Pins or unpins a color, returning whether it is now pinned.
+    pub fn toggle_pin(&mut self, color: &str) -> bool {
+        if let Some(ix) = self.pinned.iter().position(|c| c == color) {
+            self.pinned.remove(ix);
+            false
+        } else {
+            self.pinned.push(color.to_string());
+            true
+        }
+    }
+
+    /// Up to `n` distinct colors: pinned colors first, in the order they were
+    /// pinned, then the rest ordered by frecency. Near-identical colors are
+    /// merged, represented by their most recent commit.
+    pub fn recent(&self, n: usize, now: u64) -> Vec<HistoryEntry> {
+        let pinned: Vec<HistoryEntry> = self.pinned.iter()
+            .map(|color| {
+                let latest = self.entries.iter().rev().find(|e| &e.color == color);
+                HistoryEntry {
+                    color: color.clone(),
+                    timestamp: latest.map_or(0, |e| e.timestamp),
+                    context: latest.and_then(|e| e.context.clone()),
+                    pinned: true,
+                }
+            })
+            .take(n)
+            .collect();
+        let pinned_colors: Vec<Option<Color>> = pinned.iter().map(|e| e.parsed_color()).collect();
+
+        let mut groups: Vec<(HistoryEntry, Option<Color>, f64)> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if self.pinned.contains(&entry.color) {
+                continue;
+            }
+            let color = entry.parsed_color();
+            if let Some(color) = &color {
+                if pinned_colors.iter().flatten().any(|p| same_color(p, color)) {
+                    continue;
+                }
+            }
+            let score = frecency_weight(now.saturating_sub(entry.timestamp));
+            let existing = groups.iter_mut().find(|(e, c, _)| match (c, &color) {
+                (Some(a), Some(b)) => same_color(a, b),
+                _ => e.color == entry.color,
+            });
+            match existing {
+                Some(group) => group.2 += score,
+                None => groups.push((entry.clone(), color, score)),
+            }
+        }
+        // stable sort, so equal scores stay newest first
+        groups.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+        let rest = n - pinned.len();
+        pinned.into_iter()
+            .chain(groups.into_iter().take(rest).map(|(e, _, _)| e))
+            .collect()
+    }
+}
+
+fn same_color(a: &Color, b: &Color) -> bool {
+    a.pixel()[3] == b.pixel()[3] && a.delta_e(b) < SAME_COLOR_DELTA_E
+}
+
+/// Score contributed by a single commit made `age` seconds ago.
+fn frecency_weight(age: u64) -> f64 {
+    const DAY: u64 = 86_400;
+    match age / DAY {
+        0..=3 => 100.0,
+        4..=13 => 70.0,
+        14..=30 => 50.0,
+        31..=89 => 30.0,
+        _ => 10.0,
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn relative_time(then: u64, now: u64) -> String {
+    let secs = now.saturating_sub(then);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3_599 => format!("{}m ago", secs / 60),
+        3_600..=86_399 => format!("{}h ago", secs / 3_600),
+        86_400..=604_799 => format!("{}d ago", secs / 86_400),
+        604_800..=31_535_999 => format!("{}w ago", secs / 604_800),
+        _ => format!("{}y ago", secs / 31_536_000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+    use crate::color::{Color, HexShort};
+
+    #[test]
+    fn relative_time() {
+        assert_eq!(super::relative_time(100, 130), "just now");
+        assert_eq!(super::relative_time(0, 150), "2m ago");
+        assert_eq!(super::relative_time(0, 3 * 3600 + 5), "3h ago");
+        assert_eq!(super::relative_time(0, 2 * 86_400), "2d ago");
+        assert_eq!(super::relative_time(0, 3 * 604_800), "3w ago");
+        assert_eq!(super::relative_time(0, 2 * 31_536_000), "2y ago");
+        assert_eq!(super::relative_time(200, 100), "just now");
+    }
+
+    #[test]
+    fn recent_merges_similar_colors() {
+        let red = Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0);
+        let almost_red = Color::from_rgba_f32(0.995, 0.005, 0.0, 1.0);
+        let blue = Color::from_rgba_f32(0.0, 0.0, 1.0, 1.0);
+        let mut history = History::default();
+        history.record(&red, None, 1);
+        history.record(&blue, None, 2);
+        history.record(&almost_red, Some("a.css:1:1".to_string()), 3);
+
+        let recent = history.recent(5, 10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].color, almost_red.to_hex_string(HexShort::Never));
+        assert_eq!(recent[0].timestamp, 3);
+        assert_eq!(recent[1].color, "#0000ff");
+        assert_eq!(history.recent(1, 10).len(), 1);
+    }
+
+    #[test]
+    fn recent_prefers_frequent_over_newest() {
+        let day = 86_400;
+        let red = Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0);
+        let blue = Color::from_rgba_f32(0.0, 0.0, 1.0, 1.0);
+        let green = Color::from_rgba_f32(0.0, 1.0, 0.0, 1.0);
+        let mut history = History::default();
+        history.record(&blue, None, 10 * day);
+        history.record(&red, None, 18 * day);
+        history.record(&red, None, 19 * day);
+        history.record(&green, None, 20 * day);
+
+        // red: 100 + 100, green: 100, blue: 70
+        let order: Vec<_> = history.recent(3, 21 * day).into_iter().map(|e| e.color).collect();
+        assert_eq!(order, vec!["#ff0000", "#00ff00", "#0000ff"]);
+    }
+
+    #[test]
+    fn pinned_colors_come_first_and_never_rotate_out() {
+        let day = 86_400;
+        let red = Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0);
+        let almost_red = Color::from_rgba_f32(0.995, 0.005, 0.0, 1.0);
+        let blue = Color::from_rgba_f32(0.0, 0.0, 1.0, 1.0);
+        let green = Color::from_rgba_f32(0.0, 1.0, 0.0, 1.0);
+        let mut history = History::default();
+        history.record(&red, None, 0);
+        history.record(&blue, None, 200 * day);
+        history.record(&almost_red, None, 200 * day);
+        history.record(&green, None, 200 * day);
+        assert!(history.toggle_pin("#ff0000"));
+
+        let recent = history.recent(2, 201 * day);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].color, "#ff0000");
+        assert!(recent[0].pinned);
+        assert_eq!(recent[1].color, "#00ff00");
+        assert!(!recent[1].pinned);
+
+        assert!(!history.toggle_pin("#ff0000"));
+        assert!(!history.recent(2, 201 * day)[0].pinned);
+    }
+}
@@ -0,0 +1,184 @@
+//! UI strings, per language.
+//!
+//! To add a language, add a [`Lang`] variant and a [`Strings`] table for
+//! it; the compiler points out anything missed. Format names and color
+//! values aren't translated.
+
+use std::{fmt::Display, str::FromStr};
+
+use once_cell::sync::OnceCell;
+
+static STRINGS: OnceCell<&'static Strings> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lang {
+    En,
+    De,
+}
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(Lang::En),
+            "de" => Ok(Lang::De),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::En => "en",
+            Self::De => "de",
+        })
+    }
+}
+
+impl Lang {
+    pub fn values() -> Vec<Lang> {
+        vec![Lang::En, Lang::De]
+    }
+
+    /// The language of a POSIX locale like `de_AT.UTF-8`, if there's a table
+    /// for it.
+    pub fn from_locale(locale: &str) -> Option<Lang> {
+        let lang = locale.split(['_', '-', '.']).next()?;
+        lang.to_lowercase().parse().ok()
+    }
+
+    /// The language of the system locale, falling back to English.
+    pub fn from_env() -> Lang {
+        env_locale()
+            .and_then(|locale| Lang::from_locale(&locale))
+            .unwrap_or(Lang::En)
+    }
+
+    fn strings(self) -> &'static Strings {
+        match self {
+            Lang::En => &EN,
+            Lang::De => &DE,
+        }
+    }
+}
+
+pub struct Strings {
+    pub copy: &'static str,
+    pub paste: &'static str,
+    pub reset: &'static str,
+    pub swap: &'static str,
+    pub picker: &'static str,
+    pub history: &'static str,
+    pub tokens: &'static str,
+    pub clipped: &'static str,
+    /// Why a format button is disabled at a higher `--depth`.
+    pub eight_bit_only: &'static str,
+    pub theme: &'static str,
+    pub light: &'static str,
+    pub dark: &'static str,
+    pub slider_labels: &'static str,
+    pub default_format: &'static str,
+    pub output: &'static str,
+    pub hex_short: &'static str,
+    pub rgb_percent: &'static str,
+    pub alpha_number: &'static str,
+    pub next_launch: &'static str,
+    /// Example queries for the command palette, whose commands are English.
+    pub palette_placeholder: &'static str,
+}
+
+const EN: Strings = Strings {
+    copy: "COPY",
+    paste: "PASTE",
+    reset: "RESET",
+    swap: "SWAP",
+    picker: "PICKER",
+    history: "HISTORY",
+    tokens: "TOKENS",
+    clipped: "CLIPPED",
+    eight_bit_only: "Only 8 bits per channel",
+    theme: "THEME",
+    light: "LIGHT",
+    dark: "DARK",
+    slider_labels: "Label slider ends (0°, 100%)",
+    default_format: "DEFAULT FORMAT",
+    output: "OUTPUT",
+    hex_short: "Hex shorthand (#fc0)",
+    rgb_percent: "RGB as percentages",
+    alpha_number: "Alpha as a number (0.50)",
+    next_launch: "Output settings apply from the next launch",
+    palette_placeholder: "copy hsl, rotate hue 30, contrast against #fff...",
+};
+
+const DE: Strings = Strings {
+    copy: "KOPIEREN",
+    paste: "EINFÜGEN",
+    reset: "ZURÜCK",
+    swap: "TAUSCHEN",
+    picker: "AUSWAHL",
+    history: "VERLAUF",
+    tokens: "TOKENS",
+    clipped: "BESCHNITTEN",
+    eight_bit_only: "Nur 8 Bit pro Kanal",
+    theme: "DESIGN",
+    light: "HELL",
+    dark: "DUNKEL",
+    slider_labels: "Reglerenden beschriften (0°, 100%)",
+    default_format: "STANDARDFORMAT",
+    output: "AUSGABE",
+    hex_short: "Hex-Kurzform (#fc0)",
+    rgb_percent: "RGB in Prozent",
+    alpha_number: "Alpha als Zahl (0.50)",
+    next_launch: "Ausgabeeinstellungen gelten ab dem nächsten Start",
+    palette_placeholder: "z. B. copy hsl, rotate hue 30, contrast against #fff...",
+};
+
+/// Languages written right to left, by ISO 639 code.
+const RTL_LANGS: &[&str] = &["ar", "ckb", "dv", "fa", "he", "ps", "sd", "ug", "ur", "yi"];
+
+/// `LC_ALL`, `LC_MESSAGES` or `LANG`, whichever is set first.
+fn env_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Whether a POSIX locale's language is written right to left, whether or
+/// not there's a table for it.
+pub fn is_rtl(locale: &str) -> bool {
+    let lang = locale.split(['_', '-', '.']).next().unwrap_or_default().to_lowercase();
+    RTL_LANGS.contains(&lang.as_str())
+}
+
+/// Whether the system locale's language is written right to left.
+pub fn rtl_from_env() -> bool {
+    env_locale().map_or(false, |locale| is_rtl(&locale))
+}
+
+/// Sets the language [`strings`] returns for the rest of the session.
+pub fn install(lang: Lang) {
+    if STRINGS.set(lang.strings()).is_err() {
+        log::warn!("language already installed");
+    }
+}
+
+/// The installed language's strings; English if none was.
+pub fn strings() -> &'static Strings {
+    STRINGS.get().copied().unwrap_or(&EN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lang;
+
+    #[test]
+    fn from_locale() {
+        assert_eq!(Lang::from_locale("de_AT.UTF-8"), Some(Lang::De));
+        assert_eq!(Lang::from_locale("en-US"), Some(Lang::En));
+        assert_eq!(Lang::from_locale("C"), None);
+        assert_eq!(Lang::from_locale("fr_FR"), None);
+
+        assert!(super::is_rtl("he_IL.UTF-8"));
+        assert!(!super::is_rtl("de_AT.UTF-8"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{ffi::OsString, fs::File, io::{IsTerminal, Write}, path::Path, sync::Mutex};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+struct Logger {
+    level: LevelFilter,
+    file: Option<Mutex<File>>,
+    /// Whether to color the level, for a person reading stderr.
+    color: bool,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = format!("{:<5}", record.level());
+        let level = if self.color { paint(record.level(), &level) } else { level };
+        let line = format!("[{} {}] {}\n", level, record.target(), record.args());
+        match &self.file {
+            Some(file) => {
+                if let Ok(mut file) = file.lock() {
+                    let _ = file.write_all(line.as_bytes());
+                }
+            }
+            // stdout is reserved for color output, so diagnostics always go to stderr
+            None => eprint!("{}", line),
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(Ok(mut file)) = self.file.as_ref().map(|f| f.lock()) {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// The level to log at for `-v`s, or only errors with `--quiet`.
+pub fn level_for(verbosity: u8, quiet: bool) -> LevelFilter {
+    match verbosity {
+        _ if quiet => LevelFilter::Error,
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Whether diagnostics are for a person: stderr is a terminal and
+/// `NO_COLOR` isn't set to anything.
+pub fn color_stderr() -> bool {
+    wants_color(std::io::stderr().is_terminal(), std::env::var_os("NO_COLOR"))
+}
+
+fn wants_color(terminal: bool, no_color: Option<OsString>) -> bool {
+    terminal && no_color.map_or(true, |value| value.is_empty())
+}
+
+/// `text` in the ANSI color for `level`.
+fn paint(level: Level, text: &str) -> String {
+    let code = match level {
+        Level::Error => "1;31",
+        Level::Warn => "33",
+        Level::Info => "32",
+        Level::Debug => "34",
+        Level::Trace => "2",
+    };
+    format!("\u{1b}[{}m{}\u{1b}[0m", code, text)
+}
+
+pub fn init(verbosity: u8, quiet: bool, log_file: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let file = match log_file {
+        Some(path) => Some(Mutex::new(
+            std::fs::OpenOptions::new().create(true).append(true).open(path)?
+        )),
+        None => None,
+    };
+    let level = level_for(verbosity, quiet);
+    let color = file.is_none() && color_stderr();
+    log::set_boxed_logger(Box::new(Logger { level, file, color }))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, LevelFilter};
+
+    use super::{level_for, paint, wants_color};
+
+    #[test]
+    fn color_and_quiet() {
+        assert!(wants_color(true, None));
+        assert!(wants_color(true, Some("".into())));
+        assert!(!wants_color(true, Some("1".into())));
+        assert!(!wants_color(false, None));
+        assert_eq!(paint(Level::Warn, "WARN "), "\u{1b}[33mWARN \u{1b}[0m");
+
+        assert_eq!(level_for(0, false), LevelFilter::Warn);
+        assert_eq!(level_for(2, true), LevelFilter::Error);
+    }
+}
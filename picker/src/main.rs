@@ -1,11 +1,25 @@
-use std::{fmt::Display, str::FromStr};
+// `map_or` over `is_some_and` and friends, as the toolchains we still build
+// with (rust-version) predate some of them
+#![allow(clippy::unnecessary_map_or)]
 
-use druid::{AppDelegate, AppLauncher, Command, Cursor, Data, DelegateCtx, Env, Event, FontDescriptor, FontFamily, Lens, PlatformError, RenderContext, Selector, Target, TextAlignment, TextLayout, Widget, WidgetExt, WindowDesc, commands, keyboard_types::Key, theme, widget::ControllerHost};
-use druid::widget::{Flex, Painter};
+use std::{cell::{Cell, RefCell}, fmt::Display, fs, io::Read, path::{Path, PathBuf}, rc::Rc, str::FromStr, sync::{Arc, atomic::{AtomicBool, Ordering}}};
+
+use druid::{AppDelegate, AppLauncher, Application, Command, Cursor, Data, DelegateCtx, Env, Event, ExtEventSink, FontDescriptor, FontFamily, Lens, LensExt, PlatformError, RenderContext, Selector, Target, TextAlignment, TextLayout, Widget, WidgetExt, WindowDesc, commands, keyboard_types::Key, theme};
+use druid::widget::{Checkbox, Controller, CrossAxisAlignment, Either, EnvScope, Flex, Label, List, Painter, Scroll, TextBox};
+use log::{debug, info, trace, warn};
+use serde_json::json;
 use structopt::StructOpt;
+use structopt::clap::{self, App, AppSettings, Arg, ErrorKind, Shell};
 
-mod color;
-use color::Color;
+use color_core::{ansi, color, derive, icc, names, relative, template, vars};
+use color_core::{Color, Format, FormatOptions};
+use color_core::format::UserFormat;
+use color_core::snap::SnapSet;
+use color::{AlphaRepr, Depth, GamutClamp, HexShort, WhitePoint};
+use derive::Derivation;
+use relative::Relative;
+use template::Template;
+use vars::VarSyntax;
 
 mod widgets;
 use widgets::*;
@@ -16,86 +30,433 @@ use shape_util::*;
 mod widget_util;
 use widget_util::*;
 
-#[derive(Debug, Clone)]
-enum Position {
-    Under,
-    Over,
+mod logging;
+
+mod output;
+
+mod backend;
+use backend::Backend;
+
+#[cfg(feature = "egui")]
+mod egui_ui;
+
+mod history;
+use history::{History, HistoryEntry};
+
+mod export;
+use export::{SwatchTarget, preview::PreviewFrames, terminal::SchemeTarget};
+
+#[cfg(feature = "palettes")]
+mod palettes;
+
+mod settings;
+use settings::{Session, Settings, Theme};
+
+mod palette;
+
+mod stats;
+use stats::Stats;
+
+mod placement;
+use placement::{Anchor, Position};
+
+mod ui_colors;
+use ui_colors::UiColors;
+
+mod locale;
+use locale::Lang;
+
+mod parent;
+
+mod viz3d;
+use viz3d::Viz3d;
+
+/// What the 2D picker surface plots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Model {
+    Hsv,
+    Oklch,
+}
+impl FromStr for Model {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hsv" => Ok(Model::Hsv),
+            "oklch" => Ok(Model::Oklch),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for Model {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Hsv => "hsv",
+            Self::Oklch => "oklch",
+        })
+    }
+}
+impl Model {
+    fn values() -> Vec<Model> {
+        vec![Model::Hsv, Model::Oklch]
+    }
+}
+
+/// How picker surfaces are sampled when drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sampling {
+    Nearest,
+    Bilinear,
 }
-impl FromStr for Position {
+impl FromStr for Sampling {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "under" => Ok(Position::Under),
-            "over" => Ok(Position::Over),
+            "nearest" => Ok(Sampling::Nearest),
+            "bilinear" => Ok(Sampling::Bilinear),
             s => Err(format!("Invalid value: {}", s)),
         }
     }
 }
-impl Display for Position {
+impl Display for Sampling {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", match self {
-            Self::Under => "under",
-            Self::Over => "over",
+            Self::Nearest => "nearest",
+            Self::Bilinear => "bilinear",
         })
     }
 }
 
+/// What `--indexed` picks from: the `--palette` or `--snap` set, or else
+/// the slots of the `--base16` scheme.
+#[derive(Clone)]
+enum IndexedPalette {
+    Set(SnapSet),
+    Slots(Arc<Vec<Color>>),
+}
+impl IndexedPalette {
+    /// The index of the entry closest to `color`; the first of equally close
+    /// ones.
+    fn index(&self, color: &Color) -> usize {
+        match self {
+            Self::Set(set) => set.index(color),
+            Self::Slots(slots) => slots.iter()
+                .map(|slot| color.delta_e(slot))
+                .enumerate()
+                .fold((0, f32::INFINITY), |best, (i, d)| if d < best.1 { (i, d) } else { best })
+                .0,
+        }
+    }
+
+    /// That entry, with `color`'s alpha.
+    fn nearest(&self, color: &Color) -> Color {
+        match self {
+            Self::Set(set) => set.nearest(color),
+            Self::Slots(slots) => {
+                let slot = &slots[self.index(color)];
+                Color::from_rgba_f32(slot.red(), slot.green(), slot.blue(), color.alpha())
+            }
+        }
+    }
+}
+
+/// A fixed palette output is restricted to: `builtin:<name>`.
+#[derive(Debug, Clone, Copy)]
+struct PaletteArg(SnapSet);
+impl FromStr for PaletteArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("builtin:") {
+            Some(name) => name.parse().map(PaletteArg),
+            None => Err(format!("Invalid value: {}, expected builtin:<name>", s)),
+        }
+    }
+}
+
 #[derive(StructOpt, Debug, Clone)]
 struct Args {
     #[structopt(default_value = "#FF0000")]
     color: ColorFormat,
 
+    /// Read the color from a file instead, or stdin for -, so it needn't be
+    /// quoted for a shell
+    #[structopt(long)]
+    color_from: Option<PathBuf>,
+
+    /// Anchor to open beside, in virtual desktop coordinates: from the
+    /// primary monitor's top left, negative to its left or above
     #[structopt(short, default_value = "500.0")]
     x: f64,
 
     #[structopt(short, default_value = "100.0")]
     y: f64,
 
+    /// Side of the -x/-y anchor to open on, under or over; it flips to the
+    /// other side if the window wouldn't fit on screen
     #[structopt(long, default_value = "under")]
     position: Position,
 
+    /// Bounds of the color token to open beside, as x,y,w,h in the same
+    /// coordinates as -x/-y, which it replaces
+    #[structopt(long)]
+    anchor: Option<Anchor>,
+
+    /// Windowing backend on linux: auto, x11 or wayland; or egui, in builds
+    /// with that feature, for a plainer window where druid's has trouble
+    #[structopt(long, default_value = "auto")]
+    backend: Backend,
+
     #[structopt(long)]
     font: Option<String>,
 
     #[structopt(long)]
     font_size: Option<f64>,
 
+    /// Scale every dimension and font size, e.g. 1.5 for a larger picker (0.5 to 4)
+    #[structopt(long, default_value = "1.0")]
+    scale: f64,
+
+    /// Editor theme colors to derive the UI's from, as JSON: {"background",
+    /// "foreground", "accent"}; overrides the light/dark setting
+    #[structopt(long)]
+    ui_colors: Option<UiColors>,
+
+    /// Language of the UI: en or de; defaults to the system locale's
+    #[structopt(long)]
+    lang: Option<Lang>,
+
+    /// Mirror the layout for right-to-left languages; on by default when the
+    /// system locale's language is one
+    #[structopt(long)]
+    rtl: bool,
+
     #[structopt(long)]
     continuous: bool,
-}
 
-#[derive(Clone, Debug, Data, PartialEq)]
-enum Format {
-    Rgb,
-    Hex,
-    Hsl,
-    Hsv,
-    Vec,
+    /// Keep a small PNG of the color as it's edited at preview.png in this
+    /// directory, for callers that can't easily read stdout
+    #[structopt(long)]
+    preview_frames: Option<PathBuf>,
+
+    /// Run as a desktop utility: a normal movable window that ignores -x/-y,
+    /// where keys don't abort and each commit is printed without exiting.
+    /// Closing it saves the color, format, window position and committed
+    /// colors, and the next --standalone launch starts from them
+    #[structopt(long)]
+    standalone: bool,
+
+    /// Commit the color when terminated by SIGINT, SIGTERM or SIGHUP (or a
+    /// console close on Windows), rather than aborting
+    #[structopt(long)]
+    commit_on_term: bool,
+
+    /// Abort when the process with this id exits, e.g. the editor that
+    /// launched the picker
+    #[structopt(long)]
+    parent_pid: Option<u32>,
+
+    /// Show a toolbar above the picker with copy, paste, reset and swap, and
+    /// 5% lightness and chroma steppers
+    #[structopt(long)]
+    toolbar: bool,
+
+    /// Use 3/4 digit hex shorthand when possible: auto or never; defaults to
+    /// the setting, which is off (never) to begin with
+    #[structopt(long)]
+    hex_short: Option<HexShort>,
+
+    /// Output RGB as percentages, e.g. rgb(100% 0% 50%)
+    #[structopt(long)]
+    rgb_percent: bool,
+
+    /// Write alpha as a percentage or a number, e.g. rgba(255, 0, 0, 50%) or
+    /// rgba(255, 0, 0, 0.50): percent or number; defaults to the setting,
+    /// which is off (percent) to begin with
+    #[structopt(long)]
+    alpha: Option<AlphaRepr>,
+
+    /// Snap output to the nearest color in a set: web (216 web-safe), vga (16
+    /// colors) or css (named colors). The picker itself stays free
+    #[structopt(long)]
+    snap: Option<SnapSet>,
+
+    /// Restrict output to a console's palette: builtin:nes, builtin:snes
+    /// (15 bit) or builtin:gameboy. Like --snap, the swatch previews the
+    /// nearest entry while dragging
+    #[structopt(long, conflicts_with = "snap")]
+    palette: Option<PaletteArg>,
+
+    /// On commit, print the index of the --palette (or --snap) entry instead
+    /// of a color, for sprite and tilemap data; the picker only lands on
+    /// entries. Without a set, the --base16 scheme's slots are the entries
+    #[structopt(long)]
+    indexed: bool,
+
+    /// Output precision: 8 or 16 bits per channel, or float (hex is #rrrrggggbbbb above 8 bits)
+    #[structopt(long, default_value = "8")]
+    depth: Depth,
+
+    /// Picker surface: hsv (saturation x value) or oklch (chroma x lightness)
+    #[structopt(long, default_value = "hsv")]
+    model: Model,
+
+    /// How the surfaces and sliders are sampled: nearest, sharp as they're
+    /// drawn at device resolution, or bilinear
+    #[structopt(long, default_value = "nearest")]
+    sampling: Sampling,
+
+    /// Let the hue slider wrap from 360° back to 0° when dragged past either
+    /// end, instead of stopping there
+    #[structopt(long)]
+    hue_wrap: bool,
+
+    /// Show an OKLCH lightness slider, which changes perceived lightness but
+    /// not chroma or hue
+    #[structopt(long)]
+    lightness_slider: bool,
+
+    /// Show an OKLCH chroma slider; the range sRGB can't show is greyed out
+    #[structopt(long)]
+    chroma_slider: bool,
+
+    /// Show a gamma slider under the picker, applying c^(1/gamma) to each
+    /// channel
+    #[structopt(long)]
+    gamma_slider: bool,
+
+    /// Experimental: show where the color sits in a 3D view under the
+    /// picker, rgb (the cube) or hsv (the cone); drag it to turn it
+    #[structopt(long)]
+    viz3d: Option<viz3d::Space>,
+
+    /// How the OKLCH controls handle colors outside sRGB: chroma (reduce
+    /// chroma, keeping lightness and hue) or clip (clip the RGB channels)
+    #[structopt(long, default_value = "chroma")]
+    gamut_clamp: GamutClamp,
+
+    /// Experimental: let values go up to this multiple of SDR white (e.g. 4)
+    /// and output extended-range color(srgb ...)
+    #[structopt(long)]
+    hdr: Option<f32>,
+
+    /// Custom output format, e.g. "0x{b*255|hex}{g*255|hex}{r*255|hex}" (see template.rs)
+    #[structopt(long)]
+    template: Option<Template>,
+
+    /// Base expression for the REL format, e.g. var(--brand); the color is
+    /// written as a color-mix() or relative hsl() of the launch color
+    #[structopt(long)]
+    relative_to: Option<String>,
+
+    /// Also show a color derived from the one being edited, updated live:
+    /// lighten, darken, saturate or desaturate:<percent>, spin:<degrees> or
+    /// alpha:<0..1>, e.g. lighten:10%; repeatable
+    #[structopt(long, number_of_values = 1)]
+    derive: Vec<Derivation>,
+
+    /// Print each color as a JSON object (value, format, hex, name) instead of plain text
+    #[structopt(long)]
+    json: bool,
+
+    /// On exit, print a summary of the session to stderr: time spent, number
+    /// of adjustments, OKLab distance moved and what changed (JSON with --json)
+    #[structopt(long)]
+    stats: bool,
+
+    /// Also write the committed color to a solid PNG, e.g. swatch.png:64 for
+    /// 64x64 (default 1x1); Ctrl+E writes one at any time
+    #[structopt(long)]
+    export_swatch: Option<SwatchTarget>,
+
+    /// On commit, also write pinned and recent colors as a terminal color
+    /// scheme: xresources:<path>, iterm2:<path> or windows-terminal:<path>
+    #[structopt(long)]
+    export_terminal: Option<SchemeTarget>,
+
+    /// Name for copied variable declarations (Ctrl+C or right-click the
+    /// swatch); defaults to the nearest color name
+    #[structopt(long)]
+    var_name: Option<String>,
+
+    /// Variable syntax: css (--name), or scss ($name) / less (@name), which
+    /// also add an output format for declarations
+    #[structopt(long, default_value = "css")]
+    var_syntax: VarSyntax,
+
+    /// ICC profile of the monitor; the preview is converted through it so it
+    /// matches what sRGB consumers see
+    #[structopt(long, parse(from_os_str))]
+    display_profile: Option<PathBuf>,
+
+    /// Show the color adapted to D50, D65 and tungsten white points (Bradford)
+    /// under the swatches, to anticipate how it shifts under other light
+    #[structopt(long)]
+    white_points: bool,
+
+    #[cfg(feature = "palettes")]
+    #[structopt(flatten)]
+    palettes: palettes::Args,
+
+    /// Where the color is being edited (e.g. file:line:col), recorded in the history
+    #[structopt(long)]
+    context: Option<String>,
+
+    /// Append diagnostic logs to this file instead of stderr
+    #[structopt(long, parse(from_os_str))]
+    log_file: Option<PathBuf>,
+
+    /// Print what this build supports as JSON (formats, modes, protocol
+    /// version, features) and exit
+    #[structopt(long)]
+    capabilities: bool,
+
+    /// Increase log verbosity (-v info, -vv debug, -vvv trace)
+    #[structopt(short, parse(from_occurrences))]
+    verbose: u8,
+
+    /// Only log errors
+    #[structopt(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    #[structopt(subcommand)]
+    cmd: Option<Subcommand>,
+
+    /// Format buttons from the settings file.
+    #[structopt(skip)]
+    user_formats: Vec<UserFormat>,
 }
-impl Format {
-    pub fn format(&self, color: &Color) -> String {
-        match self {
-            Self::Rgb => color.to_rgb_string(),
-            Self::Hex => color.to_hex_string(),
-            Self::Hsl => color.to_hsl_string(),
-            Self::Hsv => color.to_hsv_string(),
-            Self::Vec => color.to_vec_string(),
-        }
-    }
-    pub fn values() -> Vec<Format> {
-        vec![Self::Rgb, Self::Hex, Self::Hsl, Self::Hsv, Self::Vec]
-    }
+
+#[derive(StructOpt, Debug, Clone)]
+enum Subcommand {
+    /// Print completions for a shell: bash, zsh, fish, powershell or elvish
+    Completions {
+        #[structopt(possible_values = &Shell::variants())]
+        shell: Shell,
+    },
 }
-impl std::fmt::Display for Format {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Rgb => write!(f, "RGB"),
-            Self::Hex => write!(f, "HEX"),
-            Self::Hsl => write!(f, "HSL"),
-            Self::Hsv => write!(f, "HSV"),
-            Self::Vec => write!(f, "VEC"),
-        }
+
+/// The button for `--template`.
+const TEMPLATE_LABEL: &str = "TPL";
+
+fn format_options(args: &Args) -> FormatOptions {
+    FormatOptions {
+        hex_short: args.hex_short.unwrap_or(HexShort::Never),
+        rgb_percent: args.rgb_percent,
+        alpha: args.alpha.unwrap_or(AlphaRepr::Percent),
+        snap: args.palette.map(|p| p.0).or(args.snap),
+        depth: args.depth,
+        hdr: args.hdr.is_some() || args.color.format == Format::Srgb,
+        var_name: args.var_name.clone(),
+        var_syntax: args.var_syntax,
+        relative: args.relative_to.clone().map(|base| Arc::new(Relative::new(base, args.color.color.clone()))),
+        user_formats: Arc::new(
+            args.template.iter()
+                .map(|template| UserFormat { label: TEMPLATE_LABEL.to_string(), template: template.clone() })
+                .chain(args.user_formats.iter().cloned())
+                .collect()
+        ),
     }
 }
 
@@ -103,29 +464,69 @@ impl std::fmt::Display for Format {
 struct ColorFormat {
     color: Color,
     format: Format,
+    options: FormatOptions,
 }
 
 impl ColorFormat {
-    fn new(color: Color, format: Format) -> Self { Self { color, format } }
+    fn new(color: Color, format: Format) -> Self { Self { color, format, options: FormatOptions::default() } }
+
+    fn with_options(self, options: FormatOptions) -> Self { Self { options, ..self } }
+
+    /// The color that gets output, snapped by `--snap` or `--palette`.
+    fn output(&self) -> Color { self.options.snapped(&self.color) }
+
+    fn to_json(&self) -> serde_json::Value {
+        let color = self.output();
+        let (name, _) = names::nearest(&color);
+        json!({
+            "color": self.to_string(),
+            "format": self.format.to_string().to_lowercase(),
+            "hex": color.to_hex_string(self.options.hex_short),
+            "name": name,
+        })
+    }
+
+    /// The color as a CSS value: the current format when CSS understands it,
+    /// hex otherwise.
+    fn css_value(&self) -> String {
+        match self.format {
+            Format::Rgb | Format::Hex | Format::Hsl | Format::Srgb | Format::Relative => self.to_string(),
+            _ => self.output().to_hex_string(self.options.hex_short),
+        }
+    }
+
+    /// Prints the index of the color in `palette`, or the color when there's
+    /// no palette.
+    fn emit_index(&self, palette: Option<&IndexedPalette>, as_json: bool) {
+        match palette.map(|palette| palette.index(&self.color)) {
+            Some(index) if as_json => output::print_line(json!({
+                "index": index,
+                "hex": self.output().to_hex_string(self.options.hex_short),
+            })),
+            Some(index) => output::print_line(index),
+            None => self.emit(as_json),
+        }
+    }
+
+    fn emit(&self, as_json: bool) {
+        if as_json {
+            output::print_line(self.to_json());
+        } else {
+            output::print_line(self);
+        }
+    }
 }
 
 impl std::fmt::Display for ColorFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.format.format(&self.color))
+        write!(f, "{}", self.format.format(&self.color, &self.options))
     }
 }
 
 impl std::str::FromStr for ColorFormat {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.parse()
-            .map(|c: css_color::Rgba| {
-                ColorFormat::new(
-                    Color::from_rgba_f32(c.red, c.green, c.blue, c.alpha),
-                    Format::Hex,
-                )
-            })
-            .map_err(|e| format!("{:?}", e))
+        color_core::parse(s).map(|(color, format)| ColorFormat::new(color, format))
     }
 }
 
@@ -133,13 +534,113 @@ impl std::str::FromStr for ColorFormat {
 struct PickerState {
     initial_color: ColorFormat,
     current_color: ColorFormat,
+    recent: Arc<Vec<HistoryEntry>>,
+    history: Arc<Vec<HistoryEntry>>,
+    show_history: bool,
+    /// What's shown of the `--base16` scheme and `--vscode-theme`.
+    #[cfg(feature = "palettes")]
+    palettes: palettes::State,
+    show_tokens: bool,
+    settings: Settings,
+    show_settings: bool,
+    /// What's typed in the Ctrl+P command palette.
+    palette_query: String,
+    show_palette: bool,
+    /// The side of the anchor the window is on, `--position` unless it had
+    /// to flip to fit on screen.
+    position: Position,
+    /// How far across the window the caret points, once it's been placed
+    /// and kept on screen; the middle until then.
+    caret_x: Option<f64>,
+    /// Whether the current color is sliding to one that was set, see
+    /// `AnimateSetColor`.
+    animating: bool,
+    /// Colors committed in `--standalone` sessions, oldest first.
+    collected: Arc<Vec<Color>>,
+    /// The format button under the pointer, previewed in the swatch.
+    preview_format: Option<Format>,
 }
 
+const RECENT_COUNT: usize = 8;
+
+/// Most colors kept in `collected`, dropping the oldest.
+const COLLECTED_LIMIT: usize = 64;
+
 impl PickerState {
-    fn new(args: &Args) -> Self {
+    fn new(args: &Args, history: &History, settings: Settings) -> Self {
+        let mut color = args.color.clone().with_options(format_options(args));
+        if args.template.is_some() {
+            color.format = Format::User(TEMPLATE_LABEL.into());
+        } else if args.relative_to.is_some() {
+            color.format = Format::Relative;
+        } else if args.hdr.is_some() {
+            color.format = Format::Srgb;
+        } else if args.var_syntax != VarSyntax::Css {
+            color.format = Format::Var(args.var_syntax);
+        } else if color.format == Format::Hex {
+            // hex is also what a bare color parses as, so the setting only
+            // overrides that
+            if let Some(format) = Format::named(&settings.default_format, &color.options) {
+                if Format::values(&color.options).contains(&format) {
+                    color.format = format;
+                }
+            }
+        }
         Self {
-            initial_color: args.color.clone(),
-            current_color: args.color.clone(),
+            initial_color: color.clone(),
+            current_color: color,
+            recent: Arc::new(history.recent(RECENT_COUNT, history::now())),
+            history: Arc::new(history.newest_first()),
+            show_history: false,
+            #[cfg(feature = "palettes")]
+            palettes: palettes::State::default(),
+            show_tokens: false,
+            settings,
+            show_settings: false,
+            palette_query: String::new(),
+            show_palette: false,
+            position: args.position,
+            caret_x: None,
+            animating: false,
+            collected: Arc::new(Vec::new()),
+            preview_format: None,
+        }
+    }
+
+    /// Picks up where the last `--standalone` session left off.
+    fn with_session(mut self, session: &Session) -> Self {
+        if let Some(color) = session.color.as_ref().and_then(|c| c.parse().ok()) {
+            self.initial_color.color = color;
+            self.current_color.color = self.initial_color.color.clone();
+        }
+        if let Some(format) = session.format.as_ref().and_then(|f| Format::named(f, &self.current_color.options)) {
+            if Format::values(&self.current_color.options).contains(&format) {
+                self.initial_color.format = format;
+                self.current_color.format = format;
+            }
+        }
+        let skip = session.collected.len().saturating_sub(COLLECTED_LIMIT);
+        self.collected = Arc::new(session.collected.iter().skip(skip).filter_map(|c| c.parse().ok()).collect());
+        self
+    }
+
+    /// What to restore next time, with the window last at `window`.
+    fn session(&self, window: Option<druid::Rect>) -> Session {
+        Session {
+            color: Some(self.current_color.color.to_hex_string(HexShort::Never)),
+            format: Some(self.current_color.format.to_string().to_lowercase()),
+            position: window.map(|w| (w.x0, w.y0)),
+            size: window.map(|w| (w.width(), w.height())),
+            collected: self.collected.iter().map(|c| c.to_hex_string(HexShort::Never)).collect(),
+        }
+    }
+
+    /// Adds a `--standalone` commit, keeping the newest `COLLECTED_LIMIT`.
+    fn collect(&mut self, color: Color) {
+        let collected = Arc::make_mut(&mut self.collected);
+        collected.push(color);
+        if collected.len() > COLLECTED_LIMIT {
+            collected.drain(..collected.len() - COLLECTED_LIMIT);
         }
     }
 }
@@ -152,28 +653,239 @@ struct Sizing {
     current_swatch_size: f64,
     initial_swatch_size: f64,
     button_height: f64,
+    recent_size: f64,
+    /// The blend strip under the swatches.
+    scrubber_height: f64,
+    /// Height of the caret pointing at the anchor, 0 when the window can't
+    /// be placed there.
+    caret_size: f64,
+    /// Whether the `--base16` slot strip is shown.
+    #[cfg(feature = "palettes")]
+    scheme_strip: bool,
+    /// Whether the `--derive` strip is shown.
+    derive_strip: bool,
+    /// Whether rows read right to left, from `--rtl` or the locale.
+    rtl: bool,
+    /// `--scale`, already applied to the dimensions above.
+    scale: f64,
 }
 impl Sizing {
-    fn window_size(&self) -> (f64, f64) {
-        (
-            self.window_width(),
-            self.window_height(),
-        )
+    fn checker_size(&self) -> f64 {
+        self.slider_size / 4.0
     }
-    fn window_width(&self) -> f64 {
-        self.padding*4.0 + self.picker_size + self.slider_size*2.0
+    fn scaled(self, scale: f64) -> Self {
+        Self {
+            padding: self.padding * scale,
+            picker_size: self.picker_size * scale,
+            slider_size: self.slider_size * scale,
+            current_swatch_size: self.current_swatch_size * scale,
+            initial_swatch_size: self.initial_swatch_size * scale,
+            button_height: self.button_height * scale,
+            recent_size: self.recent_size * scale,
+            scrubber_height: self.scrubber_height * scale,
+            caret_size: self.caret_size * scale,
+            scale: self.scale * scale,
+            ..self
+        }
     }
-    fn window_height(&self) -> f64 {
-        self.current_swatch_size + self.initial_swatch_size + self.padding*2.0 + self.picker_size + self.button_height
+    /// A font size, scaled.
+    fn text(&self, size: f64) -> f64 {
+        size * self.scale
     }
-    fn checker_size(&self) -> f64 {
-        self.slider_size / 4.0
+    /// Where columns line up their children: the left, or the right when
+    /// reading right to left.
+    fn start(&self) -> CrossAxisAlignment {
+        if self.rtl { CrossAxisAlignment::End } else { CrossAxisAlignment::Start }
+    }
+}
+
+/// The command line definition: `Args` plus `--error-format`, which isn't
+/// in them as it applies before they're parsed.
+fn app() -> App<'static, 'static> {
+    Args::clap().arg(
+        Arg::with_name("error-format")
+            .long("error-format")
+            .takes_value(true)
+            .possible_values(&["text", "json"])
+            .default_value("text")
+            .help("How to report bad arguments: text, or json for one object on stderr with the field at fault, the message and any suggestion")
+    )
+}
+
+/// Parses the command line, reporting errors as `--error-format` asks;
+/// that's looked for directly.
+fn parse_args() -> Args {
+    let raw: Vec<_> = std::env::args_os().collect();
+    let json = raw.windows(2).any(|w| w[0] == "--error-format" && w[1] == "json")
+        || raw.iter().any(|a| a == "--error-format=json");
+    let mut app = app();
+    // clap colors a terminal by itself, but doesn't know NO_COLOR
+    if json || !logging::color_stderr() {
+        app = app.setting(AppSettings::ColorNever);
+    }
+    let args = app.get_matches_from_safe(raw).and_then(|matches| {
+        let mut args = Args::from_clap(&matches);
+        if let Some(path) = &args.color_from {
+            args.color = read_color(path).map_err(|e| clap::Error::with_description(
+                &format!("Invalid value for '--color-from <color-from>': {}", e),
+                ErrorKind::InvalidValue,
+            ))?;
+        }
+        Ok(args)
+    });
+    match args {
+        Ok(args) => args,
+        Err(e) if json && !matches!(e.kind, ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed) => {
+            eprintln!("{}", output::usage_error(&e));
+            output::exit(1);
+        }
+        Err(e) => e.exit(),
     }
 }
 
+/// Reads `--color-from`: a file, or stdin for `-`.
+fn read_color(path: &Path) -> Result<ColorFormat, String> {
+    let text = if path == Path::new("-") {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).map(|_| text)
+    } else {
+        fs::read_to_string(path)
+    };
+    let text = text.map_err(|e| format!("{}: {}", path.display(), e))?;
+    text.trim().parse()
+}
+
+/// Ways of running the picker that callers may need to check for, as
+/// reported by `--capabilities`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Continuous,
+    Standalone,
+    Json,
+    Indexed,
+    Stats,
+    ErrorFormatJson,
+    CommitOnTerm,
+    ParentPid,
+    PreviewFrames,
+    #[cfg(feature = "palettes")]
+    ThemePatch,
+}
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Continuous => "continuous",
+            Self::Standalone => "standalone",
+            Self::Json => "json",
+            Self::Indexed => "indexed",
+            Self::Stats => "stats",
+            Self::ErrorFormatJson => "error-format-json",
+            Self::CommitOnTerm => "commit-on-term",
+            Self::ParentPid => "parent-pid",
+            Self::PreviewFrames => "preview-frames",
+            #[cfg(feature = "palettes")]
+            Self::ThemePatch => "theme-patch",
+        })
+    }
+}
+impl Mode {
+    fn values() -> Vec<Mode> {
+        vec![
+            Mode::Continuous, Mode::Standalone, Mode::Json, Mode::Indexed, Mode::Stats, Mode::ErrorFormatJson,
+            Mode::CommitOnTerm, Mode::ParentPid, Mode::PreviewFrames,
+            #[cfg(feature = "palettes")]
+            Mode::ThemePatch,
+        ]
+    }
+}
+
+/// What `--capabilities` reports, for callers that need to work with older
+/// builds.
+fn capabilities() -> serde_json::Value {
+    let mut formats = Format::values(&FormatOptions::default());
+    formats.extend([Format::Srgb, Format::Var(VarSyntax::Scss), Format::Var(VarSyntax::Less), Format::Relative, Format::User(TEMPLATE_LABEL.into())]);
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "protocol": output::PROTOCOL_VERSION,
+        "formats": formats.iter().map(|f| f.to_string().to_lowercase()).collect::<Vec<_>>(),
+        "models": Model::values().iter().map(Model::to_string).collect::<Vec<_>>(),
+        "depths": Depth::values().iter().map(Depth::to_string).collect::<Vec<_>>(),
+        "snap": SnapSet::values().iter().map(SnapSet::to_string).collect::<Vec<_>>(),
+        "modes": Mode::values().iter().map(Mode::to_string).collect::<Vec<_>>(),
+        "langs": Lang::values().iter().map(Lang::to_string).collect::<Vec<_>>(),
+        "subcommands": ["completions"],
+        "features": {
+            "backends": Backend::values().iter().map(Backend::to_string).collect::<Vec<_>>(),
+            "image": cfg!(feature = "image"),
+            "palettes": cfg!(feature = "palettes"),
+            "egui": cfg!(feature = "egui"),
+        },
+    })
+}
+
 fn main() -> Result<(), PlatformError> {
-    let args = Args::from_args();
-    let data = PickerState::new(&args);
+    output::install_panic_hook();
+
+    let mut args = parse_args();
+    if let Some(Subcommand::Completions { shell }) = args.cmd {
+        app().gen_completions_to(env!("CARGO_PKG_NAME"), shell, &mut std::io::stdout());
+        output::flush();
+        return Ok(());
+    }
+    if args.capabilities {
+        output::print_line(capabilities());
+        return Ok(());
+    }
+    if let Err(e) = logging::init(args.verbose, args.quiet, args.log_file.as_deref()) {
+        eprintln!("could not initialize logging: {}", e);
+    }
+    info!("starting with {:?}", args);
+    // parsed before there was a logger to say why
+    debug!("starting color read as {}", args.color.format);
+    locale::install(args.lang.unwrap_or_else(Lang::from_env));
+
+    if let Some(path) = &args.display_profile {
+        match icc::DisplayProfile::load(path) {
+            Ok(profile) => icc::install(profile),
+            Err(e) => warn!("ignoring display profile: {}", e),
+        }
+    }
+
+    let history_path = History::default_path();
+    let history = history_path.as_deref().map(History::load).unwrap_or_default();
+    let settings = Settings::default_path().map(|p| Settings::load(&p)).unwrap_or_default();
+    if args.hex_short.is_none() && settings.hex_short {
+        args.hex_short = Some(HexShort::Auto);
+    }
+    args.rgb_percent |= settings.rgb_percent;
+    if args.alpha.is_none() && settings.alpha_number {
+        args.alpha = Some(AlphaRepr::Number);
+    }
+    args.user_formats = settings.user_formats(&format_options(&args));
+    let mut data = PickerState::new(&args, &history, settings);
+    let session_path = if args.standalone { Session::default_path() } else { None };
+    let session = session_path.as_deref().map(Session::load).unwrap_or_default();
+    if args.standalone {
+        data = data.with_session(&session);
+    }
+
+    #[cfg(feature = "palettes")]
+    let palettes = palettes::Files::load(&args.palettes);
+    #[cfg(feature = "palettes")]
+    palettes.start(&args.palettes, &args.color.color, &mut data);
+
+    #[cfg(feature = "egui")]
+    if args.backend == Backend::Egui {
+        egui_ui::warn_unsupported(&args);
+        if let Err(e) = egui_ui::run(&args, data.current_color) {
+            log::error!("egui backend failed: {}", e);
+            output::exit(1);
+        }
+        output::flush();
+        return Ok(());
+    }
+
+    let can_position = backend::select(args.backend) && !args.standalone;
 
     let sizing = Sizing{
         padding: 10.0,
@@ -182,48 +894,327 @@ fn main() -> Result<(), PlatformError> {
         current_swatch_size: 64.0,
         initial_swatch_size: 26.0,
         button_height: 20.0,
-    };
+        recent_size: 18.0,
+        scrubber_height: 8.0,
+        caret_size: if can_position { 7.0 } else { 0.0 },
+        #[cfg(feature = "palettes")]
+        scheme_strip: palettes.has_scheme(),
+        derive_strip: !args.derive.is_empty(),
+        rtl: args.rtl || locale::rtl_from_env(),
+        scale: 1.0,
+    }.scaled(args.scale.clamp(0.5, 4.0));
 
-    let main_window =
-        WindowDesc::new(build_root(args.clone(), sizing.clone()))
-        .window_size(sizing.window_size())
-        .set_position(druid::kurbo::Point::new(args.x - sizing.window_width() / 2.0, args.y))
+    let stats = if args.stats { Some(Rc::new(RefCell::new(Stats::new(&data.current_color.color)))) } else { None };
+    let preview = args.preview_frames.clone().and_then(|dir| match PreviewFrames::start(dir, &data.current_color.color) {
+        Ok(frames) => Some(Arc::new(frames)),
+        Err(e) => {
+            warn!("ignoring --preview-frames: {}", e);
+            None
+        }
+    });
+
+    let mut indexed = args.palette.map(|p| p.0).or(args.snap).map(IndexedPalette::Set);
+    #[cfg(feature = "palettes")]
+    if indexed.is_none() && !data.palettes.scheme().is_empty() {
+        indexed = Some(IndexedPalette::Slots(data.palettes.scheme().clone()));
+    }
+    if !args.indexed {
+        indexed = None;
+    } else if indexed.is_none() {
+        warn!("--indexed needs --palette, --snap or --base16; printing colors instead");
+    }
+
+    // the hue slider's histogram
+    #[cfg(feature = "palettes")]
+    let palette = data.palettes.colors();
+    #[cfg(not(feature = "palettes"))]
+    let palette = Vec::new();
+
+    let window = Rc::new(Cell::new(None));
+    #[cfg(feature = "palettes")]
+    let root = build_root(args.clone(), sizing.clone(), palette, &palettes, indexed.clone(), stats.clone(), preview, window.clone());
+    #[cfg(not(feature = "palettes"))]
+    let root = build_root(args.clone(), sizing.clone(), palette, indexed.clone(), stats.clone(), preview, window.clone());
+    let anchor = args.anchor.unwrap_or_else(|| Anchor::point(druid::Point::new(args.x, args.y)));
+    let position = args.position;
+    let mut main_window =
+        WindowDesc::new(move || if can_position { root().on_fit(place_window(anchor, position)) } else { root() })
+        // resized to fit once laid out, see `FitWindow`
+        .window_size((sizing.picker_size, sizing.picker_size))
+        // square and without a shadow: drawing either needs a transparent
+        // window, which this druid (0.6, as locked) can't ask for; 0.7
+        // still has no `WindowDesc::transparent`
         .resizable(false)
         .title("Color Picker")
-        .show_titlebar(false);
-
-    AppLauncher::with_window(main_window)
-        .delegate(Delegate{})
-        .configure_env(|env, _| {
-            let window_background = druid::Color::grey8(0xEB);
-
-            env.set(theme::WINDOW_BACKGROUND_COLOR, window_background.clone());
-            env.set(TOGGLE_ACTIVE_BG, window_background);
-            env.set(TOGGLE_ACTIVE_FG, druid::Color::grey8(0x55));
-            env.set(TOGGLE_INACTIVE_BG, druid::Color::grey8(0xD6));
-            env.set(TOGGLE_INACTIVE_FG, druid::Color::grey8(0x77));
-            env.set(TOGGLE_BORDER, druid::Color::grey8(0xC0));
+        .show_titlebar(args.standalone);
+
+    if args.standalone {
+        info!("standalone, ignoring -x/-y");
+        if let Some((x, y)) = session.position {
+            main_window = main_window.set_position(druid::kurbo::Point::new(x, y));
+        }
+        if let Some((width, height)) = session.size {
+            main_window = main_window.window_size((width, height));
+        }
+    } else if can_position {
+        // placed by `place_window` once the size is known
+        debug!("anchor {:?} {}", anchor.0, args.position);
+    } else {
+        info!("backend does not allow window positioning, ignoring -x/-y");
+    }
+
+    let text_scale = sizing.scale;
+    let ui_colors = args.ui_colors.clone();
+    let smooth = args.sampling == Sampling::Bilinear;
+    let launcher = AppLauncher::with_window(main_window);
+    handle_signals(launcher.get_external_handle(), args.commit_on_term, args.standalone);
+    if let Some(pid) = args.parent_pid {
+        let sink = launcher.get_external_handle();
+        parent::watch(pid, move || {
+            if sink.submit_command(ABORT_ACTION, (), Target::Global).is_err() {
+                output::exit(0);
+            }
+        });
+    }
+    let result = launcher
+        .delegate(Delegate{
+            history,
+            history_path,
+            context: args.context.clone(),
+            json: args.json,
+            indexed,
+            export_swatch: args.export_swatch.clone(),
+            export_terminal: args.export_terminal.clone(),
+            #[cfg(feature = "palettes")]
+            palettes,
+            standalone: args.standalone,
+            held_shortcut: None,
+            session_path,
+            window,
+        })
+        .configure_env(move |env, _| {
+            apply_theme(env, false, ui_colors.as_ref());
+            env.set(TOGGLE_TEXT_SIZE, text_scale * 9.0);
+            env.set(SMOOTH_SURFACES, smooth);
+            env.set(SLIDER_LABELS, false);
         })
-        .launch(data)
+        .launch(data);
+    if result.is_ok() {
+        info!("exited");
+        if let Some(stats) = stats {
+            stats.borrow().print(args.json);
+        }
+    }
+    output::flush();
+    result
+}
+
+/// Takes the abort path on SIGINT, SIGTERM or SIGHUP, or a console close on
+/// Windows, so the extension can close the picker and still get a clean
+/// exit; with `commit` the color is committed instead. If the app has
+/// already gone, or a second signal finds it stuck, it exits directly.
+fn handle_signals(sink: ExtEventSink, commit: bool, standalone: bool) {
+    static SIGNALLED: AtomicBool = AtomicBool::new(false);
+    let result = ctrlc::set_handler(move || {
+        // the handler replaces the default of exiting, so a second signal
+        // still has to kill a stuck event loop
+        if SIGNALLED.swap(true, Ordering::SeqCst) {
+            warn!("terminated again, exiting");
+            output::exit(0);
+        }
+        info!("terminated");
+        let mut sent = Ok(());
+        if commit {
+            sent = sink.submit_command(COMMIT_ACTION, (), Target::Global);
+        }
+        // a commit only quits outside --standalone
+        if !commit || standalone {
+            sent = sent.and_then(|()| sink.submit_command(ABORT_ACTION, (), Target::Global));
+        }
+        if sent.is_err() {
+            output::exit(0);
+        }
+    });
+    if let Err(e) = result {
+        warn!("could not handle signals: {}", e);
+    }
+}
+
+fn apply_theme(env: &mut Env, dark: bool, ui: Option<&UiColors>) {
+    // greys are given for the light theme, from the window background (0xEB)
+    // to text (0x33), so `--ui-colors` shades between its own two instead
+    let grey = |light: u8| match ui {
+        Some(ui) => ui.shade((0xEB - light as i32) as f32 / (0xEB - 0x33) as f32).to_druid(),
+        None => druid::Color::grey8(if dark { 0xFF - light + 0x10 } else { light }),
+    };
+    let window_background = grey(0xEB);
+
+    env.set(theme::WINDOW_BACKGROUND_COLOR, window_background.clone());
+    env.set(theme::LABEL_COLOR, grey(0x33));
+    env.set(TOGGLE_ACTIVE_BG, window_background);
+    env.set(TOGGLE_ACTIVE_FG, grey(0x55));
+    env.set(TOGGLE_INACTIVE_BG, grey(0xD6));
+    env.set(TOGGLE_INACTIVE_FG, grey(0x77));
+    env.set(TOGGLE_BORDER, grey(0xC0));
+    match ui.and_then(|ui| ui.accent.as_ref()) {
+        Some(accent) => {
+            env.set(TOGGLE_FOCUS, accent.to_druid());
+            env.set(theme::PRIMARY_LIGHT, accent.to_druid());
+            env.set(theme::PRIMARY_DARK, accent.to_druid());
+        }
+        None => env.set(TOGGLE_FOCUS, grey(0x88)),
+    }
+    env.set(LABEL_SECONDARY, grey(0x55));
+    env.set(LABEL_MUTED, grey(0x77));
+
+    // these sit on colors rather than the window, so don't follow dark mode
+    env.set(SELECTION, druid::Color::WHITE);
+    env.set(SHADOW, druid::Color::BLACK.with_alpha(0.2));
+    env.set(OUTLINE, druid::Color::BLACK.with_alpha(0.2));
+    env.set(MARKER, druid::Color::BLACK.with_alpha(0.5));
+    env.set(HISTOGRAM, druid::Color::BLACK.with_alpha(0.35));
+    env.set(CHECKER_LIGHT, druid::Color::WHITE);
+    env.set(CHECKER_DARK, druid::Color::grey(0.9));
+    env.set(SWATCH_TEXT, druid::Color::WHITE);
+    env.set(CLIPPED_BADGE, druid::Color::rgba8(0xd0, 0x20, 0x20, 0xcc));
 }
 
 const COMMIT_ACTION: Selector<()> = Selector::new("commit-action");
 const RESET_ACTION: Selector<()> = Selector::new("reset-action");
+const SWAP_ACTION: Selector<()> = Selector::new("swap-action");
 const ABORT_ACTION: Selector<()> = Selector::new("abort-action");
+const SET_COLOR: Selector<Color> = Selector::new("set-color");
+const TOGGLE_PIN: Selector<String> = Selector::new("toggle-pin");
+const EXPORT_SWATCH: Selector<()> = Selector::new("export-swatch");
+const COPY_DECLARATION: Selector<()> = Selector::new("copy-declaration");
+const COPY_VALUE: Selector<()> = Selector::new("copy-value");
+const PASTE_COLOR: Selector<()> = Selector::new("paste-color");
+const TOGGLE_PALETTE: Selector<()> = Selector::new("toggle-palette");
+const FOCUS_PALETTE: Selector<()> = Selector::new("focus-palette");
+const RUN_PALETTE: Selector<()> = Selector::new("run-palette");
+const SET_POSITION: Selector<Position> = Selector::new("set-position");
+const SET_CARET_X: Selector<f64> = Selector::new("set-caret-x");
+const PREVIEW_FORMAT: Selector<Option<Format>> = Selector::new("preview-format");
+
+struct Delegate {
+    history: History,
+    history_path: Option<PathBuf>,
+    context: Option<String>,
+    json: bool,
+    /// `--indexed`: what commits print an index into.
+    indexed: Option<IndexedPalette>,
+    export_swatch: Option<SwatchTarget>,
+    export_terminal: Option<SchemeTarget>,
+    /// The `--base16` scheme, `--vscode-theme` and so on, to write back to.
+    #[cfg(feature = "palettes")]
+    palettes: palettes::Files,
+    /// `--standalone`: commits don't quit, and keys don't abort.
+    standalone: bool,
+    /// A shortcut key that's still down; its key up mustn't abort.
+    held_shortcut: Option<Key>,
+    /// Where the `--standalone` session is saved.
+    session_path: Option<PathBuf>,
+    /// Where the window last was, see `TrackWindow`.
+    window: Rc<Cell<Option<druid::Rect>>>,
+}
 
-struct Delegate;
+impl Delegate {
+    fn save_session(&self, state: &PickerState) {
+        if let Some(path) = &self.session_path {
+            match state.session(self.window.get()).save(path) {
+                Ok(()) => debug!("saved session to {}", path.display()),
+                Err(e) => warn!("could not save session to {}: {}", path.display(), e),
+            }
+        }
+    }
+    fn record(&mut self, color: &Color) {
+        self.history.record(color, self.context.clone(), history::now());
+        self.save_history();
+    }
+    fn write_swatch(&self, color: &ColorFormat, target: &SwatchTarget) {
+        match target.write(&color.output(), color.options.depth) {
+            Ok(()) => info!("wrote swatch to {}", target.path.display()),
+            Err(e) => warn!("could not write swatch to {}: {}", target.path.display(), e),
+        }
+    }
+    /// Writes the palette the recent strip would show, up to the 16 ANSI slots.
+    fn write_terminal_scheme(&self, target: &SchemeTarget) {
+        let colors: Vec<Color> = self.history.recent(16, history::now())
+            .iter()
+            .filter_map(HistoryEntry::parsed_color)
+            .collect();
+        match fs::write(&target.path, export::terminal::render(target.format, &colors)) {
+            Ok(()) => info!("wrote {} {} scheme to {}", colors.len(), target.format, target.path.display()),
+            Err(e) => warn!("could not write scheme to {}: {}", target.path.display(), e),
+        }
+    }
+    fn run(&self, ctx: &mut DelegateCtx, action: palette::Action, state: &mut PickerState) {
+        let color = &mut state.current_color;
+        match action {
+            palette::Action::Copy(format) => {
+                let value = format.format(&color.color, &color.options);
+                debug!("copied {}", value);
+                Application::global().clipboard().put_string(value);
+            }
+            palette::Action::Derive(derivation) => color.color = derivation.apply(&color.color),
+            palette::Action::Gamma(gamma) => color.color = color.color.with_gamma([gamma; 3]),
+            palette::Action::Contrast(other) => color.color = color.color.with_contrast(&other, palette::CONTRAST_RATIO),
+            palette::Action::Paste => ctx.submit_command(Command::new(PASTE_COLOR, (), Target::Global)),
+            palette::Action::Reset => ctx.submit_command(Command::new(RESET_ACTION, (), Target::Global)),
+            palette::Action::Swap => ctx.submit_command(Command::new(SWAP_ACTION, (), Target::Global)),
+            palette::Action::Commit => ctx.submit_command(Command::new(COMMIT_ACTION, (), Target::Global)),
+        }
+    }
+    fn save_history(&self) {
+        if let Some(path) = &self.history_path {
+            if let Err(e) = self.history.save(path) {
+                warn!("could not save history to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
 impl AppDelegate<PickerState> for Delegate {
-    fn event(&mut self, ctx: &mut druid::DelegateCtx, _window_id: druid::WindowId, event: druid::Event, _state: &mut PickerState, _env: &druid::Env) -> Option<druid::Event> {
+    fn event(&mut self, ctx: &mut druid::DelegateCtx, _window_id: druid::WindowId, event: druid::Event, state: &mut PickerState, _env: &druid::Env) -> Option<druid::Event> {
         match &event {
+            Event::KeyDown(e) if e.mods.ctrl() || e.mods.meta() => {
+                let shortcut = match &e.key {
+                    Key::Character(c) if c == "e" => EXPORT_SWATCH,
+                    Key::Character(c) if c == "c" => COPY_DECLARATION,
+                    Key::Character(c) if c == "p" => TOGGLE_PALETTE,
+                    Key::Character(c) if c == "s" => SWAP_ACTION,
+                    _ => return Some(event),
+                };
+                ctx.submit_command(Command::new(shortcut, (), Target::Global));
+                self.held_shortcut = Some(e.key.clone());
+                None
+            }
+            Event::KeyUp(e) if self.held_shortcut.as_ref() == Some(&e.key) => {
+                self.held_shortcut = None;
+                None
+            }
+            // typing goes to the palette rather than aborting
+            Event::KeyUp(e) if state.show_palette => {
+                let action = match e.key {
+                    Key::Enter => RUN_PALETTE,
+                    Key::Escape => TOGGLE_PALETTE,
+                    _ => return Some(event),
+                };
+                ctx.submit_command(Command::new(action, (), Target::Global));
+                None
+            }
             Event::KeyUp(e) => {
+                trace!("key up: {:?}", e.key);
                 match e.key {
                     Key::Enter => {
                         ctx.submit_command(Command::new(COMMIT_ACTION, (), Target::Global));
                         None
                     },
-                    Key::Meta | Key::Control | Key::Shift | Key::Alt => {
+                    // modifiers, and keys that move around the window
+                    Key::Meta | Key::Control | Key::Shift | Key::Alt |
+                    Key::ArrowLeft | Key::ArrowRight | Key::ArrowUp | Key::ArrowDown | Key::Tab => {
                         Some(event)
                     }
+                    _ if self.standalone => Some(event),
                     _ => {
                         ctx.submit_command(Command::new(ABORT_ACTION, (), Target::Global));
                         None
@@ -235,130 +1226,992 @@ impl AppDelegate<PickerState> for Delegate {
     }
     fn command(&mut self, ctx: &mut DelegateCtx, _target: Target, cmd: &Command, state: &mut PickerState, _env: &Env) -> bool {
         if cmd.is(COMMIT_ACTION) {
-            println!("{}", state.current_color);
+            #[cfg(feature = "palettes")]
+            let committed = self.palettes.graded(&state.current_color);
+            #[cfg(not(feature = "palettes"))]
+            let committed = state.current_color.clone();
+            info!("commit {}", committed);
+            let color = committed.output();
+            match () {
+                #[cfg(feature = "palettes")]
+                _ if self.palettes.print_patch(&state.palettes, &color) => {}
+                _ if self.indexed.is_some() => committed.emit_index(self.indexed.as_ref(), self.json),
+                _ => committed.emit(self.json),
+            }
+            self.record(&color);
+            if let Some(target) = &self.export_swatch {
+                self.write_swatch(&committed, target);
+            }
+            if let Some(target) = &self.export_terminal {
+                self.write_terminal_scheme(target);
+            }
+            #[cfg(feature = "palettes")]
+            self.palettes.write(&state.palettes, &color);
+            if self.standalone {
+                state.collect(color);
+                state.recent = Arc::new(self.history.recent(RECENT_COUNT, history::now()));
+                return false
+            }
             ctx.submit_command(Command::new(commands::QUIT_APP, (), Target::Global));
             return false
         }
         if cmd.is(ABORT_ACTION) {
+            info!("abort");
+            // quitting doesn't remove the window first
+            self.save_session(state);
             ctx.submit_command(Command::new(commands::QUIT_APP, (), Target::Global));
             return false
         }
         if cmd.is(RESET_ACTION) {
+            debug!("reset to {}", state.initial_color);
             state.current_color = state.initial_color.to_owned();
         }
+        // a focused button took the key, so its release shouldn't commit or abort
+        if let Some(key) = cmd.get(KEY_HANDLED) {
+            self.held_shortcut = Some(key.clone());
+            return false
+        }
+        if let Some(format) = cmd.get(PREVIEW_FORMAT) {
+            state.preview_format = format.clone();
+        }
+        if let Some(position) = cmd.get(SET_POSITION) {
+            info!("flipped {} the anchor to fit on screen", position);
+            state.position = *position;
+        }
+        if let Some(x) = cmd.get(SET_CARET_X) {
+            state.caret_x = Some(*x);
+        }
+        if cmd.is(SWAP_ACTION) {
+            // only the colors; the chosen format stays
+            std::mem::swap(&mut state.initial_color.color, &mut state.current_color.color);
+            debug!("swapped to {}", state.current_color);
+        }
+        if cmd.is(EXPORT_SWATCH) {
+            let target = self.export_swatch.clone()
+                .unwrap_or_else(|| SwatchTarget::default_for(&state.current_color.color));
+            self.write_swatch(&state.current_color, &target);
+        }
+        if cmd.is(COPY_DECLARATION) {
+            let color = &state.current_color;
+            let options = &color.options;
+            let declaration = match color.format {
+                Format::Var(_) => color.to_string(),
+                _ => options.var_syntax.declare(&options.var_name(&color.color), &color.css_value()),
+            };
+            debug!("copied {}", declaration);
+            Application::global().clipboard().put_string(declaration);
+        }
+        if cmd.is(COPY_VALUE) {
+            debug!("copied {}", state.current_color);
+            Application::global().clipboard().put_string(state.current_color.to_string());
+        }
+        if cmd.is(PASTE_COLOR) {
+            match Application::global().clipboard().get_string().map(|s| color_core::parse(s.trim())) {
+                Some(Ok((color, _))) => ctx.submit_command(Command::new(SET_COLOR, color, Target::Global)),
+                _ => debug!("nothing to paste"),
+            }
+        }
+        if cmd.is(TOGGLE_PALETTE) {
+            state.show_palette = !state.show_palette;
+            state.palette_query.clear();
+            if state.show_palette {
+                state.show_history = false;
+                state.show_tokens = false;
+                state.show_settings = false;
+                ctx.submit_command(Command::new(FOCUS_PALETTE, (), Target::Global));
+            }
+        }
+        if cmd.is(RUN_PALETTE) {
+            let formats = Format::values(&state.current_color.options);
+            let suggestion = palette::suggestions(&state.palette_query, &formats).into_iter().next();
+            match suggestion.and_then(|s| s.action) {
+                Some(action) => {
+                    debug!("palette: {:?}", action);
+                    state.show_palette = false;
+                    state.palette_query.clear();
+                    self.run(ctx, action, state);
+                }
+                None => debug!("palette: nothing to run for {:?}", state.palette_query),
+            }
+        }
+        if let Some(color) = cmd.get(SET_COLOR) {
+            // the color itself slides in, see AnimateSetColor
+            debug!("set color {}", color.to_hex_string(HexShort::Never));
+            state.show_history = false;
+        }
+        #[cfg(feature = "palettes")]
+        if let Some(key) = cmd.get(palettes::EDIT_TOKEN) {
+            debug!("edit {}", key);
+            self.palettes.edit_token(key, state);
+        }
+        if let Some(color) = cmd.get(TOGGLE_PIN) {
+            let pinned = self.history.toggle_pin(color);
+            debug!("{} {}", if pinned { "pinned" } else { "unpinned" }, color);
+            self.save_history();
+            state.recent = Arc::new(self.history.recent(RECENT_COUNT, history::now()));
+        }
 
         true
     }
+    fn window_added(&mut self, id: druid::WindowId, _data: &mut PickerState, _env: &Env, _ctx: &mut DelegateCtx) {
+        debug!("window added: {:?}", id);
+    }
+    fn window_removed(&mut self, id: druid::WindowId, data: &mut PickerState, _env: &Env, _ctx: &mut DelegateCtx) {
+        debug!("window removed: {:?}", id);
+        // e.g. closed from its titlebar
+        self.save_session(data);
+    }
 }
 
-fn build_root(args: Args, sizing: Sizing) -> impl Fn() -> ControllerHost<Flex<PickerState>, OnDataChange<PickerState>> {
+/// How long a color from [`SET_COLOR`] takes to slide in, in nanoseconds.
+const SET_COLOR_ANIMATION: f64 = 150e6;
+
+/// Slides the current color to one set by [`SET_COLOR`] (paste, a strip
+/// click) instead of jumping, so it's clear what moved. Input finishes the
+/// slide at once.
+#[derive(Default)]
+struct AnimateSetColor {
+    /// From, to and nanoseconds so far.
+    slide: Option<(Color, Color, u64)>,
+}
+impl AnimateSetColor {
+    fn finish(&mut self, data: &mut PickerState) {
+        if let Some((_, to, _)) = self.slide.take() {
+            data.current_color.color = to;
+            data.animating = false;
+        }
+    }
+}
+impl<W: Widget<PickerState>> Controller<PickerState, W> for AnimateSetColor {
+    fn event(&mut self, child: &mut W, ctx: &mut druid::EventCtx, event: &Event, data: &mut PickerState, env: &Env) {
+        match event {
+            Event::Command(cmd) => {
+                if let Some(to) = cmd.get(SET_COLOR) {
+                    self.slide = Some((data.current_color.color.clone(), to.clone(), 0));
+                    data.animating = true;
+                    ctx.request_anim_frame();
+                }
+            }
+            Event::AnimFrame(interval) => {
+                if let Some((from, to, elapsed)) = &mut self.slide {
+                    *elapsed += interval;
+                    let t = (*elapsed as f64 / SET_COLOR_ANIMATION).min(1.0) as f32;
+                    if t < 1.0 {
+                        // ease out
+                        data.current_color.color = from.mix(to, 1.0 - (1.0 - t).powi(3));
+                        ctx.request_anim_frame();
+                    } else {
+                        self.finish(data);
+                    }
+                }
+            }
+            Event::MouseDown(_) | Event::KeyDown(_) => self.finish(data),
+            _ => {}
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
+/// Keeps where the window is for the `--standalone` session, as of its
+/// last event; the delegate that saves it can't ask the window, which may
+/// be gone by then.
+struct TrackWindow {
+    window: Rc<Cell<Option<druid::Rect>>>,
+}
+impl<W: Widget<PickerState>> Controller<PickerState, W> for TrackWindow {
+    fn event(&mut self, child: &mut W, ctx: &mut druid::EventCtx, event: &Event, data: &mut PickerState, env: &Env) {
+        let window = ctx.window();
+        self.window.set(Some(druid::Rect::from_origin_size(window.get_position(), window.get_size())));
+        child.event(ctx, event, data, env);
+    }
+}
+
+/// Keeps the edited color on an `--indexed` entry, once any slide to it is
+/// over, so the picker only lands on colors that have an index.
+struct KeepIndexed {
+    palette: Option<IndexedPalette>,
+}
+impl<W: Widget<PickerState>> Controller<PickerState, W> for KeepIndexed {
+    fn event(&mut self, child: &mut W, ctx: &mut druid::EventCtx, event: &Event, data: &mut PickerState, env: &Env) {
+        child.event(ctx, event, data, env);
+        if let (Some(palette), false) = (&self.palette, data.animating) {
+            let entry = palette.nearest(&data.current_color.color);
+            if !entry.same(&data.current_color.color) {
+                data.current_color.color = entry;
+            }
+        }
+    }
+}
+
+/// Keeps the window beside the anchor as it's refitted. When it first
+/// opens, it flips to the other side if `preferred` would leave the
+/// monitor's work area. The caret follows the anchor when the window is
+/// kept on screen sideways.
+fn place_window(anchor: Anchor, preferred: Position) -> impl FnMut(&mut druid::LayoutCtx, druid::Size) {
+    let mut side = None;
+    let mut caret_x = None;
+    move |ctx, size| {
+        let monitors: Vec<_> = druid::Screen::get_monitors().iter()
+            .map(|m| (m.is_primary(), m.virtual_rect(), m.virtual_work_rect()))
+            .collect();
+        let scale = ctx.window().get_scale().map_or(1.0, |s| s.x());
+        let desktop = placement::Desktop::new(placement::Platform::current(), &monitors, scale);
+        let side = *side.get_or_insert_with(|| {
+            let side = desktop.side(anchor, size, preferred);
+            if side != preferred {
+                ctx.submit_command(Command::new(SET_POSITION, side, Target::Global));
+            }
+            side
+        });
+        ctx.window().set_position(desktop.origin(anchor, size, side));
+        let x = desktop.caret_x(anchor, size);
+        if caret_x != Some(x) {
+            caret_x = Some(x);
+            ctx.submit_command(Command::new(SET_CARET_X, x, Target::Global));
+        }
+    }
+}
+
+fn build_root(args: Args, sizing: Sizing, palette: Vec<Color>, #[cfg(feature = "palettes")] palettes: &palettes::Files, indexed: Option<IndexedPalette>, stats: Option<Rc<RefCell<Stats>>>, preview: Option<Arc<PreviewFrames>>, window: Rc<Cell<Option<druid::Rect>>>) -> impl Fn() -> FitWindow<PickerState> {
     let checker_size = sizing.checker_size();
 
-    let curr_size = args.font_size.unwrap_or(16.0).min(20.0);
-    let init_size = (curr_size - 4.0).max(10.0);
+    let curr_size = sizing.text(args.font_size.unwrap_or(16.0).min(20.0));
+    let init_size = (curr_size - sizing.text(4.0)).max(sizing.text(10.0));
     let font = druid::FontDescriptor::new(
         args.font.clone()
             .map_or(FontFamily::MONOSPACE, FontFamily::new_unchecked)
     );
 
     let print_continuous = args.continuous;
+    let print_json = args.json;
+
+    #[cfg(feature = "palettes")]
+    let (has_tokens, spots, lut) = (palettes.has_theme(), palettes.spots.clone(), palettes.lut.clone());
+    #[cfg(not(feature = "palettes"))]
+    let has_tokens = false;
 
     move || {
-        let curr_swatch =
-            swatch(font.clone().with_size(curr_size), sizing.checker_size())
-            .background(checkered_bgbrush(checker_size))
-            .fix_size(sizing.window_width(), sizing.current_swatch_size)
-            .lens(PickerState::current_color)
-            .on_click(|ctx, _state, _env| {
-                ctx.submit_command(Command::new(COMMIT_ACTION, (), Target::Global))
-            })
-            .with_cursor(&Cursor::Arrow); // TODO: Pointer
-
-        let init_swatch =
-            swatch(font.clone().with_size(init_size), sizing.checker_size())
-            .background(checkered_bgbrush(checker_size))
-            .fix_size(sizing.window_width(), sizing.initial_swatch_size)
-            .lens(PickerState::initial_color)
-            .on_click(|ctx, _state, _env| {
-                ctx.submit_command(Command::new(RESET_ACTION, (), Target::Global))
-            })
-            .with_cursor(&Cursor::Arrow); // TODO: Pointer
-
-        let picker =
-            hsva_picker(&sizing)
-            .lens(ColorFormat::color)
-            .lens(PickerState::current_color);
-
-        let mut col = Flex::column().must_fill_main_axis(true);
-        col = match args.position {
-            Position::Under =>
-                col
-                .with_child(curr_swatch)
-                .with_child(init_swatch)
-                .with_child(picker),
-
-            Position::Over =>
-                col
-                .with_child(picker)
-                .with_child(init_swatch)
-                .with_child(curr_swatch)
-        };
+        // the picker row's, which everything else stretches to
+        let width = Rc::new(Cell::new(0.0));
+        // built for both sides, as the window may flip when placed
+        let layout = |position: Position| {
+            let curr_swatch =
+                swatch(font.clone().with_size(curr_size), &sizing, true)
+                .background(checkered_bgbrush(checker_size))
+                .expand_width().fix_height(sizing.current_swatch_size)
+                // shows the hovered format button's output, without choosing it
+                .lens(druid::lens::Map::new(
+                    |state: &PickerState| match &state.preview_format {
+                        Some(format) => ColorFormat { format: format.clone(), ..state.current_color.clone() },
+                        None => state.current_color.clone(),
+                    },
+                    |state: &mut PickerState, previewed: ColorFormat| state.current_color.color = previewed.color,
+                ))
+                .on_click(|ctx, _state, _env| {
+                    ctx.submit_command(Command::new(COMMIT_ACTION, (), Target::Global))
+                })
+                .on_right_click(|ctx, _state, _env| {
+                    ctx.submit_command(Command::new(COPY_DECLARATION, (), Target::Global))
+                })
+                .controller(QuickAdjust::new(PickerState::current_color.then(ColorFormat::color)))
+                .with_cursor(&Cursor::Arrow); // TODO: Pointer
 
-        let buttons =
-            format_buttons(&sizing)
-            .lens(ColorFormat::format)
-            .lens(PickerState::current_color);
+            let init_swatch =
+                swatch(font.clone().with_size(init_size), &sizing, false)
+                .background(checkered_bgbrush(checker_size))
+                .expand_width().fix_height(sizing.initial_swatch_size)
+                .lens(PickerState::initial_color)
+                .on_click(|ctx, _state, _env| {
+                    ctx.submit_command(Command::new(RESET_ACTION, (), Target::Global))
+                })
+                .on_right_click(|ctx, _state, _env| {
+                    ctx.submit_command(Command::new(SWAP_ACTION, (), Target::Global))
+                })
+                .with_cursor(&Cursor::Arrow); // TODO: Pointer
 
-        col = col.with_child(buttons);
+            let derived = derive_strip(font.clone().with_size(init_size), args.derive.clone())
+                .background(checkered_bgbrush(checker_size))
+                .expand_width().fix_height(sizing.initial_swatch_size)
+                .lens(PickerState::current_color);
+            let scrubber = BlendScrubber::new()
+                .background(checkered_bgbrush(checker_size))
+                .expand_width().fix_height(sizing.scrubber_height)
+                .lens(druid::lens::Map::new(
+                    |state: &PickerState| (state.initial_color.color.clone(), state.current_color.color.clone()),
+                    |state: &mut PickerState, (_, blend): (Color, Color)| state.current_color.color = blend,
+                ));
 
-        col.on_data_change(move |d| {
-            if print_continuous {
-                println!("{}", d.current_color)
+            let mut swatches = Flex::column();
+            swatches = match position {
+                Position::Under => swatches.with_child(curr_swatch).with_child(init_swatch).with_child(scrubber),
+                Position::Over => swatches.with_child(scrubber).with_child(init_swatch).with_child(curr_swatch),
+            };
+            if sizing.derive_strip {
+                swatches = match position {
+                    Position::Under => swatches.with_child(derived),
+                    Position::Over => Flex::column().with_child(derived).with_child(swatches),
+                };
             }
-        })
+            if args.white_points {
+                let strip = adaptation_strip(font.clone().with_size(init_size))
+                    .background(checkered_bgbrush(checker_size))
+                    .expand_width().fix_height(sizing.initial_swatch_size)
+                    .lens(PickerState::current_color);
+                swatches = match position {
+                    Position::Under => swatches.with_child(strip),
+                    Position::Over => Flex::column().with_child(strip).with_child(swatches),
+                };
+            }
+            #[cfg(feature = "palettes")]
+            if let Some(lut) = &lut {
+                let strip = palettes::lut_strip(font.clone().with_size(init_size), lut.clone())
+                    .expand_width().fix_height(sizing.initial_swatch_size)
+                    .lens(PickerState::current_color);
+                swatches = match position {
+                    Position::Under => swatches.with_child(strip),
+                    Position::Over => Flex::column().with_child(strip).with_child(swatches),
+                };
+            }
+            #[cfg(feature = "palettes")]
+            if let Some(library) = &spots {
+                let strip = palettes::spot_strip(font.clone().with_size(init_size), library.clone())
+                    .expand_width().fix_height(sizing.initial_swatch_size * 1.5)
+                    .lens(PickerState::current_color);
+                swatches = match position {
+                    Position::Under => swatches.with_child(strip),
+                    Position::Over => Flex::column().with_child(strip).with_child(swatches),
+                };
+            }
+
+            let picker = Either::new(
+                |state: &PickerState, _env| state.show_settings,
+                settings_panel(&sizing, &args),
+                Either::new(
+                    |state: &PickerState, _env| state.show_palette,
+                    palette_panel(&sizing),
+                    hsva_picker(&sizing, &args, &palette)
+                        .measure_width(width.clone())
+                        .lens(ColorFormat::color)
+                        .lens(PickerState::current_color),
+                ),
+            );
+            #[cfg(feature = "palettes")]
+            let picker = Either::new(
+                |state: &PickerState, _env| state.show_tokens,
+                palettes::token_panel(&sizing),
+                picker,
+            );
+            let picker = Either::new(
+                |state: &PickerState, _env| state.show_history,
+                history_panel(&sizing),
+                picker,
+            );
+
+            let mut col = Flex::column();
+            if position == Position::Under {
+                col = col.with_child(caret(&sizing, position));
+            }
+            if args.toolbar {
+                col = col.with_child(toolbar(&sizing));
+            }
+            col = match position {
+                Position::Under =>
+                    col
+                    .with_child(swatches)
+                    .with_child(picker),
+
+                Position::Over =>
+                    col
+                    .with_child(picker)
+                    .with_child(swatches)
+            };
+
+            let buttons =
+                format_buttons(&sizing, &format_options(&args))
+                .lens(ColorFormat::format)
+                .lens(PickerState::current_color);
+
+            if args.gamma_slider {
+                col = col.with_child(
+                    GammaSlider::new()
+                        .expand_width().fix_height(sizing.button_height)
+                        .padding((sizing.padding, 0.0, sizing.padding, sizing.padding))
+                        .lens(ColorFormat::color)
+                        .lens(PickerState::current_color)
+                );
+            }
+            if let Some(space) = args.viz3d {
+                col = col.with_child(
+                    Viz3d::new(space)
+                        .expand_width().fix_height(sizing.picker_size)
+                        .padding((sizing.padding, 0.0, sizing.padding, sizing.padding))
+                        .lens(ColorFormat::color)
+                        .lens(PickerState::current_color)
+                );
+            }
+            col = col.with_child(buttons);
+            #[cfg(feature = "palettes")]
+            if sizing.scheme_strip {
+                col = col.with_child(palettes::scheme_strip(&sizing));
+            }
+            if args.standalone {
+                col = col.with_child(collected_strip(&sizing));
+            }
+            col = col.with_child(recent_strip(&sizing, has_tokens));
+            if position == Position::Over {
+                col = col.with_child(caret(&sizing, position));
+            }
+            col
+        };
+        let col = Either::new(
+            |state: &PickerState, _env| state.position == Position::Under,
+            layout(Position::Under),
+            layout(Position::Over),
+        );
+
+        // the color is compared at 16 bits, so skip changes that don't show
+        // at the chosen depth
+        let last_printed = RefCell::new(String::new());
+        let stats = stats.clone();
+        let preview = preview.clone();
+        let col = col
+            .controller(AnimateSetColor::default())
+            .controller(TrackWindow { window: window.clone() })
+            .controller(KeepIndexed { palette: indexed.clone() })
+            .on_data_change(move |d: &PickerState| {
+            // only where it ends up counts
+            if d.animating {
+                return;
+            }
+            if let Some(stats) = &stats {
+                stats.borrow_mut().record(&d.current_color.color);
+            }
+            if let Some(preview) = &preview {
+                preview.show(&d.current_color.color);
+            }
+            if !print_continuous {
+                return;
+            }
+            let line = d.current_color.to_string();
+            if *last_printed.borrow() != line {
+                d.current_color.emit(print_json);
+                last_printed.replace(line);
+            }
+        });
+        let ui_colors = args.ui_colors.clone();
+        FitWindow::new(
+            width,
+            EnvScope::new(move |env, state: &PickerState| {
+                apply_theme(env, state.settings.is_dark(), ui_colors.as_ref());
+                env.set(SLIDER_LABELS, state.settings.slider_labels);
+            }, col),
+        )
     }
 }
-fn swatch(font: FontDescriptor, checker_size: f64) -> impl Widget<ColorFormat> {
+fn swatch(font: FontDescriptor, sizing: &Sizing, show_name: bool) -> impl Widget<ColorFormat> {
+    let min_text = sizing.text(9.0);
     Painter::new(move |ctx, data: &ColorFormat, env| {
         let size = ctx.size();
         ctx.clip(size.to_rect());
-        ctx.fill(size.to_rect(), &data.color.to_druid());
+        // previews the snapped color, as that's what will be output
+        let color = data.output();
+        ctx.fill(size.to_rect(), &color.to_druid());
 
         let mut text: TextLayout<String> = TextLayout::new();
         text.set_font(font.clone());
         text.set_text_alignment(TextAlignment::Center);
-        text.set_text_color(druid::Color::WHITE);
+        text.set_text_color(env.get(SWATCH_TEXT));
         text.set_wrap_width(ctx.size().width);
         text.set_text(data.to_string());
         text.rebuild_if_needed(ctx.text(), env);
 
-        let center = (size.to_vec2() - text.size().to_vec2()) / 2.0;
+        // the nearest name, then luminance (Y) and OKLab lightness (L)
+        let mut name: TextLayout<String> = TextLayout::new();
+        let name_height = if show_name {
+            let (l, _, _) = color.to_oklab();
+            name.set_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_size((font.size * 0.6).max(min_text)));
+            name.set_text_alignment(TextAlignment::Center);
+            name.set_text_color(env.get(SWATCH_TEXT).with_alpha(0.8));
+            name.set_wrap_width(ctx.size().width);
+            name.set_text(format!(
+                "{}\nY {:.3}  L {:.3}",
+                names::nearest(&color).0,
+                color.relative_luminance(),
+                l,
+            ));
+            name.rebuild_if_needed(ctx.text(), env);
+            name.size().height
+        } else {
+            0.0
+        };
+
+        let block = druid::Size::new(text.size().width, text.size().height + name_height);
+        let center = (size.to_vec2() - block.to_vec2()) / 2.0;
 
-        ctx.blurred_rect(text.size().to_rect().translate(center.x, center.y), 55.0, &druid::Color::BLACK.with_alpha(0.2));
+        ctx.blurred_rect(text.size().to_rect().translate(center.x, center.y), 55.0, &env.get(SHADOW));
 
         text.draw(ctx, center.to_point());
-    }).background(checkered_bgbrush(checker_size))
+        if data.format == Format::Ansi256 {
+            // what the terminal will actually show
+            let [r, g, b] = ansi::palette_256(ansi::nearest_256(&color));
+            let found = Color::from_rgba_f32(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0);
+            let preview = druid::Rect::from_origin_size((size.width - 17.0, 5.0), (12.0, 12.0));
+            ctx.fill(preview, &found.to_druid());
+            ctx.stroke(preview, &env.get(SWATCH_TEXT).with_alpha(0.8), 1.0);
+        }
+        if show_name {
+            name.draw(ctx, (center.x, center.y + text.size().height));
+        }
+
+        if data.color.is_clipped() {
+            let mut clipped: TextLayout<String> = TextLayout::new();
+            clipped.set_font(FontDescriptor::new(FontFamily::SYSTEM_UI).with_size(min_text));
+            clipped.set_text_color(env.get(SWATCH_TEXT));
+            clipped.set_text(locale::strings().clipped.to_string());
+            clipped.rebuild_if_needed(ctx.text(), env);
+            let badge = clipped.size().to_rect().inflate(3.0, 1.0).translate((5.0, 3.0));
+            ctx.fill(badge.to_rounded_rect(2.0), &env.get(CLIPPED_BADGE));
+            clipped.draw(ctx, (5.0, 3.0));
+        }
+    }).background(checkered_bgbrush(sizing.checker_size()))
 }
 
-fn hsva_picker(sizing: &Sizing) -> impl Widget<Color> {
-    Flex::row()
-        .with_child(SatValuePicker::new().fix_size(sizing.picker_size, sizing.picker_size))
-        .with_spacer(sizing.padding)
-        .with_child(HuePicker::new().fix_size(sizing.slider_size, sizing.picker_size))
-        .with_spacer(sizing.padding)
-        .with_child(AlphaPicker::new().fix_size(sizing.slider_size, sizing.picker_size).background(checkered_bgbrush(sizing.checker_size())))
+/// `--derive` results side by side, each in the current output format.
+fn derive_strip(font: FontDescriptor, derivations: Vec<Derivation>) -> impl Widget<ColorFormat> {
+    Painter::new(move |ctx, data: &ColorFormat, env| {
+        let size = ctx.size();
+        let width = size.width / derivations.len().max(1) as f64;
+        for (ix, derivation) in derivations.iter().enumerate() {
+            let derived = derivation.apply(&data.color);
+            let cell = druid::Rect::from_origin_size((ix as f64 * width, 0.0), (width, size.height));
+            let label = format!("{}  {}", derivation, data.format.format(&derived, &data.options));
+            strip_cell(ctx, env, &font, cell, &derived, label);
+        }
+    })
+}
+
+/// `--white-points`: the color adapted to each white point.
+fn adaptation_strip(font: FontDescriptor) -> impl Widget<ColorFormat> {
+    const WHITES: [WhitePoint; 3] = [WhitePoint::D50, WhitePoint::D65, WhitePoint::Tungsten];
+    Painter::new(move |ctx, data: &ColorFormat, env| {
+        let size = ctx.size();
+        let width = size.width / WHITES.len() as f64;
+        for (ix, white) in WHITES.iter().enumerate() {
+            let adapted = data.color.adapt(*white);
+            let cell = druid::Rect::from_origin_size((ix as f64 * width, 0.0), (width, size.height));
+            let label = format!("{}  {}", white, data.format.format(&adapted, &data.options));
+            strip_cell(ctx, env, &font, cell, &adapted, label);
+        }
+    })
+}
+
+/// Fills a cell of a strip with a color, labelled in black or white to suit.
+fn strip_cell(ctx: &mut druid::PaintCtx, env: &Env, font: &FontDescriptor, cell: druid::Rect, color: &Color, label: String) {
+    ctx.fill(cell, &color.to_druid());
+
+    let mut text: TextLayout<String> = TextLayout::new();
+    text.set_font(font.clone().with_size(font.size * 0.8));
+    text.set_text_alignment(TextAlignment::Center);
+    text.set_text_color(if color.relative_luminance() > 0.4 { druid::Color::BLACK } else { druid::Color::WHITE });
+    text.set_wrap_width(cell.width());
+    text.set_text(label);
+    text.rebuild_if_needed(ctx.text(), env);
+    text.draw(ctx, (cell.x0, cell.y0 + (cell.height() - text.size().height) / 2.0));
+}
+
+fn hsva_picker(sizing: &Sizing, args: &Args, palette: &[Color]) -> impl Widget<Color> {
+    let surface: Box<dyn Widget<Color>> = match args.model {
+        Model::Hsv => Box::new(SatValuePicker::new().with_headroom(args.hdr.unwrap_or(1.0))),
+        Model::Oklch => Box::new(ChromaLightnessPicker::new(args.gamut_clamp)),
+    };
+    let mut items = vec![
+        RowItem::Child(surface.fix_size(sizing.picker_size, sizing.picker_size).boxed()),
+        RowItem::Spacer(sizing.padding),
+        RowItem::Child(HuePicker::new().with_histogram(palette).with_wrap(args.hue_wrap).fix_size(sizing.slider_size, sizing.picker_size).boxed()),
+    ];
+    if args.lightness_slider {
+        items.push(RowItem::Spacer(sizing.padding));
+        items.push(RowItem::Child(LightnessPicker::new(args.gamut_clamp).fix_size(sizing.slider_size, sizing.picker_size).boxed()));
+    }
+    if args.chroma_slider {
+        items.push(RowItem::Spacer(sizing.padding));
+        items.push(RowItem::Child(ChromaPicker::new(args.gamut_clamp).fix_size(sizing.slider_size, sizing.picker_size).boxed()));
+    }
+    items.push(RowItem::Spacer(sizing.padding));
+    items.push(RowItem::Child(
+        AlphaPicker::new().fix_size(sizing.slider_size, sizing.picker_size).background(checkered_bgbrush(sizing.checker_size())).boxed()
+    ));
+    reading_row(sizing.rtl, items)
         .padding(sizing.padding)
 }
 
-fn format_buttons(sizing: &Sizing) -> impl Widget<Format> {
-    let mut col = Flex::row().must_fill_main_axis(true);
-    let values = Format::values();
-    let len = values.len();
-    for variant in values.into_iter().enumerate() {
-        col.add_flex_child(
-            ToggleButton::new(variant.1, variant.0 == 0, variant.0 == len-1).expand(),
-            1.0
+/// How far the toolbar's lightness and chroma steppers move, 5%.
+const STEP: f32 = 0.05;
+
+/// A caret on the edge nearest the `-x/-y` anchor, pointing at it.
+fn caret(sizing: &Sizing, position: Position) -> impl Widget<PickerState> {
+    Painter::new(move |ctx, state: &PickerState, env| {
+        let size = ctx.size();
+        let (tip, base) = match position {
+            Position::Under => (0.0, size.height),
+            Position::Over => (size.height, 0.0),
+        };
+        // kept whole within the window
+        let mid = state.caret_x.unwrap_or(size.width / 2.0).clamp(size.height, size.width - size.height);
+        let mut path = druid::kurbo::BezPath::new();
+        path.move_to((mid, tip));
+        path.line_to((mid - size.height, base));
+        path.line_to((mid + size.height, base));
+        path.close_path();
+        ctx.fill(path, &env.get(theme::LABEL_COLOR));
+    })
+    .expand_width().fix_height(sizing.caret_size)
+}
+
+/// Actions otherwise only on keys or clicks that aren't obvious, and
+/// lightness and chroma steppers.
+fn toolbar(sizing: &Sizing) -> impl Widget<PickerState> {
+    let text_size = sizing.text(9.0);
+    let t = locale::strings();
+    let action = |label: &str, selector: Selector<()>| {
+        Label::new(label)
+            .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI))
+            .with_text_size(text_size)
+            .with_text_color(LABEL_SECONDARY)
+            .on_click(move |ctx, _state: &mut PickerState, _env| {
+                ctx.submit_command(Command::new(selector, (), Target::Global))
+            })
+            .with_cursor(&Cursor::Arrow) // TODO: Pointer
+    };
+    // SCSS's lighten/darken/saturate/desaturate, but in OKLCH
+    let step = |label: &str, adjust: fn(&Color) -> Color| {
+        Label::new(label)
+            .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI))
+            .with_text_size(text_size)
+            .with_text_color(LABEL_SECONDARY)
+            .on_click(move |_ctx, state: &mut PickerState, _env| {
+                state.current_color.color = adjust(&state.current_color.color);
+            })
+            .with_cursor(&Cursor::Arrow) // TODO: Pointer
+    };
+    reading_row(sizing.rtl, vec![
+        RowItem::Child(action(t.copy, COPY_VALUE).boxed()),
+        RowItem::Spacer(8.0),
+        RowItem::Child(action(t.paste, PASTE_COLOR).boxed()),
+        RowItem::Spacer(8.0),
+        RowItem::Child(action(t.reset, RESET_ACTION).boxed()),
+        RowItem::Spacer(8.0),
+        RowItem::Child(action(t.swap, SWAP_ACTION).boxed()),
+        RowItem::FlexSpacer(1.0),
+        RowItem::Child(step("L\u{2212}", |c| c.lighten_oklch(-STEP)).boxed()),
+        RowItem::Spacer(6.0),
+        RowItem::Child(step("L+", |c| c.lighten_oklch(STEP)).boxed()),
+        RowItem::Spacer(8.0),
+        RowItem::Child(step("S\u{2212}", |c| c.saturate_oklch(-STEP)).boxed()),
+        RowItem::Spacer(6.0),
+        RowItem::Child(step("S+", |c| c.saturate_oklch(STEP)).boxed()),
+    ])
+        .fix_height(sizing.button_height)
+        .padding((sizing.padding, 0.0))
+}
+
+/// How many format buttons fit across the window; more go behind "…".
+const FORMAT_BUTTONS: usize = 6;
+
+fn format_buttons(sizing: &Sizing, options: &FormatOptions) -> impl Widget<Format> {
+    let depth = options.depth;
+    OverflowButtons::new(Format::values(options), FORMAT_BUTTONS, sizing.button_height, |group| {
+        group.with_hover(PREVIEW_FORMAT).with_buttons(move |format, button| {
+            if depth != Depth::Eight && format.eight_bit_only() {
+                button.disabled(true).with_tooltip(locale::strings().eight_bit_only)
+            } else {
+                button
+            }
+        })
+    })
+    .clears_hover(PREVIEW_FORMAT)
+}
+
+fn mini_swatch(size: f64) -> impl Widget<HistoryEntry> {
+    Painter::new(|ctx, entry: &HistoryEntry, env| {
+        let rect = ctx.size().to_rect();
+        if let Some(color) = entry.parsed_color() {
+            ctx.fill(rect, &color.to_druid());
+        }
+        ctx.stroke(rect.to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+        if entry.pinned {
+            let pin = druid::kurbo::Circle::new((rect.x1 - 3.0, rect.y0 + 3.0), 2.0);
+            ctx.fill(pin, &env.get(SELECTION));
+            ctx.stroke(pin, &env.get(MARKER), 0.5);
+        }
+    })
+    .background(checkered_bgbrush(size / 4.0))
+    .fix_size(size, size)
+}
+
+fn recent_strip(sizing: &Sizing, has_tokens: bool) -> impl Widget<PickerState> {
+    let size = sizing.recent_size;
+    let swatches = List::new(move || {
+        mini_swatch(size)
+            .on_click(|ctx, entry: &mut HistoryEntry, _env| {
+                if let Some(color) = entry.parsed_color() {
+                    ctx.submit_command(Command::new(SET_COLOR, color, Target::Global))
+                }
+            })
+            .on_right_click(|ctx, entry: &mut HistoryEntry, _env| {
+                ctx.submit_command(Command::new(TOGGLE_PIN, entry.color.clone(), Target::Global))
+            })
+            .with_cursor(&Cursor::Arrow) // TODO: Pointer
+            .padding((0.0, 0.0, 4.0, 0.0))
+    })
+    .horizontal()
+    .lens(PickerState::recent);
+
+    let toggle = Label::dynamic(|state: &PickerState, _env| {
+        let t = locale::strings();
+        String::from(if state.show_history { t.picker } else { t.history })
+    })
+    .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI))
+    .with_text_size(sizing.text(9.0))
+    .with_text_color(LABEL_SECONDARY)
+    .on_click(|_ctx, state: &mut PickerState, _env| {
+        state.show_history = !state.show_history;
+        state.show_tokens = false;
+        state.show_settings = false;
+    });
+
+    // a gear, until it's open
+    let settings = Label::dynamic(|state: &PickerState, _env| {
+        String::from(if state.show_settings { locale::strings().picker } else { "\u{2699}" })
+    })
+    .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI))
+    .with_text_size(sizing.text(9.0))
+    .with_text_color(LABEL_SECONDARY)
+    .on_click(|_ctx, state: &mut PickerState, _env| {
+        state.show_settings = !state.show_settings;
+        state.show_history = false;
+        state.show_tokens = false;
+    });
+
+    let mut items = vec![RowItem::Child(swatches.boxed()), RowItem::FlexSpacer(1.0)];
+    if has_tokens {
+        let tokens = Label::dynamic(|state: &PickerState, _env| {
+            let t = locale::strings();
+            String::from(if state.show_tokens { t.picker } else { t.tokens })
+        })
+        .with_font(FontDescriptor::new(FontFamily::SYSTEM_UI))
+        .with_text_size(sizing.text(9.0))
+        .with_text_color(LABEL_SECONDARY)
+        .on_click(|_ctx, state: &mut PickerState, _env| {
+            state.show_tokens = !state.show_tokens;
+            state.show_history = false;
+            state.show_settings = false;
+        });
+        items.push(RowItem::Child(tokens.boxed()));
+        items.push(RowItem::Spacer(8.0));
+    }
+    items.push(RowItem::Child(settings.boxed()));
+    items.push(RowItem::Spacer(8.0));
+    items.push(RowItem::Child(toggle.boxed()));
+    reading_row(sizing.rtl, items)
+        .fix_height(sizing.recent_size)
+        .padding((sizing.padding, sizing.padding / 2.0))
+}
+
+/// Colors committed in `--standalone` sessions; empty until the first.
+fn collected_strip(sizing: &Sizing) -> impl Widget<PickerState> {
+    Painter::new(|ctx, state: &PickerState, _env| {
+        let size = ctx.size();
+        let width = size.height.min(size.width / state.collected.len().max(1) as f64);
+        for (ix, color) in state.collected.iter().enumerate() {
+            let cell = druid::Rect::from_origin_size((ix as f64 * width, 0.0), (width, size.height));
+            ctx.fill(cell, &color.to_druid());
+        }
+    })
+    .fix_height(sizing.recent_size)
+    .padding((sizing.padding, sizing.padding / 2.0))
+}
+
+/// The Ctrl+P command palette: a query box and the best matches for it.
+/// Enter runs the top one.
+fn palette_panel(sizing: &Sizing) -> impl Widget<PickerState> {
+    let start = sizing.start();
+    const ROWS: usize = 8;
+    let t = locale::strings();
+    let query = TextBox::new()
+        .with_placeholder(t.palette_placeholder)
+        .with_text_size(sizing.text(12.0))
+        .controller(TakeFocus(FOCUS_PALETTE))
+        .expand_width()
+        .lens(PickerState::palette_query);
+
+    let mut col = Flex::column()
+        .cross_axis_alignment(start)
+        .with_child(query)
+        .with_spacer(4.0);
+    for row in 0..ROWS {
+        col.add_child(
+            Label::dynamic(move |state: &PickerState, _env| {
+                let formats = Format::values(&state.current_color.options);
+                palette::suggestions(&state.palette_query, &formats)
+                    .get(row)
+                    .map(|s| s.label.clone())
+                    .unwrap_or_default()
+            })
+            .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
+            .with_text_size(sizing.text(11.0))
+            .with_text_color(if row == 0 { theme::LABEL_COLOR } else { LABEL_MUTED })
+            .padding((0.0, 1.0))
         );
     }
-    col.fix_height(sizing.button_height)
-}
\ No newline at end of file
+
+    col
+        .expand_width().fix_height(sizing.picker_size)
+        .padding(sizing.padding)
+}
+
+/// Preferences saved to `settings.json`. The theme applies right away, the
+/// output options from the next launch.
+fn settings_panel(sizing: &Sizing, args: &Args) -> impl Widget<PickerState> {
+    let start = sizing.start();
+    // the settings file's formats can be the default too, but not a one-off
+    // --template
+    let options = FormatOptions { user_formats: Arc::new(args.user_formats.clone()), ..FormatOptions::default() };
+    let t = locale::strings();
+    let heading = |text: &str| {
+        Label::new(text.to_string())
+            .with_text_size(sizing.text(10.0))
+            .with_text_color(LABEL_MUTED)
+            .padding((0.0, 6.0, 0.0, 2.0))
+    };
+    let named = options.clone();
+    let default_format = druid::lens::Map::new(
+        move |s: &Settings| Format::named(&s.default_format, &named).unwrap_or(Format::Hex),
+        |s: &mut Settings, format: Format| s.default_format = format.to_string().to_lowercase(),
+    );
+
+    Flex::column()
+        .cross_axis_alignment(start)
+        .with_child(heading(t.theme))
+        .with_child(
+            ToggleGroup::new(vec![t.light.to_string(), t.dark.to_string()])
+                .with_icons(move |theme| if theme == t.dark { "☾" } else { "☀" })
+                .fix_height(sizing.button_height)
+                .lens(druid::lens::Map::new(
+                    move |s: &Settings| String::from(if s.is_dark() { t.dark } else { t.light }),
+                    move |s: &mut Settings, theme: String| {
+                        s.theme = if theme == t.dark { Theme::Dark } else { Theme::Light }
+                    },
+                ))
+        )
+        .with_spacer(4.0)
+        .with_child(Checkbox::new(t.slider_labels).lens(Settings::slider_labels))
+        .with_child(heading(t.default_format))
+        .with_child(
+            format_buttons(sizing, &options)
+                .lens(default_format)
+        )
+        .with_child(heading(t.output))
+        .with_child(Checkbox::new(t.hex_short).lens(Settings::hex_short))
+        .with_spacer(4.0)
+        .with_child(Checkbox::new(t.rgb_percent).lens(Settings::rgb_percent))
+        .with_spacer(4.0)
+        .with_child(Checkbox::new(t.alpha_number).lens(Settings::alpha_number))
+        .with_spacer(6.0)
+        .with_child(
+            Label::new(t.next_launch)
+                .with_text_size(sizing.text(9.0))
+                .with_text_color(LABEL_MUTED)
+        )
+        .on_data_change(|settings: &Settings| {
+            if let Some(path) = Settings::default_path() {
+                if let Err(e) = settings.save(&path) {
+                    warn!("could not save settings to {}: {}", path.display(), e);
+                }
+            }
+        })
+        .lens(PickerState::settings)
+        .expand_width().fix_height(sizing.picker_size)
+        .padding(sizing.padding)
+}
+
+fn history_panel(sizing: &Sizing) -> impl Widget<PickerState> {
+    let (start, rtl) = (sizing.start(), sizing.rtl);
+    let row_swatch = sizing.recent_size;
+    let (color_text, time_text, context_text) = (sizing.text(11.0), sizing.text(10.0), sizing.text(9.0));
+    let rows = List::new(move || {
+        let top = reading_row(rtl, vec![
+            RowItem::Child(mini_swatch(row_swatch).boxed()),
+            RowItem::Spacer(6.0),
+            RowItem::Child(
+                Label::dynamic(|entry: &HistoryEntry, _env| entry.color.clone())
+                    .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
+                    .with_text_size(color_text)
+                    .with_text_color(theme::LABEL_COLOR)
+                    .boxed()
+            ),
+            RowItem::FlexSpacer(1.0),
+            RowItem::Child(
+                Label::dynamic(|entry: &HistoryEntry, _env| history::relative_time(entry.timestamp, history::now()))
+                    .with_text_size(time_text)
+                    .with_text_color(LABEL_MUTED)
+                    .boxed()
+            ),
+        ]);
+        let context = Label::dynamic(|entry: &HistoryEntry, _env| entry.context.clone().unwrap_or_default())
+            .with_text_size(context_text)
+            .with_text_color(LABEL_MUTED);
+
+        Flex::column()
+            .cross_axis_alignment(start)
+            .with_child(top)
+            .with_child(context)
+            .padding((0.0, 2.0))
+            .on_click(|ctx, entry: &mut HistoryEntry, _env| {
+                if let Some(color) = entry.parsed_color() {
+                    ctx.submit_command(Command::new(SET_COLOR, color, Target::Global))
+                }
+            })
+            .with_cursor(&Cursor::Arrow) // TODO: Pointer
+    });
+
+    Scroll::new(rows)
+        .vertical()
+        .lens(PickerState::history)
+        .expand_width().fix_height(sizing.picker_size)
+        .padding(sizing.padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use structopt::StructOpt;
+
+    use super::{Args, Color, Format, History, PickerState, Settings, COLLECTED_LIMIT};
+
+    #[test]
+    fn session_round_trip() {
+        let args = Args::from_iter(&["picker", "#336699"]);
+        let fresh = || PickerState::new(&args, &History::default(), Settings::default());
+        let mut state = fresh();
+        state.current_color.color = Color::from_rgba_f32(1.0, 0.5, 0.0, 1.0);
+        state.current_color.format = Format::Hsl;
+        for i in 0..=COLLECTED_LIMIT {
+            state.collect(Color::from_rgba_f32(i as f32 / COLLECTED_LIMIT as f32, 0.0, 0.0, 1.0));
+        }
+        // the oldest is dropped
+        assert_eq!(state.collected.len(), COLLECTED_LIMIT);
+
+        let session = state.session(Some(druid::Rect::new(10.0, 20.0, 260.0, 400.0)));
+        assert_eq!(session.position, Some((10.0, 20.0)));
+        assert_eq!(session.size, Some((250.0, 380.0)));
+        let json = serde_json::to_string(&session).unwrap();
+        let restored = fresh().with_session(&serde_json::from_str(&json).unwrap());
+        assert_eq!(restored.current_color.color.pixel(), state.current_color.color.pixel());
+        assert_eq!(restored.current_color.format, Format::Hsl);
+        let pixels = |s: &PickerState| s.collected.iter().map(Color::pixel).collect::<Vec<_>>();
+        assert_eq!(pixels(&restored), pixels(&state));
+        // and nothing to restore leaves the launch color
+        let launched = fresh().with_session(&Default::default());
+        assert_eq!(launched.current_color.color.pixel(), fresh().current_color.color.pixel());
+    }
+}
@@ -0,0 +1,161 @@
+use std::io::Write;
+
+use log::{error, warn};
+use serde_json::{json, Value};
+use structopt::clap;
+
+/// Exit code used when the picker panics, distinct from a normal commit or
+/// abort (both of which exit with 0; an abort simply prints nothing).
+pub const CRASH_EXIT_CODE: i32 = 70;
+
+/// Version of what's printed on stdout and stderr: colors, `--json` objects
+/// and error objects. Bumped when a change would break existing readers.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Writes a single complete line to stdout. The line is assembled before
+/// writing so a consumer never observes a partial color, and flushed after,
+/// so one reading a pipe sees it straight away rather than when a buffer
+/// fills.
+pub fn print_line(line: impl std::fmt::Display) {
+    let stdout = std::io::stdout();
+    if let Err(e) = write_line(&mut stdout.lock(), line) {
+        warn!("could not write to stdout: {}", e);
+    }
+}
+
+fn write_line(out: &mut impl Write, line: impl std::fmt::Display) -> std::io::Result<()> {
+    let line = format!("{}\n", line);
+    out.write_all(line.as_bytes())?;
+    out.flush()
+}
+
+/// Flushes stdout and stderr. Called on every way out, as `process::exit`
+/// doesn't and some platforms drop what's left in a pipe's buffer.
+pub fn flush() {
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+}
+
+/// [`flush`]es and exits with `code`.
+pub fn exit(code: i32) -> ! {
+    flush();
+    std::process::exit(code)
+}
+
+/// Replaces the default panic output with a single JSON object on stderr and
+/// exits with [`CRASH_EXIT_CODE`].
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let payload = info.payload();
+        let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let location = info.location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+
+        error!("panic at {}: {}", location.as_deref().unwrap_or("?"), message);
+        eprintln!("{}", json!({
+            "error": "panic",
+            "message": message,
+            "location": location,
+            "exit_code": CRASH_EXIT_CODE,
+        }));
+        exit(CRASH_EXIT_CODE);
+    }));
+}
+
+/// A command line parse error as JSON: the argument at fault, clap's
+/// one-line message and its "did you mean" suggestion, if any.
+pub fn usage_error(e: &clap::Error) -> Value {
+    let text = strip_color(&e.message);
+    let mut lines = text.lines();
+    let message = lines.next().unwrap_or_default().trim_start_matches("error:").trim();
+    // flags are quoted as '--name <value>', among other quoted text
+    let field = message.split('\'')
+        .find(|quoted| quoted.starts_with('-') || quoted.starts_with('<'))
+        .and_then(|quoted| quoted.split_whitespace().next())
+        .map(str::to_string)
+        .or_else(|| e.info.as_ref().and_then(|info| info.first().cloned()));
+    let suggestion = lines
+        .find_map(|line| line.trim().strip_prefix("Did you mean "))
+        .map(|s| s.trim_end_matches('?').trim_matches('\'').to_string());
+    json!({
+        "error": "usage",
+        "kind": format!("{:?}", e.kind),
+        "field": field,
+        "message": message,
+        "suggestion": suggestion,
+    })
+}
+
+/// Removes terminal color codes, which clap adds to suggestions regardless of
+/// its color setting.
+fn strip_color(s: &str) -> String {
+    let mut plain = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // skip to the end of the sequence
+            chars.by_ref().find(char::is_ascii_alphabetic);
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use structopt::clap::{App, AppSettings, Arg};
+
+    use super::{usage_error, write_line};
+
+    #[test]
+    fn usage_errors() {
+        let app = || App::new("picker")
+            .setting(AppSettings::ColorNever)
+            .arg(Arg::with_name("position").long("position").takes_value(true).possible_values(&["under", "over"]))
+            .arg(Arg::with_name("scale").long("scale").takes_value(true).validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string())));
+
+        let e = app().get_matches_from_safe(vec!["picker", "--positon", "over"]).unwrap_err();
+        let json = usage_error(&e);
+        assert_eq!(json["kind"], "UnknownArgument");
+        assert_eq!(json["field"], "--positon");
+        assert_eq!(json["suggestion"], "--position");
+
+        let json = usage_error(&app().get_matches_from_safe(vec!["picker", "--position", "ovr"]).unwrap_err());
+        assert_eq!(json["field"], "--position");
+        assert_eq!(json["suggestion"], "over");
+
+        let json = usage_error(&app().get_matches_from_safe(vec!["picker", "--scale", "big"]).unwrap_err());
+        assert_eq!(json["field"], "--scale");
+        assert!(json["message"].as_str().unwrap().contains("invalid float literal"));
+        assert!(json["suggestion"].is_null());
+    }
+
+    /// Counts flushes, so a line left in a buffer shows.
+    #[derive(Default)]
+    struct Pipe {
+        written: Vec<u8>,
+        flushed: usize,
+    }
+    impl std::io::Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushed = self.written.len();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lines_are_flushed() {
+        let mut pipe = Pipe::default();
+        write_line(&mut pipe, "#ff0000").unwrap();
+        write_line(&mut pipe, "#00ff00").unwrap();
+        assert_eq!(pipe.written, b"#ff0000\n#00ff00\n");
+        assert_eq!(pipe.flushed, pipe.written.len());
+    }
+}
@@ -0,0 +1,207 @@
+//! The Ctrl+P command palette: fuzzy matching typed text such as `copy hsl`,
+//! `rotate hue 30` or `contrast against #fff` to an action.
+
+use color_core::{derive::{Derivation, DeriveOp}, Color, Format};
+
+/// The contrast `contrast against` aims for, WCAG AA for body text.
+pub const CONTRAST_RATIO: f32 = 4.5;
+
+/// What a palette entry does when run.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Copies the current color in a format.
+    Copy(Format),
+    Derive(Derivation),
+    /// Applies `c^(1/gamma)` to each channel.
+    Gamma(f32),
+    /// Moves the current color's lightness to reach [`CONTRAST_RATIO`]
+    /// against another color.
+    Contrast(Color),
+    Paste,
+    Reset,
+    /// Exchanges the initial and current colors.
+    Swap,
+    Commit,
+}
+
+/// An entry in the palette's list. `action` is `None` until the argument
+/// it needs has been typed.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub label: String,
+    pub action: Option<Action>,
+}
+
+#[derive(Clone, Copy)]
+enum Arg {
+    None,
+    Number(&'static str),
+    Color,
+}
+
+/// Makes the action from the typed argument, if it's valid.
+type Build = Box<dyn Fn(&str) -> Option<Action>>;
+
+struct Command {
+    name: String,
+    arg: Arg,
+    build: Build,
+}
+
+impl Command {
+    fn plain(name: impl Into<String>, action: Action) -> Self {
+        Command { name: name.into(), arg: Arg::None, build: Box::new(move |_| Some(action.clone())) }
+    }
+
+    fn derive(name: &str, op: DeriveOp, unit: &'static str) -> Self {
+        Command {
+            name: name.to_string(),
+            arg: Arg::Number(unit),
+            build: Box::new(move |arg| {
+                let amount = arg.trim_end_matches('%').parse().ok()?;
+                Some(Action::Derive(Derivation { op, amount }))
+            }),
+        }
+    }
+
+    fn hint(&self) -> String {
+        match self.arg {
+            Arg::None => self.name.clone(),
+            Arg::Number(unit) => format!("{} <{}>", self.name, unit),
+            Arg::Color => format!("{} <color>", self.name),
+        }
+    }
+}
+
+fn commands(formats: &[Format]) -> Vec<Command> {
+    let mut commands: Vec<Command> = formats.iter()
+        .map(|f| Command::plain(format!("copy {}", f.to_string().to_lowercase()), Action::Copy(f.clone())))
+        .collect();
+    commands.extend(vec![
+        Command::derive("rotate hue", DeriveOp::Spin, "degrees"),
+        Command::derive("lighten", DeriveOp::Lighten, "%"),
+        Command::derive("darken", DeriveOp::Darken, "%"),
+        Command::derive("saturate", DeriveOp::Saturate, "%"),
+        Command::derive("desaturate", DeriveOp::Desaturate, "%"),
+        Command::derive("alpha", DeriveOp::Alpha, "0-1"),
+        Command {
+            name: "gamma".to_string(),
+            arg: Arg::Number("gamma"),
+            build: Box::new(|arg| arg.parse().ok().filter(|g: &f32| *g > 0.0).map(Action::Gamma)),
+        },
+        Command {
+            name: "contrast against".to_string(),
+            arg: Arg::Color,
+            build: Box::new(|arg| color_core::parse(arg).ok().map(|(c, _)| Action::Contrast(c))),
+        },
+        Command::plain("paste", Action::Paste),
+        Command::plain("reset", Action::Reset),
+        Command::plain("swap", Action::Swap),
+        Command::plain("commit", Action::Commit),
+    ]);
+    commands
+}
+
+/// How well `query` matches `name` as a subsequence, ignoring case and
+/// spaces, or `None` if it doesn't. Runs of consecutive characters and
+/// matches at word starts score higher.
+fn score(query: &str, name: &str) -> Option<i32> {
+    let name: Vec<char> = name.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase) {
+        let i = (next..name.len()).find(|&i| name[i] == q)?;
+        score += 1;
+        if last.map_or(false, |l| l + 1 == i) {
+            score += 2;
+        }
+        if i == 0 || name[i - 1] == ' ' {
+            score += 3;
+        }
+        last = Some(i);
+        next = i + 1;
+    }
+    Some(score)
+}
+
+/// Splits `query` into the part matched against a command's name and its
+/// argument, trying each space from the left so arguments may contain spaces
+/// (`rgb(0, 0, 0)`). Without a parseable argument the whole query is the name.
+fn split<'a>(query: &'a str, command: &Command) -> (&'a str, Option<(&'a str, Action)>) {
+    if let Arg::None = command.arg {
+        return (query, (command.build)("").map(|action| ("", action)));
+    }
+    for (i, _) in query.match_indices(' ') {
+        let (name, arg) = (&query[..i], query[i..].trim());
+        if score(name, &command.name).is_some() {
+            if let Some(action) = (command.build)(arg) {
+                return (name, Some((arg, action)));
+            }
+        }
+    }
+    (query, None)
+}
+
+/// The commands matching `query`, best first. `formats` are the ones
+/// offered for copying.
+pub fn suggestions(query: &str, formats: &[Format]) -> Vec<Suggestion> {
+    let query = query.trim();
+    let mut matches: Vec<(i32, Suggestion)> = commands(formats).into_iter()
+        .filter_map(|command| {
+            let (name, action) = split(query, &command);
+            let score = score(name, &command.name)?;
+            Some(match action {
+                // a complete command beats any partial one
+                Some((arg, action)) => (score + 1000, Suggestion {
+                    label: format!("{} {}", command.name, arg).trim_end().to_string(),
+                    action: Some(action),
+                }),
+                None => (score, Suggestion { label: command.hint(), action: None }),
+            })
+        })
+        .collect();
+    matches.sort_by_key(|(score, _)| -score);
+    matches.into_iter().map(|(_, s)| s).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{score, suggestions, Action};
+    use color_core::{derive::DeriveOp, Format};
+
+    const FORMATS: [Format; 3] = [Format::Rgb, Format::Hex, Format::Hsl];
+
+    fn best(query: &str) -> Option<Action> {
+        suggestions(query, &FORMATS).into_iter().next()?.action
+    }
+
+    #[test]
+    fn fuzzy() {
+        assert!(score("cphsl", "copy hsl").is_some());
+        assert!(score("hslc", "copy hsl").is_none());
+        assert!(score("copy", "copy hsl") > score("cpy", "copy hsl"));
+        assert!(matches!(best("cp hsl"), Some(Action::Copy(Format::Hsl))));
+        assert!(matches!(best("copy h"), Some(Action::Copy(Format::Hex))));
+        assert_eq!(suggestions("cp hsl", &FORMATS)[0].label, "copy hsl");
+    }
+
+    #[test]
+    fn arguments() {
+        match best("rotate hue 30") {
+            Some(Action::Derive(d)) => assert_eq!((d.op, d.amount), (DeriveOp::Spin, 30.0)),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(suggestions("rot hue 30", &FORMATS)[0].label, "rotate hue 30");
+        match best("rot -45") {
+            Some(Action::Derive(d)) => assert_eq!((d.op, d.amount), (DeriveOp::Spin, -45.0)),
+            other => panic!("{:?}", other),
+        }
+        match best("contrast against rgb(255, 255, 255)") {
+            Some(Action::Contrast(c)) => assert_eq!(c.pixel(), [255, 255, 255, 255]),
+            other => panic!("{:?}", other),
+        }
+        assert!(best("contrast against #ff").is_none());
+        assert_eq!(suggestions("rotate", &FORMATS)[0].label, "rotate hue <degrees>");
+    }
+}
@@ -0,0 +1,356 @@
+//! The `palettes` feature: editing a `--base16` scheme or a `--vscode-theme`,
+//! and matching against a `--spot-library` or previewing through a `--lut`.
+//! Builds without it have none of these files' parsers, or their flags.
+
+pub mod base16;
+pub mod lut;
+pub mod spot;
+pub mod vscode_theme;
+
+use std::{path::PathBuf, sync::Arc};
+
+use druid::widget::{Flex, Label, List, Painter, Scroll};
+use druid::{Command, Cursor, Data, FontDescriptor, FontFamily, Lens, LensExt, RenderContext, Selector, Target, Widget, WidgetExt, theme};
+use log::{info, warn};
+use structopt::StructOpt;
+
+use crate::color::Color;
+use crate::widget_util::*;
+use crate::widgets::*;
+use crate::{output, strip_cell, ColorFormat, PickerState, Sizing};
+use lut::Lut;
+use vscode_theme::{Token, TokenGroup};
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct Args {
+    /// Base16/Base24 scheme to edit; the committed color is written back into
+    /// the slot being edited
+    #[structopt(long, parse(from_os_str))]
+    base16: Option<PathBuf>,
+
+    /// Spot color library to match against, as CSV of name,L,a,b (CIELAB
+    /// D50, as published); the nearest entries are shown under the swatches
+    /// with their ΔE
+    #[structopt(long, parse(from_os_str))]
+    spot_library: Option<PathBuf>,
+
+    /// 3D LUT (.cube) to preview the color through, before and after, under
+    /// the swatches
+    #[structopt(long, parse(from_os_str))]
+    lut: Option<PathBuf>,
+
+    /// Commit the color after the --lut rather than before
+    #[structopt(long, requires = "lut")]
+    commit_lut: bool,
+
+    /// Scheme slot to edit, starting from its color (e.g. base0D); defaults to
+    /// the first slot holding the launch color
+    #[structopt(long)]
+    slot: Option<String>,
+
+    /// VS Code color theme to edit; pick a token from the TOKENS panel and the
+    /// committed color is written back to it
+    #[structopt(long, parse(from_os_str))]
+    vscode_theme: Option<PathBuf>,
+
+    /// Theme token to start on, e.g. editor.background
+    #[structopt(long)]
+    token: Option<String>,
+
+    /// With --vscode-theme, print a JSON merge patch for the token on commit
+    /// instead of the color, and leave the theme file alone
+    #[structopt(long)]
+    theme_patch: bool,
+}
+
+pub const EDIT_TOKEN: Selector<String> = Selector::new("edit-token");
+
+/// What's shown of the loaded files.
+#[derive(Clone, Default, Data, Lens)]
+pub struct State {
+    /// Colors of the `--base16` scheme, and the slot being edited.
+    scheme: Arc<Vec<Color>>,
+    scheme_slot: Option<usize>,
+    /// Colors of the `--vscode-theme`, and the token being edited.
+    tokens: Arc<Vec<TokenGroup>>,
+    token: Option<String>,
+}
+
+impl State {
+    /// The `--base16` scheme's colors, by slot.
+    pub fn scheme(&self) -> &Arc<Vec<Color>> {
+        &self.scheme
+    }
+
+    /// The colors of the scheme and the theme.
+    pub fn colors(&self) -> Vec<Color> {
+        let tokens = self.tokens.iter().flat_map(|g| g.tokens.iter().map(|t| t.color.clone()));
+        self.scheme.iter().cloned().chain(tokens).collect()
+    }
+}
+
+/// The files loaded from [`Args`], written back to on commit.
+pub struct Files {
+    /// The `--base16` scheme and where it was read from.
+    scheme: Option<(base16::Scheme, PathBuf)>,
+    /// The `--vscode-theme` and where it was read from.
+    theme: Option<(vscode_theme::Theme, PathBuf)>,
+    theme_patch: bool,
+    pub spots: Option<Arc<spot::Library>>,
+    pub lut: Option<Arc<Lut>>,
+    /// Whether the `--lut` applies when committing, with `--commit-lut`.
+    commit_lut: bool,
+}
+
+impl Files {
+    /// Loads what `args` name, ignoring files that can't be read.
+    pub fn load(args: &Args) -> Self {
+        let scheme = args.base16.as_ref().and_then(|path| match base16::Scheme::load(path) {
+            Ok(scheme) => Some((scheme, path.clone())),
+            Err(e) => {
+                warn!("ignoring base16 scheme: {}", e);
+                None
+            }
+        });
+        let theme = args.vscode_theme.as_ref().and_then(|path| match vscode_theme::Theme::load(path) {
+            Ok(theme) => Some((theme, path.clone())),
+            Err(e) => {
+                warn!("ignoring vscode theme: {}", e);
+                None
+            }
+        });
+        let spots = args.spot_library.as_ref().and_then(|path| match spot::Library::load(path) {
+            Ok(library) => Some(Arc::new(library)),
+            Err(e) => {
+                warn!("ignoring spot library: {}", e);
+                None
+            }
+        });
+        let lut = args.lut.as_ref().and_then(|path| match Lut::load(path) {
+            Ok(lut) => Some(Arc::new(lut)),
+            Err(e) => {
+                warn!("ignoring LUT: {}", e);
+                None
+            }
+        });
+        Files { scheme, theme, theme_patch: args.theme_patch, spots, lut, commit_lut: args.commit_lut }
+    }
+
+    pub fn has_scheme(&self) -> bool {
+        self.scheme.is_some()
+    }
+    pub fn has_theme(&self) -> bool {
+        self.theme.is_some()
+    }
+
+    /// Starts `state` on the scheme slot or theme token `args` name, if any;
+    /// the slot defaults to the first holding the `launch` color.
+    pub fn start(&self, args: &Args, launch: &Color, state: &mut PickerState) {
+        if let Some((scheme, path)) = &self.scheme {
+            let slot = match &args.slot {
+                Some(name) => scheme.slot_named(name),
+                None => scheme.slot_with(launch),
+            };
+            match slot {
+                Some(slot) => info!("editing {} of {}", scheme.name(slot), path.display()),
+                None => warn!("no slot to edit in {}, it won't be written", path.display()),
+            }
+            state.palettes.scheme = Arc::new(scheme.slots().map(|(_, c)| c.clone()).collect());
+            state.palettes.scheme_slot = slot;
+            if let (Some(slot), true) = (slot, args.slot.is_some()) {
+                state.initial_color.color = state.palettes.scheme[slot].clone();
+                state.current_color.color = state.palettes.scheme[slot].clone();
+            }
+        }
+        if let Some((theme, _)) = &self.theme {
+            state.palettes.tokens = Arc::new(theme.groups());
+            match &args.token {
+                Some(key) => self.edit_token(key, state),
+                None => state.show_tokens = true,
+            }
+        }
+    }
+
+    /// Starts over from the token's color.
+    pub fn edit_token(&self, key: &str, state: &mut PickerState) {
+        let color = match &self.theme {
+            Some((theme, _)) => theme.color(key),
+            None => return,
+        };
+        match color {
+            Some(color) => {
+                state.palettes.token = Some(key.to_string());
+                state.initial_color.color = color.clone();
+                state.current_color.color = color;
+                state.show_tokens = false;
+            }
+            None => warn!("no color for {} in the theme", key),
+        }
+    }
+
+    /// A copy of the color to commit, run through the `--lut` with
+    /// `--commit-lut`; the edited color is left alone, so a later
+    /// `--standalone` commit isn't graded twice.
+    pub fn graded(&self, color: &ColorFormat) -> ColorFormat {
+        match (&self.lut, self.commit_lut) {
+            (Some(lut), true) => ColorFormat { color: lut.apply(&color.color), ..color.clone() },
+            _ => color.clone(),
+        }
+    }
+
+    /// Prints a JSON merge patch for the token being edited instead of the
+    /// color, with `--theme-patch`; whether it did.
+    pub fn print_patch(&self, state: &State, color: &Color) -> bool {
+        match &state.token {
+            Some(key) if self.theme_patch => {
+                output::print_line(vscode_theme::patch(key, color));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Writes the committed color back to the slot or token being edited.
+    pub fn write(&mut self, state: &State, color: &Color) {
+        if let (Some((scheme, path)), Some(slot)) = (&mut self.scheme, state.scheme_slot) {
+            scheme.set(slot, color);
+            match scheme.save(path) {
+                Ok(()) => info!("wrote {} to {}", scheme.name(slot), path.display()),
+                Err(e) => warn!("could not write scheme to {}: {}", path.display(), e),
+            }
+        }
+        if let (Some((theme, path)), Some(key), false) = (&mut self.theme, &state.token, self.theme_patch) {
+            theme.set(key, color);
+            match theme.save(path) {
+                Ok(()) => info!("wrote {} to {}", key, path.display()),
+                Err(e) => warn!("could not write theme to {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+/// `--lut`: the color before and after the LUT.
+pub fn lut_strip(font: FontDescriptor, lut: Arc<Lut>) -> impl Widget<ColorFormat> {
+    Painter::new(move |ctx, data: &ColorFormat, env| {
+        let size = ctx.size();
+        let half = size.width / 2.0;
+        let after = lut.apply(&data.color);
+        let cells = [
+            ("before", data.color.clone()),
+            (lut.title.as_deref().unwrap_or("after"), after),
+        ];
+        for (ix, (name, color)) in cells.iter().enumerate() {
+            let cell = druid::Rect::from_origin_size((ix as f64 * half, 0.0), (half, size.height));
+            let label = format!("{}  {}", name, data.format.format(color, &data.options));
+            strip_cell(ctx, env, &font, cell, color, label);
+        }
+    })
+}
+
+/// How many `--spot-library` matches the spot strip shows.
+const SPOT_MATCHES: usize = 3;
+
+/// The spot colors nearest the output color, each with its ΔE.
+pub fn spot_strip(font: FontDescriptor, library: Arc<spot::Library>) -> impl Widget<ColorFormat> {
+    Painter::new(move |ctx, data: &ColorFormat, env| {
+        let size = ctx.size();
+        let width = size.width / SPOT_MATCHES as f64;
+        for (ix, (name, color, delta)) in library.nearest(&data.output(), SPOT_MATCHES).into_iter().enumerate() {
+            let cell = druid::Rect::from_origin_size((ix as f64 * width, 0.0), (width, size.height));
+            strip_cell(ctx, env, &font, cell, &color, format!("{}\n\u{394}E {:.1}", name, delta));
+        }
+    })
+}
+
+/// The `--base16` slots, with the one being edited following the current color.
+pub fn scheme_strip(sizing: &Sizing) -> impl Widget<PickerState> {
+    Painter::new(|ctx, state: &PickerState, env| {
+        let size = ctx.size();
+        let scheme = &state.palettes.scheme;
+        let width = size.width / scheme.len().max(1) as f64;
+        for (ix, color) in scheme.iter().enumerate() {
+            let cell = druid::Rect::from_origin_size((ix as f64 * width, 0.0), (width, size.height));
+            if state.palettes.scheme_slot == Some(ix) {
+                ctx.fill(cell, &state.current_color.color.to_druid());
+                ctx.stroke(cell.inset(-1.0), &env.get(theme::LABEL_COLOR), 2.0);
+            } else {
+                ctx.fill(cell, &color.to_druid());
+            }
+        }
+    })
+    .fix_height(sizing.recent_size)
+    .padding((sizing.padding, sizing.padding / 2.0))
+}
+
+/// The `--vscode-theme` colors grouped by UI area; clicking one edits it.
+pub fn token_panel(sizing: &Sizing) -> impl Widget<PickerState> {
+    let (start, rtl) = (sizing.start(), sizing.rtl);
+    let row_swatch = sizing.recent_size;
+    let (key_text, area_text) = (sizing.text(11.0), sizing.text(10.0));
+    let groups = List::new(move || {
+        let tokens = List::new(move || {
+            reading_row(rtl, vec![
+                RowItem::Child(
+                    Painter::new(|ctx, token: &Token, env| {
+                        let rect = ctx.size().to_rect();
+                        ctx.fill(rect, &token.color.to_druid());
+                        ctx.stroke(rect.to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+                    })
+                    .background(checkered_bgbrush(row_swatch / 4.0))
+                    .fix_size(row_swatch, row_swatch)
+                    .boxed()
+                ),
+                RowItem::Spacer(6.0),
+                RowItem::Child(
+                    Label::dynamic(|token: &Token, _env| token.key.clone())
+                        .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
+                        .with_text_size(key_text)
+                        .with_text_color(theme::LABEL_COLOR)
+                        .boxed()
+                ),
+            ])
+                .padding((0.0, 1.0))
+                .on_click(|ctx, token: &mut Token, _env| {
+                    ctx.submit_command(Command::new(EDIT_TOKEN, token.key.clone(), Target::Global))
+                })
+                .with_cursor(&Cursor::Arrow) // TODO: Pointer
+        });
+
+        Flex::column()
+            .cross_axis_alignment(start)
+            .with_child(
+                Label::dynamic(|group: &TokenGroup, _env| group.area.clone())
+                    .with_text_size(area_text)
+                    .with_text_color(LABEL_MUTED)
+            )
+            .with_child(tokens.lens(TokenGroup::tokens))
+            .padding((0.0, 0.0, 0.0, 4.0))
+    });
+
+    Scroll::new(groups)
+        .vertical()
+        .lens(PickerState::palettes.then(State::tokens))
+        .expand_width().fix_height(sizing.picker_size)
+        .padding(sizing.padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Files, Lut};
+    use crate::{Color, ColorFormat, Format};
+
+    #[test]
+    fn commits_grade_once() {
+        // halves every channel
+        let lut = Lut::parse("LUT_3D_SIZE 2\n0 0 0\n0.5 0 0\n0 0.5 0\n0.5 0.5 0\n0 0 0.5\n0.5 0 0.5\n0 0.5 0.5\n0.5 0.5 0.5\n").unwrap();
+        let files = |commit_lut| Files { scheme: None, theme: None, theme_patch: false, spots: None, lut: Some(lut.clone().into()), commit_lut };
+        let current = ColorFormat::new(Color::from_rgba_f32(1.0, 1.0, 1.0, 1.0), Format::Hex);
+        let once = Color::from_rgba_f32(0.5, 0.5, 0.5, 1.0);
+        // a second --standalone commit grades the same color, not the first's result
+        for _ in 0..2 {
+            assert_eq!(files(true).graded(&current).color.pixel(), once.pixel());
+        }
+        assert_eq!(current.color.pixel(), [255; 4]);
+        assert_eq!(files(false).graded(&current).to_string(), current.to_string());
+    }
+}
@@ -0,0 +1,149 @@
+//! Base16/Base24 scheme files, e.g.
+//!
+//! ```yaml
+//! scheme: "Default Dark"
+//! base00: "181818"
+//! base0D: "7cafc2"
+//! ```
+//!
+//! Only the `baseXX` lines are interpreted, and writing back only changes the
+//! hex digits of the edited slot, so comments and quoting survive.
+
+use std::{fs, io, ops::Range, path::Path};
+
+use crate::color::{Color, HexShort};
+
+#[derive(Debug, Clone)]
+pub struct Scheme {
+    lines: Vec<String>,
+    /// Slot name, color and the line it was read from.
+    slots: Vec<(String, Color, usize)>,
+}
+
+impl Scheme {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let lines: Vec<String> = text.lines().map(String::from).collect();
+        let mut slots = Vec::new();
+        for (ix, line) in lines.iter().enumerate() {
+            if let Some((name, range)) = parse_slot(line) {
+                let hex = &line[range];
+                let color = format!("#{}", hex).parse::<Color>()
+                    .map_err(|_| format!("line {}: invalid color {}", ix + 1, hex))?;
+                slots.push((name.to_string(), color, ix));
+            }
+        }
+        if slots.is_empty() {
+            return Err("no baseXX colors found".to_string());
+        }
+        Ok(Scheme { lines, slots })
+    }
+
+    /// Slot names and colors, in file order.
+    pub fn slots(&self) -> impl Iterator<Item = (&str, &Color)> {
+        self.slots.iter().map(|(name, color, _)| (name.as_str(), color))
+    }
+
+    pub fn name(&self, slot: usize) -> &str {
+        &self.slots[slot].0
+    }
+
+    pub fn slot_named(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|(n, _, _)| n.eq_ignore_ascii_case(name))
+    }
+
+    /// The first slot holding this color, ignoring alpha.
+    pub fn slot_with(&self, color: &Color) -> Option<usize> {
+        let hex = |c: &Color| c.to_hex_string(HexShort::Never)[..7].to_string();
+        self.slots.iter().position(|(_, c, _)| hex(c) == hex(color))
+    }
+
+    pub fn set(&mut self, slot: usize, color: &Color) {
+        let (_, old, line) = &mut self.slots[slot];
+        let text = &mut self.lines[*line];
+        let hex = &color.to_hex_string(HexShort::Never)[1..7];
+        if let Some((_, range)) = parse_slot(text) {
+            text.replace_range(range, hex);
+        }
+        *old = color.clone();
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl std::fmt::Display for Scheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits `base0D: "7cafc2"  # comment` into `"base0D"` and where in the
+/// line `7cafc2` is.
+fn parse_slot(line: &str) -> Option<(&str, Range<usize>)> {
+    let colon = line.find(':')?;
+    let key = line[..colon].trim();
+    let is_slot = key.len() == 6 && key.starts_with("base") && key[4..].chars().all(|c| c.is_ascii_hexdigit());
+    if !is_slot {
+        return None;
+    }
+    let quote = |c| c == '"' || c == '\'';
+    // the first part between #s that isn't only space and quotes
+    let mut start = colon + 1;
+    for part in line[colon + 1..].split('#') {
+        let value = part.trim_start().trim_start_matches(quote);
+        let hex = value.trim_end().trim_end_matches(quote);
+        if !hex.is_empty() {
+            let start = start + part.len() - value.len();
+            let valid = hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit());
+            return if valid { Some((key, start..start + hex.len())) } else { None };
+        }
+        start += part.len() + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scheme;
+    use crate::color::Color;
+
+    const SCHEME: &str = "scheme: \"Test\"\n# comment\nbase00: \"181818\"\nbase01: '#282828' # dark\nbase0D: 7cafc2\n";
+
+    #[test]
+    fn parse_and_find() {
+        let scheme = Scheme::parse(SCHEME).unwrap();
+        let names: Vec<_> = scheme.slots().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["base00", "base01", "base0D"]);
+        assert_eq!(scheme.slot_named("BASE0D"), Some(2));
+        let grey = Color::from_rgba_f32(0x28 as f32 / 255.0, 0x28 as f32 / 255.0, 0x28 as f32 / 255.0, 1.0);
+        assert_eq!(scheme.slot_with(&grey), Some(1));
+        assert!(Scheme::parse("scheme: x\n").is_err());
+        assert!(Scheme::parse("base00: \"zzzzzz\"\n").is_err());
+    }
+
+    #[test]
+    fn set_preserves_formatting() {
+        let mut scheme = Scheme::parse(SCHEME).unwrap();
+        let red = Color::from_rgba_f32(1.0, 0.0, 0.0, 1.0);
+        scheme.set(1, &red);
+        scheme.set(2, &red);
+        assert_eq!(
+            scheme.to_string(),
+            "scheme: \"Test\"\n# comment\nbase00: \"181818\"\nbase01: '#ff0000' # dark\nbase0D: ff0000\n",
+        );
+
+        // only the value changes, not the same digits in a comment
+        let mut scheme = Scheme::parse("base00: \"181818\" # was 181818\n").unwrap();
+        scheme.set(0, &red);
+        assert_eq!(scheme.to_string(), "base00: \"ff0000\" # was 181818\n");
+    }
+}
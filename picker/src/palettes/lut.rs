@@ -0,0 +1,120 @@
+//! 3D LUTs in the `.cube` format, e.g.
+//!
+//! ```text
+//! TITLE "Warm film"
+//! LUT_3D_SIZE 2
+//! 0.0 0.0 0.0
+//! 1.0 0.0 0.0
+//! ...
+//! ```
+//!
+//! Table rows run red fastest, then green, then blue. 1D LUTs aren't
+//! supported.
+
+use std::{fs, path::Path};
+
+use crate::color::Color;
+
+#[derive(Debug, Clone)]
+pub struct Lut {
+    pub title: Option<String>,
+    size: usize,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    table: Vec<[f32; 3]>,
+}
+
+impl Lut {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lut = Lut { title: None, size: 0, domain_min: [0.0; 3], domain_max: [1.0; 3], table: Vec::new() };
+        for (ix, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |what: &str| format!("line {}: {}", ix + 1, what);
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match keyword {
+                "TITLE" => lut.title = Some(rest.trim().trim_matches('"').to_string()),
+                "LUT_3D_SIZE" => lut.size = rest.trim().parse().ok().filter(|&n| n >= 2).ok_or_else(|| err("invalid size"))?,
+                "LUT_1D_SIZE" => return Err(err("1D LUTs aren't supported")),
+                "DOMAIN_MIN" => lut.domain_min = triple(line).ok_or_else(|| err("expected three numbers"))?,
+                "DOMAIN_MAX" => lut.domain_max = triple(line).ok_or_else(|| err("expected three numbers"))?,
+                // other keywords don't affect the colors
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+                _ => lut.table.push(triple(line).ok_or_else(|| err("expected three numbers"))?),
+            }
+        }
+        if lut.size == 0 {
+            return Err("missing LUT_3D_SIZE".to_string());
+        }
+        if lut.table.len() != lut.size.pow(3) {
+            return Err(format!("expected {} rows, found {}", lut.size.pow(3), lut.table.len()));
+        }
+        Ok(lut)
+    }
+
+    /// Runs the color through the LUT, interpolating trilinearly between
+    /// entries. Alpha is kept.
+    pub fn apply(&self, color: &Color) -> Color {
+        let n = self.size - 1;
+        // position in table steps along each axis, split into cell and fraction
+        let pos: Vec<(usize, f32)> = [color.red(), color.green(), color.blue()].iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let t = ((c - self.domain_min[i]) / (self.domain_max[i] - self.domain_min[i])).clamp(0.0, 1.0) * n as f32;
+                let cell = (t.floor() as usize).min(n - 1);
+                (cell, t - cell as f32)
+            })
+            .collect();
+        let at = |r: usize, g: usize, b: usize| self.table[r + g * self.size + b * self.size * self.size];
+        let lerp = |x: [f32; 3], y: [f32; 3], t: f32| [0, 1, 2].map(|i| x[i] + (y[i] - x[i]) * t);
+
+        let ((r, fr), (g, fg), (b, fb)) = (pos[0], pos[1], pos[2]);
+        let face = |b: usize| lerp(
+            lerp(at(r, g, b), at(r + 1, g, b), fr),
+            lerp(at(r, g + 1, b), at(r + 1, g + 1, b), fr),
+            fg,
+        );
+        let [r, g, b] = lerp(face(b), face(b + 1), fb);
+        Color::from_rgba_f32(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), color.alpha())
+    }
+}
+
+fn triple(line: &str) -> Option<[f32; 3]> {
+    let values: Vec<f32> = line.split_whitespace()
+        .skip_while(|s| s.parse::<f32>().is_err())
+        .map(|s| s.parse().ok())
+        .collect::<Option<_>>()?;
+    match values[..] {
+        [r, g, b] => Some([r, g, b]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lut;
+    use crate::color::Color;
+
+    /// Swaps red and blue.
+    const SWAP: &str = "TITLE \"swap\"\n# 2x2x2\nLUT_3D_SIZE 2\n\
+        0 0 0\n0 0 1\n0 1 0\n0 1 1\n1 0 0\n1 0 1\n1 1 0\n1 1 1\n";
+
+    #[test]
+    fn parse_and_apply() {
+        let lut = Lut::parse(SWAP).unwrap();
+        assert_eq!(lut.title.as_deref(), Some("swap"));
+        let c = Color::from_rgba_f32(0.2, 0.4, 0.8, 0.5);
+        assert_eq!(lut.apply(&c).pixel(), Color::from_rgba_f32(0.8, 0.4, 0.2, 0.5).pixel());
+
+        assert!(Lut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(Lut::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+        assert!(Lut::parse("LUT_3D_SIZE 2\n0 0\n").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+//! Spot color libraries supplied by the user, as CSV of name and CIELAB
+//! values under D50, as spot color guides publish them, e.g.
+//!
+//! ```csv
+//! name,L,a,b
+//! Warm Red,55.6,69.3,57.4
+//! "Blue, Reflex",24.9,30.2,-72.4
+//! ```
+//!
+//! A header row, blank lines and `#` comments are skipped.
+
+use std::{fs, path::Path};
+
+use crate::color::{Color, WhitePoint};
+
+#[derive(Debug, Clone)]
+pub struct Library {
+    /// Name, color and its CIELAB under sRGB's D65, which is what it's
+    /// compared in.
+    entries: Vec<(String, Color, (f32, f32, f32))>,
+}
+
+impl Library {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (ix, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // the name may itself contain commas, so take the numbers from the end
+            let fields: Vec<&str> = line.rsplitn(4, ',').collect();
+            let lab: Option<Vec<f32>> = fields.iter().take(3).map(|f| f.trim().parse().ok()).collect();
+            match (lab, fields.get(3)) {
+                (Some(lab), Some(name)) => {
+                    let name = name.trim().trim_matches('"').to_string();
+                    let color = Color::from_laba_white_f32(lab[2], lab[1], lab[0], 1.0, WhitePoint::D50);
+                    let lab = color.to_lab();
+                    entries.push((name, color, lab));
+                }
+                // a header
+                _ if ix == 0 => {}
+                _ => return Err(format!("line {}: expected name,L,a,b", ix + 1)),
+            }
+        }
+        if entries.is_empty() {
+            return Err("no colors found".to_string());
+        }
+        Ok(Library { entries })
+    }
+
+    /// The `n` entries closest to `color`, nearest first, with their color
+    /// and ΔE (CIE76).
+    pub fn nearest(&self, color: &Color, n: usize) -> Vec<(&str, Color, f32)> {
+        let (l, a, b) = color.to_lab();
+        let mut found: Vec<(&str, Color, f32)> = self.entries.iter()
+            .map(|(name, entry, (el, ea, eb))| {
+                let delta = ((l - el).powi(2) + (a - ea).powi(2) + (b - eb).powi(2)).sqrt();
+                (name.as_str(), entry.clone(), delta)
+            })
+            .collect();
+        found.sort_by(|x, y| x.2.total_cmp(&y.2));
+        found.truncate(n);
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Library;
+    use crate::color::{Color, WhitePoint};
+
+    #[test]
+    fn parse_and_match() {
+        let library = Library::parse("name,L,a,b\n# reds\nWarm Red,55.6,69.3,57.4\n\n\"Blue, Reflex\",24.9,30.2,-72.4\n").unwrap();
+        assert_eq!(library.entries.len(), 2);
+        assert_eq!(library.entries[1].0, "Blue, Reflex");
+
+        let red = Color::from_laba_white_f32(56.0, 68.0, 58.0, 1.0, WhitePoint::D50);
+        let nearest = library.nearest(&red, 1);
+        assert_eq!(nearest.len(), 1);
+        assert_eq!(nearest[0].0, "Warm Red");
+        assert!(nearest[0].2 < 2.0);
+
+        assert!(Library::parse("name,L,a,b\nRed,50,x,0\n").is_err());
+        assert!(Library::parse("name,L,a,b\n").is_err());
+    }
+}
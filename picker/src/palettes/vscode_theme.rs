@@ -0,0 +1,181 @@
+//! VS Code color theme files. Only the `colors` section (workbench colors
+//! such as `editor.background`) is edited; the rest of the file is kept as is,
+//! though comments are dropped when it's written back.
+
+use std::{fs, io, path::Path, sync::Arc};
+
+use druid::{Data, Lens};
+use serde_json::{json, Value};
+
+use crate::color::{Color, HexShort};
+
+/// A workbench color, e.g. `editor.background`.
+#[derive(Clone, Debug, Data)]
+pub struct Token {
+    pub key: String,
+    pub color: Color,
+}
+
+/// Tokens sharing the part of the key before the first dot.
+#[derive(Clone, Debug, Data, Lens)]
+pub struct TokenGroup {
+    pub area: String,
+    pub tokens: Arc<Vec<Token>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    json: Value,
+}
+
+impl Theme {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parses the theme, allowing the comments and trailing commas VS Code does.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let json: Value = serde_json::from_str(&strip_jsonc(text)).map_err(|e| e.to_string())?;
+        if !json.get("colors").map_or(false, Value::is_object) {
+            return Err("no \"colors\" section".to_string());
+        }
+        Ok(Theme { json })
+    }
+
+    /// Colors in file order; values that aren't colors are skipped.
+    pub fn tokens(&self) -> Vec<Token> {
+        self.json["colors"].as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, value)| {
+                let color = value.as_str()?.parse().ok()?;
+                Some(Token { key: key.clone(), color })
+            })
+            .collect()
+    }
+
+    /// Tokens grouped by UI area, in order of each area's first token.
+    pub fn groups(&self) -> Vec<TokenGroup> {
+        let mut groups: Vec<(String, Vec<Token>)> = Vec::new();
+        for token in self.tokens() {
+            let area = token.key.split('.').next().unwrap_or_default().to_string();
+            match groups.iter_mut().find(|(a, _)| *a == area) {
+                Some((_, tokens)) => tokens.push(token),
+                None => groups.push((area, vec![token])),
+            }
+        }
+        groups.into_iter()
+            .map(|(area, tokens)| TokenGroup { area, tokens: Arc::new(tokens) })
+            .collect()
+    }
+
+    pub fn color(&self, key: &str) -> Option<Color> {
+        self.json["colors"][key].as_str()?.parse().ok()
+    }
+
+    pub fn set(&mut self, key: &str, color: &Color) {
+        self.json["colors"][key] = Value::from(color.to_hex_string(HexShort::Never));
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut text = serde_json::to_string_pretty(&self.json)?;
+        text.push('\n');
+        fs::write(path, text)
+    }
+}
+
+/// A JSON merge patch (RFC 7396) setting one token.
+pub fn patch(key: &str, color: &Color) -> Value {
+    json!({ "colors": { key: color.to_hex_string(HexShort::Never) } })
+}
+
+/// Removes `//` and `/* */` comments and trailing commas outside strings.
+fn strip_jsonc(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.peek().map_or(false, |&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            ('}', _) | (']', _) => {
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{patch, Theme};
+    use crate::color::Color;
+
+    const THEME: &str = r##"{
+        // comment
+        "name": "Test /* not a comment */",
+        "colors": {
+            "editor.background": "#1e1e1e",
+            "sideBar.background": "#252526", /* trailing */
+            "editor.foreground": "#d4d4d480",
+            "editorCursor.foreground": null,
+        },
+        "tokenColors": [],
+    }"##;
+
+    #[test]
+    fn parse_and_group() {
+        let theme = Theme::parse(THEME).unwrap();
+        assert_eq!(theme.json["name"], "Test /* not a comment */");
+        let groups: Vec<_> = theme.groups().iter()
+            .map(|g| (g.area.clone(), g.tokens.iter().map(|t| t.key.clone()).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(groups, vec![
+            ("editor".to_string(), vec!["editor.background".to_string(), "editor.foreground".to_string()]),
+            ("sideBar".to_string(), vec!["sideBar.background".to_string()]),
+        ]);
+        assert!(Theme::parse("{\"name\": \"x\"}").is_err());
+    }
+
+    #[test]
+    fn set_and_patch() {
+        let mut theme = Theme::parse(THEME).unwrap();
+        let red = Color::from_rgba_f32(1.0, 0.0, 0.0, 0.5);
+        theme.set("editor.background", &red);
+        assert_eq!(theme.json["colors"]["editor.background"], "#ff000080");
+        assert_eq!(theme.tokens()[0].key, "editor.background");
+        assert_eq!(patch("editor.background", &red).to_string(), r##"{"colors":{"editor.background":"#ff000080"}}"##);
+    }
+}
@@ -0,0 +1,72 @@
+//! `--parent-pid`: watching the process that launched the picker, so a
+//! crashed editor doesn't leave a picker window behind.
+
+use std::{convert::TryFrom, thread, time::Duration};
+
+use log::info;
+
+/// How often the parent is checked.
+const POLL: Duration = Duration::from_millis(500);
+
+/// Calls `on_exit` from a background thread once `pid` is no longer running.
+pub fn watch(pid: u32, on_exit: impl FnOnce() + Send + 'static) {
+    thread::spawn(move || {
+        while alive(pid) {
+            thread::sleep(POLL);
+        }
+        info!("parent {} exited", pid);
+        on_exit();
+    });
+}
+
+/// Whether a process with `pid` is running. One we may not signal still is.
+/// So is an unrelated process that has since been given the pid: `kill`
+/// can't tell them apart, so a reused pid keeps the picker open.
+#[cfg(unix)]
+pub fn alive(pid: u32) -> bool {
+    // 0 and negative pids address process groups, not a process
+    let pid = match libc::pid_t::try_from(pid) {
+        Ok(pid) if pid > 0 => pid,
+        _ => return false,
+    };
+    let signalled = unsafe { libc::kill(pid, 0) } == 0;
+    signalled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Whether a process with `pid` is running. One we may not open still is.
+#[cfg(windows)]
+pub fn alive(pid: u32) -> bool {
+    use winapi::um::{errhandlingapi::GetLastError, handleapi::CloseHandle, processthreadsapi::OpenProcess, synchapi::WaitForSingleObject, winnt::SYNCHRONIZE};
+    use winapi::shared::winerror::{ERROR_ACCESS_DENIED, WAIT_TIMEOUT};
+
+    unsafe {
+        let handle = OpenProcess(SYNCHRONIZE, 0, pid);
+        if handle.is_null() {
+            // e.g. an elevated editor
+            return GetLastError() == ERROR_ACCESS_DENIED;
+        }
+        let running = WaitForSingleObject(handle, 0) == WAIT_TIMEOUT;
+        CloseHandle(handle);
+        running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::alive;
+
+    #[test]
+    fn alive_and_gone() {
+        assert!(alive(std::process::id()));
+        assert!(!alive(0));
+        assert!(!alive(u32::MAX));
+
+        #[cfg(unix)]
+        {
+            let mut child = std::process::Command::new("true").spawn().unwrap();
+            let pid = child.id();
+            child.wait().unwrap();
+            assert!(!alive(pid));
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! Placing the window beside its anchor: the `-x/-y` point, or the
+//! `--anchor` rectangle.
+
+use std::{fmt::Display, str::FromStr};
+
+use druid::{Data, Point, Rect, Size, Vec2};
+
+/// Which side of the anchor the window goes.
+#[derive(Debug, Clone, Copy, PartialEq, Data)]
+pub enum Position {
+    Under,
+    Over,
+}
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "under" => Ok(Position::Under),
+            "over" => Ok(Position::Over),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Under => "under",
+            Self::Over => "over",
+        })
+    }
+}
+
+/// A rectangle to open beside, e.g. a color token's bounds, parsed from
+/// `x,y,w,h` in virtual desktop coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Anchor(pub Rect);
+impl FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Option<Vec<f64>> = s.split(',').map(|v| v.trim().parse().ok()).collect();
+        match values.as_deref() {
+            Some(&[x, y, w, h]) if w >= 0.0 && h >= 0.0 => Ok(Anchor(Rect::new(x, y, x + w, y + h))),
+            _ => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Anchor {
+    /// An empty rectangle at `-x/-y`.
+    pub fn point(point: Point) -> Self {
+        Anchor(Rect::from_origin_size(point, Size::ZERO))
+    }
+}
+
+/// How the platform's druid backend lays out the virtual desktop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Platform {
+    /// Monitors and positions in physical pixels, layout sizes in DIPs.
+    Windows,
+    /// Monitors measured down from the top of the highest one, and window
+    /// positions flipped against the height of them all.
+    Mac,
+    /// Everything in the same coordinates.
+    Other,
+}
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(target_os = "windows") {
+            Platform::Windows
+        } else if cfg!(target_os = "macos") {
+            Platform::Mac
+        } else {
+            Platform::Other
+        }
+    }
+}
+
+/// Maps `-x/-y`, given in virtual desktop coordinates (origin at the
+/// primary monitor's top left, y down, possibly negative), to where druid
+/// needs the window.
+pub struct Desktop {
+    platform: Platform,
+    /// Each monitor's bounds and work area, in druid's coordinates.
+    monitors: Vec<(Rect, Rect)>,
+    primary: Rect,
+    /// Device pixels per layout unit.
+    scale: f64,
+}
+
+impl Desktop {
+    /// `monitors` are whether each is the primary, its bounds and its work
+    /// area, as druid reports them.
+    pub fn new(platform: Platform, monitors: &[(bool, Rect, Rect)], scale: f64) -> Self {
+        let primary = monitors.iter()
+            .find(|(primary, _, _)| *primary)
+            .map_or(Rect::ZERO, |(_, bounds, _)| *bounds);
+        Desktop {
+            platform,
+            monitors: monitors.iter().map(|(_, bounds, work)| (*bounds, *work)).collect(),
+            primary,
+            scale,
+        }
+    }
+
+    /// The anchor in druid's monitor coordinates.
+    fn anchor(&self, anchor: Anchor) -> Rect {
+        match self.platform {
+            Platform::Mac => anchor.0 + Vec2::new(0.0, self.primary.y0),
+            _ => anchor.0,
+        }
+    }
+
+    /// A layout size in druid's monitor coordinates.
+    fn size(&self, size: Size) -> Size {
+        match self.platform {
+            Platform::Windows => Size::new(size.width * self.scale, size.height * self.scale),
+            _ => size,
+        }
+    }
+
+    /// The work area of the monitor showing the middle of the anchor.
+    pub fn work_area(&self, anchor: Anchor) -> Option<Rect> {
+        self.work_area_at(self.anchor(anchor))
+    }
+
+    fn work_area_at(&self, anchor: Rect) -> Option<Rect> {
+        self.monitors.iter()
+            .find(|(bounds, _)| bounds.contains(anchor.center()))
+            .map(|(_, work)| *work)
+    }
+
+    /// The side to open on: `preferred`, unless the window would leave the
+    /// work area there and fits on the other side.
+    pub fn side(&self, anchor: Anchor, size: Size, preferred: Position) -> Position {
+        let work = match self.work_area(anchor) {
+            Some(work) => work,
+            None => return preferred,
+        };
+        let (anchor, size) = (self.anchor(anchor), self.size(size));
+        let fits_under = anchor.y1 + size.height <= work.y1;
+        let fits_over = anchor.y0 - size.height >= work.y0;
+        match preferred {
+            Position::Under if !fits_under && fits_over => Position::Over,
+            Position::Over if !fits_over && fits_under => Position::Under,
+            _ => preferred,
+        }
+    }
+
+    /// The position to give the window so it's centered on the anchor, with
+    /// the edge on `side` touching it. It's moved sideways as far as it takes
+    /// to stay in the work area.
+    pub fn origin(&self, anchor: Anchor, size: Size, side: Position) -> Point {
+        let (anchor, size) = (self.anchor(anchor), self.size(size));
+        let x = self.left(anchor, size);
+        let y = match side {
+            Position::Under => anchor.y1,
+            Position::Over => anchor.y0 - size.height,
+        };
+        match self.platform {
+            // druid flips y against the height of all monitors rather than
+            // the top of the primary one
+            Platform::Mac => {
+                let height = self.monitors.iter().fold(Rect::ZERO, |all, (bounds, _)| all.union(*bounds)).height();
+                Point::new(x, y + height - self.primary.y1)
+            }
+            _ => Point::new(x, y),
+        }
+    }
+
+    /// How far across the window the middle of the anchor is, in layout
+    /// units, once [`origin`](Self::origin) has kept the window on screen.
+    pub fn caret_x(&self, anchor: Anchor, size: Size) -> f64 {
+        let anchor = self.anchor(anchor);
+        let offset = anchor.center().x - self.left(anchor, self.size(size));
+        match self.platform {
+            Platform::Windows => offset / self.scale,
+            _ => offset,
+        }
+    }
+
+    /// The window's left edge, centered on the anchor but inside the work
+    /// area, all in druid's monitor coordinates.
+    fn left(&self, anchor: Rect, size: Size) -> f64 {
+        let x = anchor.center().x - size.width / 2.0;
+        match self.work_area_at(anchor) {
+            Some(work) => x.min(work.x1 - size.width).max(work.x0),
+            None => x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid::{Point, Rect, Size};
+
+    use super::{Anchor, Desktop, Platform, Position};
+
+    fn at(x: f64, y: f64) -> Anchor {
+        Anchor::point(Point::new(x, y))
+    }
+
+    fn single(platform: Platform) -> Desktop {
+        let bounds = Rect::new(0.0, 0.0, 1920.0, 1080.0);
+        Desktop::new(platform, &[(true, bounds, Rect::new(0.0, 0.0, 1920.0, 1040.0))], 1.0)
+    }
+
+    #[test]
+    fn flips_at_edges() {
+        let desktop = single(Platform::Other);
+        let size = Size::new(260.0, 400.0);
+        assert_eq!(desktop.side(at(500.0, 100.0), size, Position::Under), Position::Under);
+        assert_eq!(desktop.side(at(500.0, 900.0), size, Position::Under), Position::Over);
+        assert_eq!(desktop.side(at(500.0, 100.0), size, Position::Over), Position::Under);
+        // too tall for either side, so keep the preference
+        assert_eq!(desktop.side(at(500.0, 500.0), Size::new(260.0, 800.0), Position::Under), Position::Under);
+
+        assert_eq!(desktop.origin(at(500.0, 900.0), size, Position::Over), Point::new(370.0, 500.0));
+        // the same on a lone mac monitor
+        assert_eq!(single(Platform::Mac).origin(at(500.0, 900.0), size, Position::Over), Point::new(370.0, 500.0));
+    }
+
+    #[test]
+    fn beside_a_rect() {
+        let desktop = single(Platform::Other);
+        let size = Size::new(260.0, 400.0);
+        let token: Anchor = "400,100,60,20".parse().unwrap();
+        assert_eq!(desktop.origin(token, size, Position::Under), Point::new(300.0, 120.0));
+        // flipped over when the bottom edge doesn't leave room under
+        let token: Anchor = "400,630,60,20".parse().unwrap();
+        assert_eq!(desktop.side(token, size, Position::Under), Position::Over);
+        assert_eq!(desktop.origin(token, size, Position::Over), Point::new(300.0, 230.0));
+        // kept on screen at the edges
+        assert_eq!(desktop.origin(at(20.0, 100.0), size, Position::Under), Point::new(0.0, 100.0));
+        assert_eq!(desktop.origin(at(1900.0, 100.0), size, Position::Under), Point::new(1660.0, 100.0));
+        // so the caret moves across to still point at it
+        assert_eq!(desktop.caret_x(at(500.0, 100.0), size), 130.0);
+        assert_eq!(desktop.caret_x(at(20.0, 100.0), size), 20.0);
+        assert_eq!(desktop.caret_x(at(1900.0, 100.0), size), 240.0);
+
+        assert!("1,2,3".parse::<Anchor>().is_err());
+        assert!("1,2,-3,4".parse::<Anchor>().is_err());
+    }
+
+    #[test]
+    fn windows_monitor_to_the_left() {
+        // negative coordinates, and a 150% scale that layout sizes don't include
+        let monitors = [
+            (true, Rect::new(0.0, 0.0, 1920.0, 1080.0), Rect::new(0.0, 0.0, 1920.0, 1040.0)),
+            (false, Rect::new(-2560.0, -200.0, 0.0, 1240.0), Rect::new(-2560.0, -200.0, 0.0, 1200.0)),
+        ];
+        let desktop = Desktop::new(Platform::Windows, &monitors, 1.5);
+        let anchor = at(-1000.0, 1000.0);
+        assert_eq!(desktop.work_area(anchor), Some(monitors[1].2));
+        let size = Size::new(200.0, 300.0);
+        assert_eq!(desktop.side(anchor, size, Position::Under), Position::Over);
+        assert_eq!(desktop.origin(anchor, size, Position::Over), Point::new(-1150.0, 550.0));
+        // the caret is in layout units, the middle of the 200 wide window
+        assert_eq!(desktop.caret_x(anchor, size), 100.0);
+        assert_eq!(desktop.work_area(at(-3000.0, 0.0)), None);
+    }
+
+    #[test]
+    fn mac_monitor_above_and_below() {
+        // druid measures mac monitors down from the top of the highest: here
+        // a 900 high monitor sits above the primary and one 600 high below
+        let monitors = [
+            (true, Rect::new(0.0, 900.0, 1440.0, 1800.0), Rect::new(0.0, 925.0, 1440.0, 1800.0)),
+            (false, Rect::new(0.0, 0.0, 1600.0, 900.0), Rect::new(0.0, 0.0, 1600.0, 900.0)),
+            (false, Rect::new(200.0, 1800.0, 1000.0, 2400.0), Rect::new(200.0, 1800.0, 1000.0, 2400.0)),
+        ];
+        let desktop = Desktop::new(Platform::Mac, &monitors, 2.0);
+        // -y is from the primary's top, so negative is the monitor above
+        assert_eq!(desktop.work_area(at(100.0, -100.0)), Some(monitors[1].2));
+        assert_eq!(desktop.work_area(at(300.0, 1000.0)), Some(monitors[2].2));
+        // druid flips against the total height (2400) rather than the
+        // primary's bottom (1800), so positions are shifted by the difference
+        let size = Size::new(200.0, 300.0);
+        assert_eq!(desktop.origin(at(300.0, 100.0), size, Position::Under), Point::new(200.0, 1600.0));
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fmt::Display, fs, io, path::{Path, PathBuf}, str::FromStr};
+
+use color_core::Format;
+use color_core::format::{FormatOptions, UserFormat};
+use druid::{Data, Lens};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+/// How many `formats` get a button; the row is only so wide.
+pub const MAX_USER_FORMATS: usize = 4;
+
+/// Preferences edited in the settings view. Command line flags win over them.
+#[derive(Clone, Debug, Data, Lens, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+    /// Format to start in when the input doesn't pick one, as a lowercase
+    /// button label.
+    pub default_format: String,
+    pub hex_short: bool,
+    pub rgb_percent: bool,
+    /// Write alpha as a 0..1 number rather than a percentage.
+    pub alpha_number: bool,
+    /// Label the values at the ends of the hue and alpha sliders.
+    pub slider_labels: bool,
+    /// Extra format buttons, only set by editing the file.
+    #[data(ignore)]
+    pub formats: Vec<FormatSetting>,
+}
+
+/// The picker's colors, saved as `light` or `dark`.
+#[derive(Clone, Copy, Debug, Data, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+}
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Light => "light",
+            Self::Dark => "dark",
+        })
+    }
+}
+
+/// A format button defined in the settings file, e.g.
+/// `{"label": "KT", "template": "Color(0x{a*255|hex}{r*255|hex}{g*255|hex}{b*255|hex})"}`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FormatSetting {
+    pub label: String,
+    /// See `color_core::template`.
+    pub template: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::Light,
+            default_format: "hex".to_string(),
+            hex_short: false,
+            rgb_percent: false,
+            alpha_number: false,
+            slider_labels: false,
+            formats: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("colorpicker").join("settings.json"))
+    }
+
+    /// Loads the settings file, falling back to defaults for a missing or
+    /// unreadable file and for missing keys.
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                warn!("ignoring malformed settings file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(e) => {
+                debug!("no settings loaded from {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    pub fn is_dark(&self) -> bool {
+        self.theme == Theme::Dark
+    }
+
+    /// The `formats` to add buttons for, skipping (with a warning) invalid
+    /// templates, labels already taken and any past [`MAX_USER_FORMATS`].
+    pub fn user_formats(&self, options: &FormatOptions) -> Vec<UserFormat> {
+        let mut options = options.clone();
+        let mut added = Vec::new();
+        for format in &self.formats {
+            let label = format.label.trim().to_uppercase();
+            if added.len() == MAX_USER_FORMATS {
+                warn!("ignoring format {}: only {} formats can be added", label, MAX_USER_FORMATS);
+            } else if label.is_empty() || Format::named(&label, &options).is_some() {
+                warn!("ignoring format {:?}: the label is empty or taken", label);
+            } else {
+                match format.template.parse() {
+                    Ok(template) => {
+                        added.push(UserFormat { label, template });
+                        options.user_formats = std::sync::Arc::new(added.clone());
+                    }
+                    Err(e) => warn!("ignoring format {}: {}", label, e),
+                }
+            }
+        }
+        added
+    }
+}
+
+/// What `--standalone` restores on its next launch, saved when it's closed
+/// or aborted.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// The color being edited, as hex.
+    pub color: Option<String>,
+    /// Its format, as a lowercase button label.
+    pub format: Option<String>,
+    /// The window's top left.
+    pub position: Option<(f64, f64)>,
+    /// The window's size, which it opens at until it's fitted to the content.
+    pub size: Option<(f64, f64)>,
+    /// Colors committed so far, as hex, oldest first.
+    pub collected: Vec<String>,
+}
+
+impl Session {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|d| d.join("colorpicker").join("session.json"))
+    }
+
+    /// Loads the last session, or an empty one if there's none to read.
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                warn!("ignoring malformed session file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(e) => {
+                debug!("no session loaded from {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_core::format::FormatOptions;
+
+    use super::{Settings, Theme};
+
+    #[test]
+    fn missing_keys_use_defaults() {
+        let settings: Settings = serde_json::from_str(r#"{"theme": "dark"}"#).unwrap();
+        assert!(settings.is_dark());
+        assert_eq!(settings.default_format, "hex");
+        assert!(!settings.rgb_percent);
+        assert!(!settings.alpha_number);
+    }
+
+    #[test]
+    fn theme_names() {
+        for theme in [Theme::Light, Theme::Dark].iter() {
+            assert_eq!(theme.to_string().parse(), Ok(*theme));
+            assert_eq!(serde_json::to_string(theme).unwrap(), format!("\"{}\"", theme));
+        }
+        assert!("solarized".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn user_formats() {
+        let settings: Settings = serde_json::from_str(r#"{"formats": [
+            {"label": "kt", "template": "0x{r*255|hex}"},
+            {"label": "hex", "template": "{r}"},
+            {"label": "bad", "template": "{nope}"},
+            {"label": "KT", "template": "{r}"}
+        ]}"#).unwrap();
+        let formats = settings.user_formats(&FormatOptions::default());
+        assert_eq!(formats.len(), 1);
+        assert_eq!(formats[0].label, "KT");
+    }
+}
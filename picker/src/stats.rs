@@ -0,0 +1,126 @@
+//! `--stats`: a summary of the editing session, printed to stderr on exit.
+
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Map, Value};
+
+use crate::color::{Color, MAX_OKLCH_CHROMA};
+
+pub struct Stats {
+    started: Instant,
+    initial: Color,
+    last: Color,
+    adjustments: u32,
+    /// Total OKLab distance the color travelled, not just start to end.
+    distance: f32,
+}
+
+impl Stats {
+    pub fn new(initial: &Color) -> Self {
+        Stats { started: Instant::now(), initial: initial.clone(), last: initial.clone(), adjustments: 0, distance: 0.0 }
+    }
+
+    /// Counts a change to the current color; repeats of the same color are
+    /// ignored.
+    pub fn record(&mut self, color: &Color) {
+        if color.pixel16() == self.last.pixel16() {
+            return;
+        }
+        self.adjustments += 1;
+        self.distance += oklab_distance(&self.last, color);
+        self.last = color.clone();
+    }
+
+    /// How the final color differs from the initial one: OKLCH lightness
+    /// and chroma and alpha in percentage points, hue in degrees. Channels
+    /// that didn't change are left out.
+    fn changes(&self) -> Map<String, Value> {
+        let (l1, c1, h1) = self.initial.to_oklch();
+        let (l2, c2, h2) = self.last.to_oklch();
+        let mut changes = Map::new();
+        let mut add = |name: &str, delta: f32| {
+            if delta.abs() >= 0.05 {
+                changes.insert(name.to_string(), json!((delta * 10.0).round() / 10.0));
+            }
+        };
+        add("lightness", (l2 - l1) * 100.0);
+        add("chroma", (c2 - c1) / MAX_OKLCH_CHROMA * 100.0);
+        // greys have no hue to have changed
+        if c1 > 0.002 && c2 > 0.002 {
+            add("hue", ((h2 - h1 + 0.5).rem_euclid(1.0) - 0.5) * 360.0);
+        }
+        add("alpha", (self.last.alpha() - self.initial.alpha()) * 100.0);
+        changes
+    }
+
+    pub fn to_json(&self, elapsed: Duration) -> Value {
+        json!({
+            "stats": {
+                "seconds": (elapsed.as_secs_f64() * 10.0).round() / 10.0,
+                "adjustments": self.adjustments,
+                "distance": (self.distance * 1000.0).round() / 1000.0,
+                "changes": self.changes(),
+            }
+        })
+    }
+
+    /// e.g. `12.3s, 14 adjustments, moved 0.231 in OKLab; lightness +12%, hue -30°`
+    pub fn describe(&self, elapsed: Duration) -> String {
+        let changes: Vec<String> = self.changes().iter()
+            .map(|(name, delta)| {
+                let unit = if name == "hue" { "\u{b0}" } else { "%" };
+                format!("{} {:+}{}", name, delta.as_f64().unwrap_or_default(), unit)
+            })
+            .collect();
+        format!(
+            "{:.1}s, {} adjustments, moved {:.3} in OKLab; {}",
+            elapsed.as_secs_f64(),
+            self.adjustments,
+            self.distance,
+            if changes.is_empty() { "no change".to_string() } else { changes.join(", ") },
+        )
+    }
+
+    /// Prints the summary to stderr, keeping stdout for the color.
+    pub fn print(&self, as_json: bool) {
+        let elapsed = self.started.elapsed();
+        if as_json {
+            eprintln!("{}", self.to_json(elapsed));
+        } else {
+            eprintln!("{}", self.describe(elapsed));
+        }
+    }
+}
+
+fn oklab_distance(a: &Color, b: &Color) -> f32 {
+    let (l1, a1, b1) = a.to_oklab();
+    let (l2, a2, b2) = b.to_oklab();
+    ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Stats;
+    use crate::color::Color;
+
+    #[test]
+    fn summary() {
+        let start = Color::from_rgba_f32(0.2, 0.4, 0.6, 1.0);
+        let mut stats = Stats::new(&start);
+        stats.record(&start);
+        let lighter = start.lighten_oklch(0.1);
+        stats.record(&lighter);
+        stats.record(&lighter);
+        stats.record(&start.lighten_oklch(0.12));
+        assert_eq!(stats.adjustments, 2);
+        assert!((stats.distance - 0.12).abs() < 0.01);
+
+        let json = stats.to_json(Duration::from_millis(12_340));
+        assert_eq!(json["stats"]["seconds"], 12.3);
+        assert_eq!(json["stats"]["changes"]["lightness"], 12.0);
+        assert!(json["stats"]["changes"].get("alpha").is_none());
+        assert!(stats.describe(Duration::from_secs(2)).starts_with("2.0s, 2 adjustments, moved 0.12"));
+    }
+}
@@ -0,0 +1,67 @@
+//! Editor theme colors passed with `--ui-colors`, e.g.
+//!
+//! ```json
+//! {"background": "#1e1e1e", "foreground": "#d4d4d4", "accent": "#007acc"}
+//! ```
+//!
+//! The whole UI palette is shaded between the background and foreground,
+//! so the picker matches the editor in light and dark themes alike.
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::color::Color;
+
+#[derive(Debug, Clone)]
+pub struct UiColors {
+    pub background: Color,
+    pub foreground: Color,
+    /// Used for focus; falls back to a shade of the foreground.
+    pub accent: Option<Color>,
+}
+
+#[derive(Deserialize)]
+struct Raw {
+    background: String,
+    foreground: String,
+    accent: Option<String>,
+}
+
+impl FromStr for UiColors {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw: Raw = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        let color = |name: &str, value: &str| value.parse::<Color>().map_err(|e| format!("{}: {}", name, e));
+        Ok(UiColors {
+            background: color("background", &raw.background)?,
+            foreground: color("foreground", &raw.foreground)?,
+            accent: raw.accent.map(|a| color("accent", &a)).transpose()?,
+        })
+    }
+}
+
+impl UiColors {
+    /// The color `t` (0..1) of the way from the background to the foreground.
+    pub fn shade(&self, t: f32) -> Color {
+        self.background.mix(&self.foreground, t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UiColors;
+
+    #[test]
+    fn parse_and_shade() {
+        let ui: UiColors = r##"{"background": "#1e1e1e", "foreground": "#d4d4d4"}"##.parse().unwrap();
+        assert!(ui.accent.is_none());
+        assert_eq!(ui.shade(0.0).pixel(), ui.background.pixel());
+        assert_eq!(ui.shade(1.0).pixel(), ui.foreground.pixel());
+
+        assert!(r##"{"background": "#1e1e1e"}"##.parse::<UiColors>().is_err());
+        let e = r##"{"background": "#1e1e1e", "foreground": "#d4d4d4", "accent": "nope"}"##.parse::<UiColors>().unwrap_err();
+        assert!(e.starts_with("accent: "), "{}", e);
+    }
+}
@@ -0,0 +1,272 @@
+//! An experimental view of where the color sits in the RGB cube or the HSV
+//! cone, projected in software: the solid's faces are cut into patches
+//! colored by what's there, turned to the view and painted back to front.
+//! Dragging turns it.
+
+use std::{f64::consts::PI, fmt::Display, str::FromStr};
+
+use druid::kurbo::{BezPath, Circle};
+use druid::widget::prelude::*;
+use druid::{Cursor, Point};
+
+use crate::color::Color;
+use crate::widget_util::ToDruid;
+use crate::widgets::{OUTLINE, SELECTION, SHADOW};
+
+/// The solid to show.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Space {
+    Rgb,
+    Hsv,
+}
+impl FromStr for Space {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rgb" => Ok(Space::Rgb),
+            "hsv" => Ok(Space::Hsv),
+            s => Err(format!("Invalid value: {}", s)),
+        }
+    }
+}
+impl Display for Space {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            Self::Rgb => "rgb",
+            Self::Hsv => "hsv",
+        })
+    }
+}
+
+type Vec3 = [f64; 3];
+
+/// Patches along each edge of a cube face, and rings and hue segments of
+/// the cone.
+const STEPS: usize = 8;
+const HUE_SEGMENTS: usize = 24;
+
+/// Radians turned per pixel dragged.
+const TURN: f64 = 0.01;
+
+/// A flat four-cornered piece of the solid's surface.
+struct Patch {
+    corners: [Vec3; 4],
+    color: druid::Color,
+}
+
+impl Space {
+    /// Where `c` sits in the solid, which fits in a unit cube centered on
+    /// the origin with y up.
+    pub fn position(self, c: &Color) -> Vec3 {
+        match self {
+            Space::Rgb => [c.red() as f64 - 0.5, c.green() as f64 - 0.5, c.blue() as f64 - 0.5],
+            Space::Hsv => cone(c.hue() as f64, c.saturation() as f64, c.value() as f64),
+        }
+    }
+
+    fn patches(self) -> Vec<Patch> {
+        let step = 1.0 / STEPS as f64;
+        let mut patches = Vec::new();
+        match self {
+            Space::Rgb => {
+                // each face holds one channel at 0 or 1 and spans the other two
+                for axis in 0..3 {
+                    for &side in &[0.0, 1.0] {
+                        for (i, j) in (0..STEPS).flat_map(|i| (0..STEPS).map(move |j| (i, j))) {
+                            let at = |u: f64, v: f64| {
+                                let mut rgb = [0.0; 3];
+                                rgb[axis] = side;
+                                rgb[(axis + 1) % 3] = u;
+                                rgb[(axis + 2) % 3] = v;
+                                rgb
+                            };
+                            let (u, v) = (i as f64 * step, j as f64 * step);
+                            let [r, g, b] = at(u + step / 2.0, v + step / 2.0);
+                            patches.push(Patch {
+                                corners: [at(u, v), at(u + step, v), at(u + step, v + step), at(u, v + step)]
+                                    .map(|p| p.map(|x| x - 0.5)),
+                                color: Color::from_rgba_f32(r as f32, g as f32, b as f32, 1.0).to_druid(),
+                            });
+                        }
+                    }
+                }
+            }
+            Space::Hsv => {
+                let hue_step = 1.0 / HUE_SEGMENTS as f64;
+                for (i, j) in (0..HUE_SEGMENTS).flat_map(|i| (0..STEPS).map(move |j| (i, j))) {
+                    let (h, t) = (i as f64 * hue_step, j as f64 * step);
+                    let mid = (h + hue_step / 2.0, t + step / 2.0);
+                    // the side, fully saturated, with value up it
+                    patches.push(Patch {
+                        corners: [cone(h, 1.0, t), cone(h + hue_step, 1.0, t), cone(h + hue_step, 1.0, t + step), cone(h, 1.0, t + step)],
+                        color: Color::from_hsva_f32(mid.0 as f32, 1.0, mid.1 as f32, 1.0).to_druid(),
+                    });
+                    // the top, at full value, with saturation out from the middle
+                    patches.push(Patch {
+                        corners: [cone(h, t, 1.0), cone(h + hue_step, t, 1.0), cone(h + hue_step, t + step, 1.0), cone(h, t + step, 1.0)],
+                        color: Color::from_hsva_f32(mid.0 as f32, mid.1 as f32, 1.0, 1.0).to_druid(),
+                    });
+                }
+            }
+        }
+        patches
+    }
+}
+
+/// A point in the HSV cone: black at the tip, white in the middle of the
+/// top, and the radius the saturation times the value.
+fn cone(hue: f64, sat: f64, value: f64) -> Vec3 {
+    let angle = hue * 2.0 * PI;
+    let radius = sat * value * 0.5;
+    [radius * angle.cos(), value - 0.5, radius * angle.sin()]
+}
+
+/// The angles the solid is seen at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct View {
+    /// About the vertical axis.
+    yaw: f64,
+    /// Tilted towards the viewer about the horizontal, within a quarter turn.
+    pitch: f64,
+}
+
+impl View {
+    /// `p` turned to the view: x right, y up, z towards the viewer.
+    fn turn(self, [x, y, z]: Vec3) -> Vec3 {
+        let (sin, cos) = self.yaw.sin_cos();
+        let (x, z) = (x * cos + z * sin, z * cos - x * sin);
+        let (sin, cos) = self.pitch.sin_cos();
+        let (y, z) = (y * cos - z * sin, z * cos + y * sin);
+        [x, y, z]
+    }
+
+    /// `p` on a square of `side` pixels, with depth towards the viewer. The
+    /// cube's diagonal fits whichever way it's turned.
+    fn project(self, p: Vec3, side: f64) -> (Point, f64) {
+        let [x, y, z] = self.turn(p);
+        let scale = side / 3f64.sqrt();
+        (Point::new(side / 2.0 + x * scale, side / 2.0 - y * scale), z)
+    }
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View { yaw: -PI / 6.0, pitch: PI / 8.0 }
+    }
+}
+
+pub struct Viz3d {
+    space: Space,
+    patches: Vec<Patch>,
+    view: View,
+    /// Where the last drag event was, while dragging.
+    dragging: Option<Point>,
+}
+
+impl Viz3d {
+    pub fn new(space: Space) -> Self {
+        Viz3d { space, patches: space.patches(), view: View::default(), dragging: None }
+    }
+}
+
+impl Widget<Color> for Viz3d {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut Color, _env: &Env) {
+        match event {
+            Event::MouseDown(e) if e.button.is_left() => {
+                ctx.set_active(true);
+                self.dragging = Some(e.pos);
+            }
+            Event::MouseMove(e) => {
+                ctx.set_cursor(&Cursor::OpenHand);
+                if let (Some(last), true) = (self.dragging, e.buttons.has_left()) {
+                    self.view.yaw += (e.pos.x - last.x) * TURN;
+                    self.view.pitch = (self.view.pitch + (e.pos.y - last.y) * TURN).clamp(-PI / 2.0, PI / 2.0);
+                    self.dragging = Some(e.pos);
+                    ctx.request_paint();
+                }
+            }
+            Event::MouseUp(e) if e.button.is_left() => {
+                ctx.set_active(false);
+                self.dragging = None;
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &Color, _env: &Env) {}
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Color, data: &Color, _env: &Env) {
+        if !old_data.same(data) {
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &Color, _env: &Env) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, env: &Env) {
+        let size = ctx.size();
+        let side = size.width.min(size.height);
+        ctx.with_save(|ctx| {
+            ctx.transform(druid::Affine::translate(((size.width - side) / 2.0, (size.height - side) / 2.0)));
+
+            // a convex solid painted back to front covers its hidden side
+            let view = self.view;
+            let mut faces: Vec<(f64, BezPath, &druid::Color)> = self.patches.iter()
+                .map(|patch| {
+                    let corners = patch.corners.map(|p| view.project(p, side));
+                    let mut path = BezPath::new();
+                    path.move_to(corners[0].0);
+                    for (point, _) in &corners[1..] {
+                        path.line_to(*point);
+                    }
+                    path.close_path();
+                    let depth = corners.iter().map(|(_, z)| z).sum::<f64>() / 4.0;
+                    (depth, path, &patch.color)
+                })
+                .collect();
+            faces.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for (_, path, color) in &faces {
+                ctx.fill(path.elements(), *color);
+                // a hairline of the same color closes the seams between patches
+                ctx.stroke(path.elements(), *color, 0.5);
+            }
+
+            let (at, _) = view.project(self.space.position(data), side);
+            let marker = Circle::new(at, 4.0);
+            ctx.fill(marker, &data.to_druid().with_alpha(1.0));
+            ctx.stroke(Circle::new(at + (0.0, 0.5), 4.0), &env.get(SHADOW), 2.0);
+            ctx.stroke(marker, &env.get(SELECTION), 1.5);
+        });
+        ctx.stroke(size.to_rect().to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Space, View};
+    use crate::color::Color;
+
+    #[test]
+    fn positions_and_projection() {
+        let white = Color::from_rgba_f32(1.0, 1.0, 1.0, 1.0);
+        assert_eq!(Space::Rgb.position(&white), [0.5, 0.5, 0.5]);
+        // white is the middle of the cone's top, black its tip
+        let [x, y, z] = Space::Hsv.position(&white);
+        assert!(x.abs() < 1e-9 && (y - 0.5).abs() < 1e-9 && z.abs() < 1e-9);
+        assert_eq!(Space::Hsv.position(&Color::from_rgba_f32(0.0, 0.0, 0.0, 1.0))[1], -0.5);
+
+        // head on, x is right and y up on screen
+        let view = View { yaw: 0.0, pitch: 0.0 };
+        let (p, z) = view.project([0.5, 0.5, 0.0], 100.0);
+        assert!(p.x > 50.0 && p.y < 50.0 && z == 0.0);
+        // every corner of the cube stays on screen however it's turned
+        let view = View { yaw: 0.7, pitch: -1.1 };
+        for corner in Space::Rgb.patches().iter().flat_map(|p| p.corners.to_vec()) {
+            let (p, _) = view.project(corner, 100.0);
+            assert!((0.0..=100.0).contains(&p.x) && (0.0..=100.0).contains(&p.y), "{:?}", p);
+        }
+    }
+}
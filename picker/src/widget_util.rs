@@ -1,6 +1,21 @@
-use druid::{Cursor, Data, widget::Controller, widget::ControllerHost};
+use std::{cell::Cell, rc::Rc};
+
+use druid::{Cursor, Data, MouseButton, Point, Selector, WidgetPod, widget::Controller, widget::ControllerHost, widget::Flex};
 use druid::widget::prelude::*;
 
+use crate::color::Color;
+
+pub trait ToDruid {
+    /// The color as drawn on screen, through the display profile if any.
+    fn to_druid(&self) -> druid::Color;
+}
+impl ToDruid for Color {
+    fn to_druid(&self) -> druid::Color {
+        let [r, g, b, a] = color_core::icc::to_display(self.pixel());
+        druid::Color::rgba8(r, g, b, a)
+    }
+}
+
 pub struct WithCursor(&'static Cursor);
 impl<T, W: Widget<T>> Controller<T, W> for WithCursor {
     fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
@@ -11,6 +26,39 @@ impl<T, W: Widget<T>> Controller<T, W> for WithCursor {
     }
 }
 
+/// Takes keyboard focus when the command arrives.
+pub struct TakeFocus(pub Selector<()>);
+impl<T, W: Widget<T>> Controller<T, W> for TakeFocus {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Event::Command(cmd) = event {
+            if cmd.is(self.0) {
+                ctx.request_focus();
+            }
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
+/// Something in a [`reading_row`].
+pub enum RowItem<T> {
+    Child(Box<dyn Widget<T>>),
+    Spacer(f64),
+    FlexSpacer(f64),
+}
+
+/// A row of `items` in reading order: left to right, or right to left when
+/// `rtl`.
+pub fn reading_row<T: Data>(rtl: bool, mut items: Vec<RowItem<T>>) -> Flex<T> {
+    if rtl {
+        items.reverse();
+    }
+    items.into_iter().fold(Flex::row(), |row, item| match item {
+        RowItem::Child(child) => row.with_child(child),
+        RowItem::Spacer(len) => row.with_spacer(len),
+        RowItem::FlexSpacer(flex) => row.with_flex_spacer(flex),
+    })
+}
+
 pub struct OnDataChange<T> {
     action: Box<dyn Fn(&T)>,
 }
@@ -28,12 +76,128 @@ impl<T: Data, W: Widget<T>> Controller<T, W> for OnDataChange<T> {
     }
 }
 
+pub struct OnRightClick<T> {
+    action: Box<dyn Fn(&mut EventCtx, &mut T, &Env)>,
+}
+impl<T: Data> OnRightClick<T> {
+    pub fn new(action: impl Fn(&mut EventCtx, &mut T, &Env) + 'static) -> Self {
+        Self{action: Box::new(action)}
+    }
+}
+impl<T: Data, W: Widget<T>> Controller<T, W> for OnRightClick<T> {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(e) if e.button == MouseButton::Right => {
+                ctx.set_active(true);
+            }
+            Event::MouseUp(e) if e.button == MouseButton::Right => {
+                if ctx.is_active() {
+                    ctx.set_active(false);
+                    if ctx.is_hot() {
+                        (self.action)(ctx, data, env);
+                    }
+                }
+            }
+            _ => child.event(ctx, event, data, env),
+        }
+    }
+}
+
+/// Sizes the window to fit its content, whatever panels are shown, at the
+/// `width` a [`MeasureWidth`] in it last measured. The content's height is
+/// unconstrained, so it must have a natural one.
+pub struct FitWindow<T> {
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    width: Rc<Cell<f64>>,
+    fitted: Size,
+    on_fit: Option<Box<dyn FnMut(&mut LayoutCtx, Size)>>,
+}
+impl<T: Data> FitWindow<T> {
+    pub fn new(width: Rc<Cell<f64>>, child: impl Widget<T> + 'static) -> Self {
+        Self { child: WidgetPod::new(Box::new(child)), width, fitted: Size::ZERO, on_fit: None }
+    }
+
+    /// Runs `f` with the new size each time the window is resized to fit,
+    /// starting before it's first painted.
+    pub fn on_fit(mut self, f: impl FnMut(&mut LayoutCtx, Size) + 'static) -> Self {
+        self.on_fit = Some(Box::new(f));
+        self
+    }
+}
+impl<T: Data> Widget<T> for FitWindow<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.child.event(ctx, event, data, env);
+    }
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, data, env);
+    }
+    fn layout(&mut self, ctx: &mut LayoutCtx, _bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let bc = |width| BoxConstraints::new(Size::new(width, 0.0), Size::new(width, f64::INFINITY));
+        let width = self.width.get();
+        let mut size = self.child.layout(ctx, &bc(width), data, env);
+        // the width is measured in the same pass, so the first time and
+        // when it changes, the rest is laid out again to match
+        if self.width.get() != width {
+            size = self.child.layout(ctx, &bc(self.width.get()), data, env);
+        }
+        self.child.set_origin(ctx, data, env, Point::ORIGIN);
+        if size != self.fitted {
+            log::debug!("fitting window to {:?}", size);
+            self.fitted = size;
+            ctx.window().set_size(size);
+            if let Some(on_fit) = &mut self.on_fit {
+                on_fit(ctx, size);
+            }
+        }
+        size
+    }
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+    }
+}
+
+/// Lays its child out at its natural width, whatever it's given, and keeps
+/// that for a [`FitWindow`].
+pub struct MeasureWidth<W> {
+    child: W,
+    width: Rc<Cell<f64>>,
+}
+impl<T: Data, W: Widget<T>> Widget<T> for MeasureWidth<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.child.event(ctx, event, data, env);
+    }
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child.lifecycle(ctx, event, data, env);
+    }
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, old_data, data, env);
+    }
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let natural = BoxConstraints::new(Size::new(0.0, bc.min().height), Size::new(f64::INFINITY, bc.max().height));
+        let size = self.child.layout(ctx, &natural, data, env);
+        self.width.set(size.width);
+        bc.constrain(size)
+    }
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, data, env);
+    }
+}
+
 pub trait MoreWidgetExt<T: Data>: Widget<T> + Sized + 'static {
     fn on_data_change(self, action: impl Fn(&T) + 'static) -> ControllerHost<Self, OnDataChange<T>> {
         ControllerHost::new(self, OnDataChange::new(action))
     }
+    fn on_right_click(self, action: impl Fn(&mut EventCtx, &mut T, &Env) + 'static) -> ControllerHost<Self, OnRightClick<T>> {
+        ControllerHost::new(self, OnRightClick::new(action))
+    }
     fn with_cursor(self, cursor: &'static Cursor) -> ControllerHost<Self, WithCursor> {
         ControllerHost::new(self, WithCursor(cursor))
     }
+    fn measure_width(self, width: Rc<Cell<f64>>) -> MeasureWidth<Self> {
+        MeasureWidth { child: self, width }
+    }
 }
 impl<T: Data, W: Widget<T> + 'static> MoreWidgetExt<T> for W {}
\ No newline at end of file
@@ -1,287 +1,619 @@
 use std::fmt::Display;
 
-use crate::color::Color;
+use crate::color::{Color, GamutClamp, MAX_OKLCH_CHROMA, max_oklch_chroma};
 use druid::{FontDescriptor, FontFamily, Key, TextAlignment, TextLayout, kurbo::Line, widget::{BackgroundBrush, Painter, prelude::*}};
-use druid::kurbo::Circle;
+use druid::kurbo::{Circle, Shape};
 use druid::piet::{ImageFormat, InterpolationMode};
-use druid::{BoxConstraints, Cursor, Data, Env, Event, EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, Point, Rect, RenderContext, Size, UpdateCtx, Widget};
+use druid::{BoxConstraints, Cursor, Data, Env, Event, EventCtx, LayoutCtx, Lens, LifeCycleCtx, PaintCtx, Point, Rect, RenderContext, Size, UpdateCtx, Widget};
+use druid::widget::{Axis, Controller, Flex};
+use druid::{Command, Selector, Target, WidgetExt, WidgetId, KbKey, WidgetPod};
 
 use crate::shape_util::*;
+use color_core::surface::{self, draw};
+
+enum Drag {
+    Start(Point),
+    Move(Point),
+    End,
+}
+
+/// Turns mouse events into a left button drag. This relies on druid-shell's
+/// implicit capture, as druid has no way to ask for it: while a button is
+/// down, moves keep arriving (and the sliders clamp them) once the pointer
+/// leaves the window, via `SetCapture` on Windows, the implicit grab on GTK
+/// and AppKit's drag tracking on macOS. `set_active` only routes them to
+/// this widget within the window. If the release happened where we didn't
+/// see it, e.g. the window lost focus mid-drag, the next move without the
+/// button ends the drag instead of carrying on.
+fn drag(ctx: &mut EventCtx, event: &Event, cursor: &'static Cursor) -> Option<Drag> {
+    match event {
+        Event::MouseDown(e) if e.button.is_left() => {
+            ctx.set_active(true);
+            Some(Drag::Start(e.pos))
+        }
+        Event::MouseUp(e) if e.button.is_left() && ctx.is_active() => {
+            ctx.set_active(false);
+            Some(Drag::End)
+        }
+        Event::MouseMove(e) => {
+            ctx.set_cursor(cursor);
+            match (ctx.is_active(), e.buttons.has_left()) {
+                (true, true) => Some(Drag::Move(e.pos)),
+                (true, false) => {
+                    ctx.set_active(false);
+                    Some(Drag::End)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
 
 pub struct SatValuePicker {
     size: Size,
+    buf: Vec<u8>,
+    /// Top of the value axis; above 1.0 the surface extends into HDR.
+    headroom: f32,
 }
 
 impl SatValuePicker {
-    pub fn new() -> Self { Self { size: Size::new(0.0, 0.0) } }
+    pub fn new() -> Self { Self { size: Size::new(0.0, 0.0), buf: Vec::new(), headroom: 1.0 } }
+
+    pub fn with_headroom(self, headroom: f32) -> Self { Self { headroom: headroom.max(1.0), ..self } }
 
     fn set(&self, p: Point, c: &mut Color) {
         // x is [0..1] saturation
         c.set_saturation((p.x.max(0.0).min(self.size.width) / self.size.width) as f32);
-        // y is [1..0] value
-        c.set_value((1.0 - p.y.max(0.0).min(self.size.height) / self.size.height) as f32);
+        // y is [headroom..0] value
+        c.set_value((1.0 - p.y.max(0.0).min(self.size.height) / self.size.height) as f32 * self.headroom);
+    }
+
+    fn position(&self, c: &Color) -> Point {
+        let x = c.saturation() as f64 * self.size.width.floor();
+        let y = (1.0 - c.value() as f64 / self.headroom as f64) * self.size.height.floor();
+        Point::new(x, y)
     }
 }
 
 impl Widget<Color> for SatValuePicker {
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, _env: &Env) {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, env: &Env) {
         self.size = ctx.size();
         let width = self.size.width.floor() as usize;
         let height = self.size.height.floor() as usize;
 
-        let buf = draw(width, height, |x, y| {
-            let sat = x as f32 / width as f32;
-            let value = 1.0 - y as f32 / width as f32;
-            Color::from_hsva_f32(data.hue(), sat, value, 1.0).pixel()
-        });
-
-        let image = ctx
-            .make_image(width, height, &buf, ImageFormat::RgbaSeparate)
-            .unwrap();
-
-        ctx.draw_image(
-            &image,
-            self.size.to_rect(),
-            InterpolationMode::Bilinear,
-        );
-        ctx.stroke(self.size.to_rounded_rect(1.0), &druid::Color::BLACK.with_alpha(0.2), 0.5);
-
-        let x = data.saturation() as f64 * width as f64;
-        let y = (1.0 - data.value() as f64) * height as f64;
-        let size = 4.5;
-        let stroke = 2.0;
-        let inset = 1.0;
-        let circle = Circle::new(Point::new(x, y), size)
-            .shrink(stroke/2.0)
-            .clamp(
-                Rect::new(0.0, 0.0, width as f64, height as f64)
-                .shrink(Size::new(inset, inset))
-                .shrink(Size::new(stroke/2.0, stroke/2.0))
-            );
+        let headroom = self.headroom;
+        paint_bitmap(ctx, env, &mut self.buf, |buf, width, height| surface::sat_value(buf, width, height, data.hue(), headroom));
+        ctx.stroke(self.size.to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+
+        let headroom = self.headroom as f64;
+        if headroom > 1.0 {
+            // everything above this line is clipped in the SDR preview
+            let white = (1.0 - 1.0 / headroom) * height as f64;
+            ctx.stroke(Line::new((0.0, white), (width as f64, white)), &env.get(MARKER), 1.0);
+        }
+
+        let circle = surface_handle(self.size, self.position(data));
         let shadow_circle = circle.translate(0.0, 1.0);
-        ctx.stroke(shadow_circle, &druid::Color::BLACK.with_alpha(0.2), stroke);
-        ctx.stroke(circle, &druid::Color::WHITE, stroke);
+        ctx.stroke(shadow_circle, &env.get(SHADOW), HANDLE_STROKE);
+        ctx.stroke(circle, &env.get(SELECTION), HANDLE_STROKE);
     }
 
     fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &Color, _env: &Env) -> druid::Size {
         bc.max()
     }
     fn event(&mut self, ctx: &mut EventCtx, event: &druid::Event, data: &mut Color, _env: &Env) {
-        match event {
-            druid::Event::MouseDown(e) => {
-                ctx.set_active(true);
-                self.set(e.pos, data);
-            }
-            druid::Event::MouseUp(_) => {
-                if ctx.is_active() {
-                    ctx.set_active(false);
-                }
-            }
-            druid::Event::MouseMove(e) => {
-                ctx.set_cursor(&Cursor::Crosshair);
-                if ctx.is_active() {
-                    self.set(e.pos, data);
-                }
-            }
-            _ => ()
+        match drag(ctx, event, &Cursor::Crosshair) {
+            Some(Drag::Start(p)) | Some(Drag::Move(p)) => self.set(p, data),
+            Some(Drag::End) | None => (),
         }
     }
     fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &druid::LifeCycle, _data: &Color, _env: &Env) {}
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Color, data: &Color, _env: &Env) {
-        if !old_data.same(data) {
-            ctx.request_paint()
+        let old = surface_handle(self.size, self.position(old_data)).bounding_box();
+        let new = surface_handle(self.size, self.position(data)).bounding_box();
+        repaint_handle(ctx, old_data.hue() != data.hue(), old, new);
+    }
+}
+
+/// The OKLCH counterpart of [`SatValuePicker`]: chroma across, lightness
+/// down, at the current hue. Colors sRGB can't show are masked in grey.
+pub struct ChromaLightnessPicker {
+    size: Size,
+    buf: Vec<u8>,
+    clamp: GamutClamp,
+}
+
+impl ChromaLightnessPicker {
+    pub fn new(clamp: GamutClamp) -> Self { Self { size: Size::new(0.0, 0.0), buf: Vec::new(), clamp } }
+
+    /// The OKLCH hue to draw. Greys have none, so theirs comes from the HSV
+    /// hue, which the hue slider still sets and greys carry along.
+    fn hue(c: &Color) -> f32 {
+        let (_, chroma, hue) = c.to_oklch();
+        if chroma > 0.002 {
+            hue
+        } else {
+            Color::from_hsva_f32(c.hue(), 1.0, 1.0, 1.0).to_oklch().2
+        }
+    }
+
+    fn set(&self, p: Point, c: &mut Color) {
+        let chroma = (p.x.max(0.0).min(self.size.width) / self.size.width) as f32 * MAX_OKLCH_CHROMA;
+        let lightness = 1.0 - (p.y.max(0.0).min(self.size.height) / self.size.height) as f32;
+        let mut next = Color::from_oklcha_clamped(lightness, chroma, Self::hue(c), c.alpha(), self.clamp);
+        if next.to_oklch().1 <= 0.002 {
+            next.set_hue(c.hue());
+        }
+        *c = next;
+    }
+
+    fn position(&self, c: &Color) -> Point {
+        let (lightness, chroma, _) = c.to_oklch();
+        let x = (chroma / MAX_OKLCH_CHROMA) as f64 * self.size.width.floor();
+        let y = (1.0 - lightness as f64) * self.size.height.floor();
+        Point::new(x, y)
+    }
+}
+
+impl Widget<Color> for ChromaLightnessPicker {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, env: &Env) {
+        self.size = ctx.size();
+
+        paint_bitmap(ctx, env, &mut self.buf, |buf, width, height| surface::chroma_lightness(buf, width, height, Self::hue(data)));
+        ctx.stroke(self.size.to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+
+        let circle = surface_handle(self.size, self.position(data));
+        let shadow_circle = circle.translate(0.0, 1.0);
+        ctx.stroke(shadow_circle, &env.get(SHADOW), HANDLE_STROKE);
+        ctx.stroke(circle, &env.get(SELECTION), HANDLE_STROKE);
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &Color, _env: &Env) -> druid::Size {
+        bc.max()
+    }
+    fn event(&mut self, ctx: &mut EventCtx, event: &druid::Event, data: &mut Color, _env: &Env) {
+        match drag(ctx, event, &Cursor::Crosshair) {
+            Some(Drag::Start(p)) | Some(Drag::Move(p)) => self.set(p, data),
+            Some(Drag::End) | None => (),
         }
     }
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &druid::LifeCycle, _data: &Color, _env: &Env) {}
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Color, data: &Color, _env: &Env) {
+        let old = surface_handle(self.size, self.position(old_data)).bounding_box();
+        let new = surface_handle(self.size, self.position(data)).bounding_box();
+        repaint_handle(ctx, Self::hue(old_data) != Self::hue(data), old, new);
+    }
 }
 
 pub struct HuePicker {
     size: Size,
+    buf: Vec<u8>,
+    /// How many of a loaded palette's colors fall in each hue band, scaled
+    /// so the fullest band is 1.
+    histogram: Vec<f32>,
+    /// Whether dragging past either end carries on round the hue circle
+    /// rather than stopping there.
+    wrap: bool,
+    labels: EndLabels,
 }
 
+/// Hue bands in the histogram, 10 degrees each.
+const HUE_BINS: usize = 36;
+
+/// How far the arrow keys move the hue, 1 degree, or 10 with shift.
+const HUE_STEP: f32 = 1.0 / 360.0;
+
 impl HuePicker {
-    pub fn new() -> Self { Self { size: Size::new(0.0, 0.0) } }
+    pub fn new() -> Self { Self { size: Size::new(0.0, 0.0), buf: Vec::new(), histogram: Vec::new(), wrap: false, labels: EndLabels::new("0°", "360°") } }
+
+    pub fn with_wrap(self, wrap: bool) -> Self { Self { wrap, ..self } }
+
+    /// Marks where `colors` cluster along the slider. Greys and near blacks
+    /// have no hue to speak of, so they're left out.
+    pub fn with_histogram(self, colors: &[Color]) -> Self {
+        let mut histogram = vec![0.0; HUE_BINS];
+        for c in colors.iter().filter(|c| c.saturation() > 0.1 && c.value() > 0.1) {
+            histogram[((c.hue() * HUE_BINS as f32) as usize).min(HUE_BINS - 1)] += 1.0;
+        }
+        let max = histogram.iter().cloned().fold(0.0, f32::max);
+        if max == 0.0 {
+            return self;
+        }
+        Self { histogram: histogram.into_iter().map(|n| n / max).collect(), ..self }
+    }
 
     fn set(&self, p: Point, c: &mut Color) {
-        c.set_hue((p.y.max(0.0).min(self.size.height) / self.size.height) as f32);
+        if self.wrap {
+            c.set_hue(((p.y / self.size.height) as f32).rem_euclid(1.0));
+        } else {
+            c.set_hue((p.y.max(0.0).min(self.size.height) / self.size.height) as f32);
+        }
+    }
+
+    fn position(&self, c: &Color) -> f64 {
+        c.hue() as f64 * self.size.height.floor()
     }
 }
 
 impl Widget<Color> for HuePicker {
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, _env: &Env) {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, env: &Env) {
         self.size = ctx.size();
-        let width = self.size.width.floor() as usize;
-        let height = self.size.height.floor() as usize;
 
-        let buf = draw(width, height, |_x, y| {
+        paint_bitmap(ctx, env, &mut self.buf, |buf, width, height| draw(buf, width, height, |_x, y| {
             let hue = y as f32 / height as f32;
             Color::from_hsva_f32(hue, data.saturation(), data.value(), 1.0).pixel()
-        });
-
-        let image = ctx
-            .make_image(width, height, &buf, ImageFormat::RgbaSeparate)
-            .unwrap();
-
-        ctx.draw_image(
-            &image,
-            Rect::from_origin_size(Point::ORIGIN, self.size),
-            InterpolationMode::Bilinear,
-        );
-        ctx.stroke(Rect::from_origin_size(Point::ORIGIN, self.size).to_rounded_rect(1.0), &druid::Color::BLACK.with_alpha(0.2), 0.5);
-
-        let y = data.hue() as f64 * height as f64;
-        let size = 5.0;
-        let inset = 1.0;
-        let stroke = 2.0;
-
-        let rect = Rect::new(0.0, y, width as f64, y + size)
-            .translate(0.0, -size/2.0)
-            .shrink(Size::new(inset, 0.0))
-            .shrink(Size::new(stroke/2.0, stroke/2.0))
-            .clamp(
-                Rect::new(0.0, 0.0, width as f64, height as f64)
-                .shrink(Size::new(stroke/2.0, stroke/2.0))
-            );
-            let rect_shadow = rect.translate(0.0, 0.5);
-        ctx.stroke(rect_shadow.to_rounded_rect(0.5), &druid::Color::BLACK.with_alpha(0.2), stroke);
-        ctx.stroke(rect.to_rounded_rect(0.5), &druid::Color::WHITE, stroke);
+        }));
+        ctx.stroke(Rect::from_origin_size(Point::ORIGIN, self.size).to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+
+        let band = self.size.height / HUE_BINS as f64;
+        for (i, &n) in self.histogram.iter().enumerate().filter(|(_, &n)| n > 0.0) {
+            let bar = Rect::new(0.0, i as f64 * band, n as f64 * self.size.width / 2.0, (i + 1) as f64 * band);
+            ctx.fill(bar, &env.get(HISTOGRAM));
+        }
+        self.labels.paint(ctx, env);
+
+        let rect = slider_handle(self.size, self.position(data));
+        let rect_shadow = rect.translate(0.0, 0.5);
+        ctx.stroke(rect_shadow.to_rounded_rect(0.5), &env.get(SHADOW), HANDLE_STROKE);
+        ctx.stroke(rect.to_rounded_rect(0.5), &env.get(SELECTION), HANDLE_STROKE);
+
+        if ctx.is_focused() {
+            ctx.stroke(self.size.to_rect().inset(-1.5).to_rounded_rect(2.0), &env.get(TOGGLE_FOCUS), 1.5);
+        }
     }
 
     fn layout( &mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &Color, _env: &Env ) -> druid::Size {
         bc.max()
     }
     fn event(&mut self, ctx: &mut EventCtx, event: &druid::Event, data: &mut Color, _env: &Env) {
-        match event {
-            druid::Event::MouseDown(e) => {
-                ctx.set_active(true);
-                self.set(e.pos, data);
-            }
-            druid::Event::MouseUp(_) => {
-                ctx.set_active(false);
-            }
-            druid::Event::MouseMove(e) => {
-                ctx.set_cursor(&Cursor::OpenHand);
-                if ctx.is_active() {
-                    self.set(e.pos, data);
-                }
+        // the arrow keys always wrap, hue being a circle
+        if let Event::KeyDown(e) = event {
+            let step = if e.mods.shift() { HUE_STEP * 10.0 } else { HUE_STEP };
+            let step = match e.key {
+                KbKey::ArrowUp if ctx.is_focused() => -step,
+                KbKey::ArrowDown if ctx.is_focused() => step,
+                _ => return,
+            };
+            data.set_hue((data.hue() + step).rem_euclid(1.0));
+            ctx.set_handled();
+            return;
+        }
+        match drag(ctx, event, &Cursor::OpenHand) {
+            Some(Drag::Start(p)) => {
+                ctx.request_focus();
+                self.set(p, data);
             }
-            _ => ()
+            Some(Drag::Move(p)) => self.set(p, data),
+            Some(Drag::End) | None => (),
         }
     }
-    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &druid::LifeCycle, _data: &Color, _env: &Env) {}
-    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Color, data: &Color, _env: &Env) {
-        if !old_data.same(data) {
-            ctx.request_paint()
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &druid::LifeCycle, _data: &Color, _env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(_) => ctx.request_paint(),
+            _ => (),
         }
     }
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Color, data: &Color, _env: &Env) {
+        let old = slider_handle(self.size, self.position(old_data));
+        let new = slider_handle(self.size, self.position(data));
+        let strip = (old_data.saturation(), old_data.value()) != (data.saturation(), data.value());
+        repaint_handle(ctx, strip || ctx.env_key_changed(&SLIDER_LABELS), old, new);
+    }
 }
 
 pub struct AlphaPicker {
     size: Size,
+    buf: Vec<u8>,
+    labels: EndLabels,
 }
 
 impl AlphaPicker {
-    pub fn new() -> Self { Self { size: Size::new(0.0, 0.0) } }
+    pub fn new() -> Self { Self { size: Size::new(0.0, 0.0), buf: Vec::new(), labels: EndLabels::new("100%", "0%") } }
 
     fn set(&self, p: Point, c: &mut Color) {
         c.set_alpha(1.0 - (p.y.max(0.0).min(self.size.height) / self.size.height) as f32);
     }
+
+    fn position(&self, c: &Color) -> f64 {
+        (1.0 - c.alpha()) as f64 * self.size.height.floor()
+    }
 }
 
 impl Widget<Color> for AlphaPicker {
-    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, _env: &Env) {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, env: &Env) {
         self.size = ctx.size();
-        let width = self.size.width.floor() as usize;
-        let height = self.size.height.floor() as usize;
 
-        let buf = draw(width, height, |_x, y| {
+        paint_bitmap(ctx, env, &mut self.buf, |buf, width, height| draw(buf, width, height, |_x, y| {
             let alpha = 1.0 - y as f32 / height as f32;
             Color::from_hsva_f32(data.hue(), data.saturation(), data.value(), alpha).pixel()
-        });
-
-        let image = ctx
-            .make_image(width, height, &buf, ImageFormat::RgbaSeparate)
-            .unwrap();
-
-        ctx.draw_image(
-            &image,
-            Rect::from_origin_size(Point::ORIGIN, self.size),
-            InterpolationMode::Bilinear,
-        );
-        ctx.stroke(Rect::from_origin_size(Point::ORIGIN, self.size).to_rounded_rect(1.0), &druid::Color::BLACK.with_alpha(0.2), 0.5);
-
-        let y = (1.0 - data.alpha()) as f64 * height as f64;
-        let size = 5.0;
-        let inset = 1.0;
-        let stroke = 2.0;
-
-        let rect = Rect::new(0.0, y, width as f64, y + size)
-            .translate(0.0, -size/2.0)
-            .shrink(Size::new(inset, 0.0))
-            .shrink(Size::new(stroke/2.0, stroke/2.0))
-            .clamp(
-                Rect::new(0.0, 0.0, width as f64, height as f64)
-                .shrink(Size::new(stroke/2.0, stroke/2.0))
-            );
+        }));
+        ctx.stroke(Rect::from_origin_size(Point::ORIGIN, self.size).to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+        self.labels.paint(ctx, env);
+
+        let rect = slider_handle(self.size, self.position(data));
         let rect_shadow = rect.translate(0.0, 0.5);
-        ctx.stroke(rect_shadow.to_rounded_rect(0.5), &druid::Color::BLACK.with_alpha(0.2), stroke);
-        ctx.stroke(rect.to_rounded_rect(0.5), &druid::Color::WHITE, stroke);
+        ctx.stroke(rect_shadow.to_rounded_rect(0.5), &env.get(SHADOW), HANDLE_STROKE);
+        ctx.stroke(rect.to_rounded_rect(0.5), &env.get(SELECTION), HANDLE_STROKE);
     }
 
     fn layout( &mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &Color, _env: &Env ) -> druid::Size {
         bc.max()
     }
     fn event(&mut self, ctx: &mut EventCtx, event: &druid::Event, data: &mut Color, _env: &Env) {
-        match event {
-            druid::Event::MouseDown(e) => {
-                ctx.set_active(true);
-                self.set(e.pos, data);
-            }
-            druid::Event::MouseUp(_) => {
-                ctx.set_active(false);
-            }
-            druid::Event::MouseMove(e) => {
-                ctx.set_cursor(&Cursor::OpenHand);
-                if ctx.is_active() {
-                    self.set(e.pos, data);
-                }
-            }
-            _ => ()
+        match drag(ctx, event, &Cursor::OpenHand) {
+            Some(Drag::Start(p)) | Some(Drag::Move(p)) => self.set(p, data),
+            Some(Drag::End) | None => (),
         }
     }
     fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &druid::LifeCycle, _data: &Color, _env: &Env) {}
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Color, data: &Color, _env: &Env) {
-        if !old_data.same(data) {
-            ctx.request_paint()
+        let old = slider_handle(self.size, self.position(old_data));
+        let new = slider_handle(self.size, self.position(data));
+        let strip = (old_data.hue(), old_data.saturation(), old_data.value()) != (data.hue(), data.saturation(), data.value());
+        repaint_handle(ctx, strip || ctx.env_key_changed(&SLIDER_LABELS), old, new);
+    }
+}
+
+
+pub struct LightnessPicker {
+    size: Size,
+    buf: Vec<u8>,
+    clamp: GamutClamp,
+    /// OKLCH chroma and hue when the drag started, held while it lasts so
+    /// passing through black or white doesn't lose them.
+    anchor: Option<(f32, f32)>,
+}
+
+impl LightnessPicker {
+    pub fn new(clamp: GamutClamp) -> Self { Self { size: Size::new(0.0, 0.0), buf: Vec::new(), clamp, anchor: None } }
+
+    fn set(&self, p: Point, c: &mut Color) {
+        let (_, chroma, hue) = c.to_oklch();
+        let (chroma, hue) = self.anchor.unwrap_or((chroma, hue));
+        let l = 1.0 - (p.y.max(0.0).min(self.size.height) / self.size.height) as f32;
+        *c = Color::from_oklcha_clamped(l, chroma, hue, c.alpha(), self.clamp);
+    }
+
+    /// The chroma and hue the strip is drawn at.
+    fn strip(&self, c: &Color) -> (f32, f32) {
+        let (_, chroma, hue) = c.to_oklch();
+        self.anchor.unwrap_or((chroma, hue))
+    }
+
+    fn position(&self, c: &Color) -> f64 {
+        (1.0 - c.to_oklch().0) as f64 * self.size.height.floor()
+    }
+}
+
+impl Widget<Color> for LightnessPicker {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, env: &Env) {
+        self.size = ctx.size();
+
+        let (chroma, hue) = self.strip(data);
+        let clamp = self.clamp;
+        paint_bitmap(ctx, env, &mut self.buf, |buf, width, height| draw(buf, width, height, |_x, y| {
+            let l = 1.0 - y as f32 / height as f32;
+            Color::from_oklcha_clamped(l, chroma, hue, 1.0, clamp).pixel()
+        }));
+        ctx.stroke(Rect::from_origin_size(Point::ORIGIN, self.size).to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+
+        let rect = slider_handle(self.size, self.position(data));
+        let rect_shadow = rect.translate(0.0, 0.5);
+        ctx.stroke(rect_shadow.to_rounded_rect(0.5), &env.get(SHADOW), HANDLE_STROKE);
+        ctx.stroke(rect.to_rounded_rect(0.5), &env.get(SELECTION), HANDLE_STROKE);
+    }
+
+    fn layout( &mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &Color, _env: &Env ) -> druid::Size {
+        bc.max()
+    }
+    fn event(&mut self, ctx: &mut EventCtx, event: &druid::Event, data: &mut Color, _env: &Env) {
+        match drag(ctx, event, &Cursor::OpenHand) {
+            Some(Drag::Start(p)) => {
+                let (_, chroma, hue) = data.to_oklch();
+                self.anchor = Some((chroma, hue));
+                self.set(p, data);
+            }
+            Some(Drag::Move(p)) => self.set(p, data),
+            Some(Drag::End) => self.anchor = None,
+            None => (),
         }
     }
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &druid::LifeCycle, _data: &Color, _env: &Env) {}
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Color, data: &Color, _env: &Env) {
+        let old = slider_handle(self.size, self.position(old_data));
+        let new = slider_handle(self.size, self.position(data));
+        repaint_handle(ctx, self.strip(old_data) != self.strip(data), old, new);
+    }
+}
+
+
+pub struct ChromaPicker {
+    size: Size,
+    buf: Vec<u8>,
+    clamp: GamutClamp,
+    /// OKLCH lightness and hue when the drag started; hue is lost at zero
+    /// chroma, so it's held for the whole drag.
+    anchor: Option<(f32, f32)>,
 }
 
+impl ChromaPicker {
+    pub fn new(clamp: GamutClamp) -> Self { Self { size: Size::new(0.0, 0.0), buf: Vec::new(), clamp, anchor: None } }
 
-fn draw(width: usize, height: usize, get_px: impl Fn(usize, usize) -> [u8; 4]) -> Vec<u8> {
-    let mut buf = vec![0; width * height * 4];
-    for y in 0..height {
-        for x in 0..width {
-            let ix = (y * width + x) * 4;
-            let [r, g, b, a] = get_px(x, y);
-            buf[ix] = r;
-            buf[ix + 1] = g;
-            buf[ix + 2] = b;
-            buf[ix + 3] = a;
+    fn set(&self, p: Point, c: &mut Color) {
+        let (lightness, _, hue) = c.to_oklch();
+        let (lightness, hue) = self.anchor.unwrap_or((lightness, hue));
+        let chroma = (1.0 - (p.y.max(0.0).min(self.size.height) / self.size.height) as f32) * MAX_OKLCH_CHROMA;
+        *c = Color::from_oklcha_clamped(lightness, chroma, hue, c.alpha(), self.clamp);
+    }
+
+    /// The lightness and hue the strip is drawn at.
+    fn strip(&self, c: &Color) -> (f32, f32) {
+        let (lightness, _, hue) = c.to_oklch();
+        self.anchor.unwrap_or((lightness, hue))
+    }
+
+    fn position(&self, c: &Color) -> f64 {
+        (1.0 - c.to_oklch().1 / MAX_OKLCH_CHROMA) as f64 * self.size.height.floor()
+    }
+}
+
+impl Widget<Color> for ChromaPicker {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &Color, env: &Env) {
+        self.size = ctx.size();
+        let width = self.size.width.floor() as usize;
+        let height = self.size.height.floor() as usize;
+
+        let (lightness, hue) = self.strip(data);
+        let max = max_oklch_chroma(lightness, hue);
+        paint_bitmap(ctx, env, &mut self.buf, |buf, width, height| surface::chroma_strip(buf, width, height, lightness, hue));
+        ctx.stroke(Rect::from_origin_size(Point::ORIGIN, self.size).to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+
+        let boundary = (1.0 - max / MAX_OKLCH_CHROMA) as f64 * height as f64;
+        ctx.stroke(Line::new((0.0, boundary), (width as f64, boundary)), &env.get(MARKER), 1.0);
+
+        let rect = slider_handle(self.size, self.position(data));
+        let rect_shadow = rect.translate(0.0, 0.5);
+        ctx.stroke(rect_shadow.to_rounded_rect(0.5), &env.get(SHADOW), HANDLE_STROKE);
+        ctx.stroke(rect.to_rounded_rect(0.5), &env.get(SELECTION), HANDLE_STROKE);
+    }
+
+    fn layout( &mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &Color, _env: &Env ) -> druid::Size {
+        bc.max()
+    }
+    fn event(&mut self, ctx: &mut EventCtx, event: &druid::Event, data: &mut Color, _env: &Env) {
+        match drag(ctx, event, &Cursor::OpenHand) {
+            Some(Drag::Start(p)) => {
+                let (lightness, _, hue) = data.to_oklch();
+                self.anchor = Some((lightness, hue));
+                self.set(p, data);
+            }
+            Some(Drag::Move(p)) => self.set(p, data),
+            Some(Drag::End) => self.anchor = None,
+            None => (),
         }
     }
-    buf
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &druid::LifeCycle, _data: &Color, _env: &Env) {}
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &Color, data: &Color, _env: &Env) {
+        let old = slider_handle(self.size, self.position(old_data));
+        let new = slider_handle(self.size, self.position(data));
+        repaint_handle(ctx, self.strip(old_data) != self.strip(data), old, new);
+    }
 }
 
 
+
+/// Width of the stroke around picker handles.
+const HANDLE_STROKE: f64 = 2.0;
+
+/// How far a handle's stroke and shadow reach past its shape.
+const HANDLE_MARGIN: f64 = 2.5;
+
+/// The circle marking `p` on a 2D surface, kept inside it.
+fn surface_handle(size: Size, p: Point) -> Circle {
+    let inset = 1.0;
+    Circle::new(p, 4.5)
+        .shrink(HANDLE_STROKE/2.0)
+        .clamp(
+            Rect::new(0.0, 0.0, size.width.floor(), size.height.floor())
+            .shrink(Size::new(inset, inset))
+            .shrink(Size::new(HANDLE_STROKE/2.0, HANDLE_STROKE/2.0))
+        )
+}
+
+/// The bar marking `y` across a vertical slider, kept inside it.
+fn slider_handle(size: Size, y: f64) -> Rect {
+    let (width, height) = (size.width.floor(), size.height.floor());
+    let bar = 5.0;
+    let inset = 1.0;
+    Rect::new(0.0, y, width, y + bar)
+        .translate(0.0, -bar/2.0)
+        .shrink(Size::new(inset, 0.0))
+        .shrink(Size::new(HANDLE_STROKE/2.0, HANDLE_STROKE/2.0))
+        .clamp(
+            Rect::new(0.0, 0.0, width, height)
+            .shrink(Size::new(HANDLE_STROKE/2.0, HANDLE_STROKE/2.0))
+        )
+}
+
+/// Repaints the whole widget when what's drawn under the handle changed;
+/// otherwise only where the handle was and now is, so dragging it doesn't
+/// redraw the rest.
+fn repaint_handle(ctx: &mut UpdateCtx, surface_changed: bool, old: Rect, new: Rect) {
+    if surface_changed {
+        ctx.request_paint();
+    } else if old != new {
+        ctx.request_paint_rect(old.union(new).inflate(HANDLE_MARGIN, HANDLE_MARGIN));
+    }
+}
+
+/// Draws a generated bitmap over the whole widget. `fill` is given `buf` to
+/// refill and the size in device pixels, so the bitmap maps one to one onto
+/// the screen; [`SMOOTH_SURFACES`] picks how it's sampled when that's not
+/// exact.
+fn paint_bitmap(ctx: &mut PaintCtx, env: &Env, buf: &mut Vec<u8>, fill: impl FnOnce(&mut Vec<u8>, usize, usize)) {
+    let scale = ctx.window().get_scale().map_or(1.0, |s| s.x());
+    let size = ctx.size();
+    let (width, height) = ((size.width * scale).round() as usize, (size.height * scale).round() as usize);
+    if width == 0 || height == 0 {
+        return;
+    }
+    fill(buf, width, height);
+    let image = ctx
+        .make_image(width, height, buf, ImageFormat::RgbaSeparate)
+        .unwrap();
+    let mode = if env.get(SMOOTH_SURFACES) { InterpolationMode::Bilinear } else { InterpolationMode::NearestNeighbor };
+    ctx.draw_image(&image, size.to_rect(), mode);
+}
+
+/// Tiny labels for the values at a slider's ends, when [`SLIDER_LABELS`] is
+/// on, each on a pill so it reads over any color. The layouts are kept, and
+/// only rebuilt when the theme changes.
+struct EndLabels {
+    top: TextLayout<String>,
+    bottom: TextLayout<String>,
+}
+
+impl EndLabels {
+    fn new(top: &str, bottom: &str) -> Self {
+        let layout = |text: &str| {
+            let mut layout = TextLayout::from_text(text.to_string());
+            layout.set_font(FontDescriptor::new(FontFamily::SYSTEM_UI));
+            layout
+        };
+        Self { top: layout(top), bottom: layout(bottom) }
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        if !env.get(SLIDER_LABELS) {
+            return;
+        }
+        let size = ctx.size();
+        let pill_bg = env.get(druid::theme::WINDOW_BACKGROUND_COLOR).with_alpha(0.7);
+        for (layout, at_top) in [(&mut self.top, true), (&mut self.bottom, false)] {
+            // both are no-ops unless the values differ from last time
+            layout.set_text_size(env.get(TOGGLE_TEXT_SIZE) * 0.8);
+            layout.set_text_color(env.get(LABEL_SECONDARY));
+            layout.rebuild_if_needed(ctx.text(), env);
+            let pill = layout.size() + Size::new(2.0, 0.0);
+            let y = if at_top { 2.0 } else { size.height - pill.height - 2.0 };
+            let rect = Rect::from_origin_size(((size.width - pill.width) / 2.0, y), pill);
+            ctx.fill(rect.to_rounded_rect(2.0), &pill_bg);
+            layout.draw(ctx, rect.origin() + druid::Vec2::new(1.0, 0.0));
+        }
+    }
+}
+
 pub fn checkered_bgbrush<T>(checker_side: f64) -> BackgroundBrush<T> {
-    BackgroundBrush::Painter(Painter::new(move |ctx, _data, _env| {
+    BackgroundBrush::Painter(Painter::new(move |ctx, _data, env| {
         let size = ctx.size();
         let width = size.width as usize;
         let height = size.height as usize;
-        ctx.fill(size.to_rect(), &druid::Color::WHITE);
+        ctx.fill(size.to_rect(), &env.get(CHECKER_LIGHT));
 
         let checker_size = Size::new(checker_side as f64, checker_side as f64);
-        let grey = druid::Color::grey(0.9);
+        let grey = env.get(CHECKER_DARK);
         for x in (0..width).step_by(checker_side as usize*2) {
             for y in (0..height).step_by(checker_side as usize*2) {
                 ctx.fill(Rect::from_origin_size(Point::new(x as f64 + checker_side, y as f64), checker_size), &grey);
@@ -292,11 +624,209 @@ pub fn checkered_bgbrush<T>(checker_side: f64) -> BackgroundBrush<T> {
 }
 
 
+/// A strip blending from the initial color on the left to the current one on
+/// the right. Dragging along it sets the current color to the blend under the
+/// pointer; the ends stay as they were when the drag started.
+pub struct BlendScrubber {
+    size: Size,
+    buf: Vec<u8>,
+    /// The ends being blended between, and where, while dragging.
+    drag: Option<(Color, Color, f32)>,
+}
+
+impl BlendScrubber {
+    pub fn new() -> Self { Self { size: Size::new(0.0, 0.0), buf: Vec::new(), drag: None } }
+
+    fn set(&mut self, p: Point, data: &mut (Color, Color)) {
+        if let Some((from, to, t)) = &mut self.drag {
+            *t = (p.x.max(0.0).min(self.size.width) / self.size.width) as f32;
+            data.1 = from.mix(to, *t);
+        }
+    }
+}
+
+impl Widget<(Color, Color)> for BlendScrubber {
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &(Color, Color), env: &Env) {
+        self.size = ctx.size();
+        let (from, to) = match &self.drag {
+            Some((from, to, _)) => (from, to),
+            None => (&data.0, &data.1),
+        };
+
+        paint_bitmap(ctx, env, &mut self.buf, |buf, width, height| draw(buf, width, height, |x, _y| from.mix(to, x as f32 / width as f32).pixel()));
+        ctx.stroke(Rect::from_origin_size(Point::ORIGIN, self.size).to_rounded_rect(1.0), &env.get(OUTLINE), 0.5);
+
+        if let Some((_, _, t)) = self.drag {
+            let x = t as f64 * self.size.width;
+            let line = Line::new((x, 0.0), (x, self.size.height));
+            ctx.stroke(line + druid::Vec2::new(0.5, 0.0), &env.get(SHADOW), 2.0);
+            ctx.stroke(line, &env.get(SELECTION), 2.0);
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(Color, Color), _env: &Env) -> druid::Size {
+        bc.max()
+    }
+    fn event(&mut self, ctx: &mut EventCtx, event: &druid::Event, data: &mut (Color, Color), _env: &Env) {
+        match drag(ctx, event, &Cursor::OpenHand) {
+            Some(Drag::Start(p)) => {
+                self.drag = Some((data.0.clone(), data.1.clone(), 1.0));
+                self.set(p, data);
+            }
+            Some(Drag::Move(p)) => self.set(p, data),
+            Some(Drag::End) => {
+                self.drag = None;
+                ctx.request_paint();
+            }
+            None => (),
+        }
+    }
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &druid::LifeCycle, _data: &(Color, Color), _env: &Env) {}
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &(Color, Color), data: &(Color, Color), _env: &Env) {
+        if !old_data.same(data) {
+            ctx.request_paint()
+        }
+    }
+}
+
+/// A horizontal slider applying a gamma to the color, from 1/4 on the left
+/// through 1 in the middle to 4 on the right, with the formula as its label.
+/// Each drag starts from the color as it was, so the gamma shown is what that
+/// drag applies; once released the change is part of the color and the
+/// slider is back at 1.
+pub struct GammaSlider {
+    layout: TextLayout<String>,
+    /// The color before the drag, and the gamma being applied to it.
+    drag: Option<(Color, f32)>,
+}
+
+impl GammaSlider {
+    pub fn new() -> Self { Self { layout: TextLayout::new(), drag: None } }
+
+    fn gamma_at(x: f64, width: f64) -> f32 {
+        4f32.powf((x.max(0.0).min(width) / width) as f32 * 2.0 - 1.0)
+    }
+
+    fn set(&mut self, p: Point, width: f64, data: &mut Color) {
+        if let Some((base, gamma)) = &mut self.drag {
+            *gamma = Self::gamma_at(p.x, width);
+            *data = base.with_gamma([*gamma; 3]);
+        }
+    }
+}
+
+impl Widget<Color> for GammaSlider {
+    fn paint(&mut self, ctx: &mut PaintCtx, _data: &Color, env: &Env) {
+        let size = ctx.size();
+        let rect = Rect::from_origin_size(Point::ORIGIN, size);
+        ctx.fill(rect.to_rounded_rect(2.0), &env.get(TOGGLE_INACTIVE_BG));
+        ctx.stroke(rect.to_rounded_rect(2.0), &env.get(TOGGLE_BORDER), 1.0);
+        let center = Line::new((size.width / 2.0, 0.0), (size.width / 2.0, size.height));
+        ctx.stroke(center, &env.get(TOGGLE_BORDER), 1.0);
+
+        let gamma = self.drag.as_ref().map_or(1.0, |(_, g)| *g);
+        let x = ((gamma.log(4.0) + 1.0) / 2.0) as f64 * size.width;
+        ctx.stroke(Line::new((x, 2.0), (x, size.height - 2.0)), &env.get(TOGGLE_ACTIVE_FG), 2.0);
+
+        self.layout.set_text(format!("\u{3b3} {:.2}   c' = c^(1/\u{3b3})", gamma));
+        self.layout.set_font(FontDescriptor::new(FontFamily::SYSTEM_UI));
+        self.layout.set_text_size(env.get(TOGGLE_TEXT_SIZE));
+        self.layout.set_text_color(env.get(TOGGLE_ACTIVE_FG));
+        self.layout.rebuild_if_needed(ctx.text(), env);
+        let offset = (size.to_vec2() - self.layout.size().to_vec2()) / 2.0;
+        self.layout.draw(ctx, offset.to_point());
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &Color, _env: &Env) -> druid::Size {
+        bc.max()
+    }
+    fn event(&mut self, ctx: &mut EventCtx, event: &druid::Event, data: &mut Color, _env: &Env) {
+        let width = ctx.size().width;
+        match drag(ctx, event, &Cursor::ResizeLeftRight) {
+            Some(Drag::Start(p)) => {
+                self.drag = Some((data.clone(), 1.0));
+                self.set(p, width, data);
+            }
+            Some(Drag::Move(p)) => {
+                self.set(p, width, data);
+                ctx.request_paint();
+            }
+            Some(Drag::End) => {
+                self.drag = None;
+                ctx.request_paint();
+            }
+            None => (),
+        }
+    }
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &druid::LifeCycle, _data: &Color, _env: &Env) {}
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &Color, _data: &Color, _env: &Env) {}
+}
+
+/// Dragging on a swatch: up and down adjust the value, left and right the
+/// alpha, each over the swatch's width. The direction the drag starts in
+/// picks the axis, and a drag doesn't also count as a click.
+pub struct QuickAdjust<L> {
+    lens: L,
+    /// Where the drag started, with the value and alpha there.
+    start: Option<(Point, f32, f32)>,
+    axis: Option<Axis>,
+}
+
+impl<L> QuickAdjust<L> {
+    pub fn new(lens: L) -> Self {
+        Self { lens, start: None, axis: None }
+    }
+}
+
+/// How far the pointer moves before a press becomes a drag.
+const DRAG_THRESHOLD: f64 = 4.0;
+
+impl<T: Data, L: Lens<T, Color>, W: Widget<T>> Controller<T, W> for QuickAdjust<L> {
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(e) if e.button.is_left() => {
+                let (value, alpha) = self.lens.with(data, |c| (c.value(), c.alpha()));
+                self.start = Some((e.pos, value, alpha));
+                self.axis = None;
+            }
+            Event::MouseMove(e) if e.buttons.has_left() => {
+                if let Some((start, value, alpha)) = self.start {
+                    let d = e.pos - start;
+                    if self.axis.is_none() && d.hypot() > DRAG_THRESHOLD {
+                        self.axis = Some(if d.y.abs() > d.x.abs() { Axis::Vertical } else { Axis::Horizontal });
+                    }
+                    if let Some(axis) = self.axis {
+                        let width = ctx.size().width;
+                        self.lens.with_mut(data, |c| match axis {
+                            Axis::Vertical => c.set_value((value - (d.y / width) as f32).clamp(0.0, value.max(1.0))),
+                            Axis::Horizontal => c.set_alpha((alpha + (d.x / width) as f32).clamp(0.0, 1.0)),
+                        });
+                        return;
+                    }
+                }
+            }
+            Event::MouseUp(e) if e.button.is_left() => {
+                self.start = None;
+                if self.axis.take().is_some() {
+                    ctx.set_active(false);
+                    return;
+                }
+            }
+            _ => (),
+        }
+        child.event(ctx, event, data, env);
+    }
+}
+
 pub struct ToggleButton<T: Display> {
     variant: T,
     layout: TextLayout<String>,
     is_first: bool,
     is_last: bool,
+    hover: Option<Selector<Option<T>>>,
+    icon: Option<&'static str>,
+    disabled: bool,
+    tooltip: Option<String>,
 }
 
 impl<T: Data+Display+PartialEq> ToggleButton<T> {
@@ -306,9 +836,49 @@ impl<T: Data+Display+PartialEq> ToggleButton<T> {
             layout: TextLayout::new(),
             is_first,
             is_last,
+            hover: None,
+            icon: None,
+            disabled: false,
+            tooltip: None,
         }
     }
 
+    /// Greys the button out and ignores clicks on it.
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+
+    /// Text shown over the button while the pointer is on it, even when
+    /// disabled, e.g. to say why.
+    pub fn with_tooltip(mut self, tooltip: impl Into<String>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+
+    fn text_color(&self, data: &T, env: &Env) -> druid::Color {
+        if self.disabled {
+            env.get(TOGGLE_INACTIVE_FG).with_alpha(0.4)
+        } else if self.is_active(data) {
+            env.get(TOGGLE_ACTIVE_FG)
+        } else {
+            env.get(TOGGLE_INACTIVE_FG)
+        }
+    }
+
+    /// Shows `icon`, e.g. a symbol character, before the label.
+    pub fn with_icon(mut self, icon: &'static str) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Sends the variant with `selector` while the pointer is over the
+    /// button, and `None` when it leaves.
+    pub fn with_hover(mut self, selector: Selector<Option<T>>) -> Self {
+        self.hover = Some(selector);
+        self
+    }
+
     pub fn is_active(&self, data: &T) -> bool {
         *data == self.variant
     }
@@ -319,14 +889,50 @@ pub const TOGGLE_ACTIVE_FG: Key<druid::Color> = Key::new("togglebutton.active.fg
 pub const TOGGLE_INACTIVE_BG: Key<druid::Color> = Key::new("togglebutton.inactive.bg");
 pub const TOGGLE_INACTIVE_FG: Key<druid::Color> = Key::new("togglebutton.inactive.fg");
 pub const TOGGLE_BORDER: Key<druid::Color> = Key::new("togglebutton.border");
+pub const TOGGLE_TEXT_SIZE: Key<f64> = Key::new("togglebutton.text-size");
+pub const TOGGLE_FOCUS: Key<druid::Color> = Key::new("togglebutton.focus");
+
+/// Whether generated surfaces are sampled bilinearly rather than nearest
+/// neighbor when drawn.
+pub const SMOOTH_SURFACES: Key<bool> = Key::new("picker.surface.smooth");
+/// Whether the hue and alpha sliders label the values at their ends.
+pub const SLIDER_LABELS: Key<bool> = Key::new("picker.slider.labels");
+
+/// The selection markers on sliders and planes.
+pub const SELECTION: Key<druid::Color> = Key::new("picker.selection");
+/// Drop shadows under selection markers and swatch text.
+pub const SHADOW: Key<druid::Color> = Key::new("picker.shadow");
+/// The thin border around sliders, planes and swatches.
+pub const OUTLINE: Key<druid::Color> = Key::new("picker.outline");
+/// Lines marking a boundary within a plane or slider, like the gamut edge.
+pub const MARKER: Key<druid::Color> = Key::new("picker.marker");
+pub const HISTOGRAM: Key<druid::Color> = Key::new("picker.histogram");
+pub const CHECKER_LIGHT: Key<druid::Color> = Key::new("picker.checker.light");
+pub const CHECKER_DARK: Key<druid::Color> = Key::new("picker.checker.dark");
+/// Text drawn over the current color.
+pub const SWATCH_TEXT: Key<druid::Color> = Key::new("picker.swatch.text");
+pub const CLIPPED_BADGE: Key<druid::Color> = Key::new("picker.swatch.clipped");
+/// Less prominent labels, like captions and hints.
+pub const LABEL_SECONDARY: Key<druid::Color> = Key::new("label.secondary");
+pub const LABEL_MUTED: Key<druid::Color> = Key::new("label.muted");
+
+/// Sent when a widget acts on a key press, with the key, so the app can
+/// ignore its release.
+pub const KEY_HANDLED: Selector<KbKey> = Selector::new("key-handled");
 
 impl<T: Data + PartialEq + Display + std::fmt::Debug> Widget<T> for ToggleButton<T> {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, _env: &Env) {
         match event {
-            Event::MouseDown(_) => {
+            Event::MouseDown(_) if !self.disabled => {
                 ctx.set_active(true);
+                ctx.request_focus();
                 ctx.request_paint();
             }
+            Event::KeyDown(e) if ctx.is_focused() && !self.disabled && is_activation(&e.key) => {
+                *data = self.variant.clone();
+                ctx.set_handled();
+                ctx.submit_command(Command::new(KEY_HANDLED, e.key.clone(), Target::Global));
+            }
             Event::MouseUp(_) => {
                 if ctx.is_active() {
                     ctx.set_active(false);
@@ -342,29 +948,34 @@ impl<T: Data + PartialEq + Display + std::fmt::Debug> Widget<T> for ToggleButton
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
         if matches!(event, LifeCycle::WidgetAdded) {
-            self.layout.set_text(self.variant.to_string());
+            self.layout.set_text(match self.icon {
+                Some(icon) => format!("{} {}", icon, self.variant),
+                None => self.variant.to_string(),
+            });
             self.layout.set_font(FontDescriptor::new(FontFamily::SYSTEM_UI));
             self.layout.set_text_alignment(TextAlignment::Center);
-            self.layout.set_text_size(9.0);
-            if self.is_active(data) {
-                self.layout.set_text_color(env.get(TOGGLE_ACTIVE_FG));
-            } else {
-                self.layout.set_text_color(env.get(TOGGLE_INACTIVE_FG));
+            self.layout.set_text_size(env.get(TOGGLE_TEXT_SIZE));
+            self.layout.set_text_color(self.text_color(data, env));
+            self.layout.rebuild_if_needed(ctx.text(), env);
+            if !self.disabled {
+                ctx.register_for_focus();
             }
-            self.layout.rebuild_if_needed(ctx.text(), env)
         }
-        if let LifeCycle::HotChanged(_) = event {
+        if let LifeCycle::FocusChanged(_) = event {
+            ctx.request_paint();
+        }
+        if let LifeCycle::HotChanged(hot) = event {
+            if let (Some(selector), false) = (self.hover, self.disabled) {
+                let variant = if *hot { Some(self.variant.clone()) } else { None };
+                ctx.submit_command(Command::new(selector, variant, Target::Global));
+            }
             ctx.request_paint();
         }
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
         if !old_data.same(data) {
-            if self.is_active(data) {
-                self.layout.set_text_color(env.get(TOGGLE_ACTIVE_FG));
-            } else {
-                self.layout.set_text_color(env.get(TOGGLE_INACTIVE_FG));
-            }
+            self.layout.set_text_color(self.text_color(data, env));
             self.layout.rebuild_if_needed(ctx.text(), env);
             ctx.request_paint();
         }
@@ -386,6 +997,9 @@ impl<T: Data + PartialEq + Display + std::fmt::Debug> Widget<T> for ToggleButton
             env.get(TOGGLE_INACTIVE_BG)
         };
         ctx.fill(Rect::new(0.0, 0.0, size.width, size.height), &bg);
+        if ctx.is_hot() && !self.disabled && !self.is_active(data) {
+            ctx.fill(Rect::new(0.0, 0.0, size.width, size.height), &env.get(TOGGLE_ACTIVE_BG).with_alpha(0.5));
+        }
 
         let border = env.get(TOGGLE_BORDER);
         if !self.is_first {
@@ -402,5 +1016,348 @@ impl<T: Data + PartialEq + Display + std::fmt::Debug> Widget<T> for ToggleButton
         // Paint the text label
         let offset = (size.to_vec2() - self.layout.size().to_vec2()) / 2.0;
         self.layout.draw(ctx, offset.to_point());
+
+        if ctx.is_focused() {
+            let ring = Rect::new(0.0, 0.0, size.width, size.height).inset(-1.5);
+            ctx.stroke(ring.to_rounded_rect(2.0), &env.get(TOGGLE_FOCUS), 1.5);
+        }
+
+        if let (Some(tooltip), true) = (&self.tooltip, ctx.is_hot()) {
+            paint_tooltip(ctx, tooltip.clone(), env);
+        }
+    }
+}
+
+/// Whether `key` presses a focused button.
+fn is_activation(key: &KbKey) -> bool {
+    match key {
+        KbKey::Enter => true,
+        KbKey::Character(c) => c == " ",
+        _ => false,
+    }
+}
+
+/// Paints `text` in a box just above the widget being painted, or below it
+/// at the top of the window, on top of everything else and kept inside the
+/// window.
+fn paint_tooltip(ctx: &mut PaintCtx, text: String, env: &Env) {
+    let (fg, bg) = (env.get(druid::theme::WINDOW_BACKGROUND_COLOR), env.get(druid::theme::LABEL_COLOR));
+    let text_size = env.get(TOGGLE_TEXT_SIZE);
+    let own = ctx.size();
+    let window = ctx.window().get_size().width / ctx.window().get_scale().map_or(1.0, |s| s.x());
+    let [.., origin_x, origin_y] = ctx.current_transform().as_coeffs();
+    ctx.paint_with_z_index(1, move |ctx| {
+        let mut layout: TextLayout<String> = TextLayout::from_text(text);
+        layout.set_font(FontDescriptor::new(FontFamily::SYSTEM_UI));
+        layout.set_text_size(text_size);
+        layout.set_text_color(fg);
+        layout.rebuild_if_needed(ctx.text(), &Env::default());
+        let size = layout.size() + Size::new(8.0, 4.0);
+        let x = ((own.width - size.width) / 2.0)
+            .min(window - origin_x - size.width)
+            .max(-origin_x);
+        let y = if origin_y >= size.height + 2.0 { -size.height - 2.0 } else { own.height + 2.0 };
+        let rect = Rect::from_origin_size((x, y), size);
+        ctx.fill(rect.to_rounded_rect(2.0), &bg);
+        layout.draw(ctx, rect.origin() + druid::Vec2::new(4.0, 2.0));
+    });
+}
+
+/// A row of [`ToggleButton`]s choosing one of `variants`, with the ends
+/// styled as such. While one of the buttons has keyboard focus, the left
+/// and right arrows cycle through the variants, taking focus along.
+pub struct ToggleGroup<T> {
+    variants: Vec<T>,
+    hover: Option<Selector<Option<T>>>,
+    icons: Option<Box<dyn Fn(&T) -> &'static str>>,
+    buttons: Option<Box<dyn Fn(&T, ToggleButton<T>) -> ToggleButton<T>>>,
+    /// Which variants' buttons are disabled, for cycling past them.
+    disabled: Vec<bool>,
+    ids: Vec<WidgetId>,
+    row: WidgetPod<T, Flex<T>>,
+}
+
+impl<T: Data + PartialEq + Display + std::fmt::Debug> ToggleGroup<T> {
+    pub fn new(variants: Vec<T>) -> Self {
+        let mut group = ToggleGroup {
+            variants,
+            hover: None,
+            icons: None,
+            buttons: None,
+            disabled: Vec::new(),
+            ids: Vec::new(),
+            row: WidgetPod::new(Flex::row()),
+        };
+        group.build();
+        group
+    }
+
+    /// See [`ToggleButton::with_hover`].
+    pub fn with_hover(mut self, selector: Selector<Option<T>>) -> Self {
+        self.hover = Some(selector);
+        self.build();
+        self
+    }
+
+    /// Gives each button the icon `icons` returns for its variant.
+    pub fn with_icons(mut self, icons: impl Fn(&T) -> &'static str + 'static) -> Self {
+        self.icons = Some(Box::new(icons));
+        self.build();
+        self
+    }
+
+    /// Passes each button through `buttons` along with its variant, e.g. to
+    /// disable some or give them tooltips.
+    pub fn with_buttons(mut self, buttons: impl Fn(&T, ToggleButton<T>) -> ToggleButton<T> + 'static) -> Self {
+        self.buttons = Some(Box::new(buttons));
+        self.build();
+        self
+    }
+
+    fn build(&mut self) {
+        let mut row = Flex::row().must_fill_main_axis(true);
+        self.disabled.clear();
+        self.ids.clear();
+        let last = self.variants.len().saturating_sub(1);
+        for (ix, variant) in self.variants.iter().enumerate() {
+            let mut button = ToggleButton::new(variant.clone(), ix == 0, ix == last);
+            if let Some(selector) = self.hover {
+                button = button.with_hover(selector);
+            }
+            if let Some(icons) = &self.icons {
+                button = button.with_icon(icons(variant));
+            }
+            if let Some(buttons) = &self.buttons {
+                button = buttons(variant, button);
+            }
+            self.disabled.push(button.disabled);
+            let id = WidgetId::next();
+            self.ids.push(id);
+            row.add_flex_child(button.with_id(id).expand(), 1.0);
+        }
+        self.row = WidgetPod::new(row);
+    }
+
+    /// Moves `step` variants along from the chosen one, wrapping around and
+    /// skipping disabled ones, and returns the new one's index. From a value
+    /// that isn't one of them, it starts before the first.
+    fn cycle(&self, data: &mut T, step: isize) -> Option<usize> {
+        let len = self.variants.len() as isize;
+        let mut current = match self.variants.iter().position(|v| v == data) {
+            Some(ix) => ix as isize,
+            None if step > 0 => -1,
+            None => len,
+        };
+        for _ in 0..len {
+            current = (current + step).rem_euclid(len);
+            if !self.disabled[current as usize] {
+                *data = self.variants[current as usize].clone();
+                return Some(current as usize);
+            }
+        }
+        None
+    }
+}
+
+impl<T: Data + PartialEq + Display + std::fmt::Debug> Widget<T> for ToggleGroup<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::KeyDown(e) if ctx.has_focus() => {
+                let step = match e.key {
+                    KbKey::ArrowLeft => -1,
+                    KbKey::ArrowRight => 1,
+                    _ => 0,
+                };
+                if step != 0 {
+                    if let Some(ix) = self.cycle(data, step) {
+                        ctx.set_focus(self.ids[ix]);
+                    }
+                    ctx.set_handled();
+                }
+            }
+            _ => {}
+        }
+        self.row.event(ctx, event, data, env);
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.row.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        self.row.update(ctx, data, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let size = self.row.layout(ctx, bc, data, env);
+        self.row.set_origin(ctx, data, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.row.paint(ctx, data, env);
+    }
+}
+
+/// A row of [`ToggleButton`]s that fits at most `max`. Past that the last
+/// slot is a "…" button that opens the rest in rows underneath, which
+/// close again once one is picked. "…" shows as active when the value is
+/// one of those.
+pub struct OverflowButtons<T> {
+    shown: WidgetPod<T, ToggleGroup<T>>,
+    hidden: WidgetPod<T, Flex<T>>,
+    hidden_values: Vec<T>,
+    max: usize,
+    height: f64,
+    open: bool,
+    more: TextLayout<String>,
+    clears: Option<Selector<Option<T>>>,
+}
+
+impl<T: Data + PartialEq + Display + std::fmt::Debug> OverflowButtons<T> {
+    /// Each row is passed through `group`, e.g. to add hover selectors or
+    /// customize the buttons.
+    pub fn new(values: Vec<T>, max: usize, height: f64, group: impl Fn(ToggleGroup<T>) -> ToggleGroup<T>) -> Self {
+        let split = if values.len() > max { max - 1 } else { values.len() };
+        let row = |values: &[T]| group(ToggleGroup::new(values.to_vec()));
+        let mut hidden = Flex::column();
+        for chunk in values[split..].chunks(max) {
+            // keep the buttons the same width as the ones above
+            let chunk_row = Flex::row()
+                .with_flex_child(row(chunk), chunk.len() as f64)
+                .with_flex_spacer((max - chunk.len()) as f64);
+            hidden.add_child(chunk_row.fix_height(height));
+        }
+        OverflowButtons {
+            shown: WidgetPod::new(row(&values[..split])),
+            hidden: WidgetPod::new(hidden),
+            hidden_values: values[split..].to_vec(),
+            max,
+            height,
+            open: false,
+            more: TextLayout::from_text("…".to_string()),
+            clears: None,
+        }
+    }
+
+    /// Sends `None` to a hover `selector` when the rows close under the
+    /// pointer, as their buttons are then no longer hot but don't say so.
+    pub fn clears_hover(mut self, selector: Selector<Option<T>>) -> Self {
+        self.clears = Some(selector);
+        self
+    }
+
+    fn close(&mut self, ctx: &mut EventCtx) {
+        if let (Some(selector), true) = (self.clears, self.hidden.is_hot()) {
+            ctx.submit_command(Command::new(selector, None, Target::Global));
+        }
+        self.open = false;
+        ctx.request_layout();
+    }
+
+    fn set_more_color(&mut self, data: &T, env: &Env) {
+        if self.hidden_values.contains(data) {
+            self.more.set_text_color(env.get(TOGGLE_ACTIVE_FG));
+        } else {
+            self.more.set_text_color(env.get(TOGGLE_INACTIVE_FG));
+        }
+    }
+
+    /// Where "…" goes, if there is one.
+    fn more_rect(&self, width: f64) -> Option<Rect> {
+        if self.hidden_values.is_empty() {
+            return None;
+        }
+        let cell = width / self.max as f64;
+        Some(Rect::new(width - cell, 0.0, width, self.height))
+    }
+}
+
+impl<T: Data + PartialEq + Display + std::fmt::Debug> Widget<T> for OverflowButtons<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let more = self.more_rect(ctx.size().width);
+        match event {
+            Event::MouseDown(e) if more.map_or(false, |r| r.contains(e.pos)) => {
+                ctx.set_active(true);
+                return;
+            }
+            Event::MouseUp(e) if ctx.is_active() => {
+                ctx.set_active(false);
+                if more.map_or(false, |r| r.contains(e.pos)) {
+                    if self.open {
+                        self.close(ctx);
+                    } else {
+                        self.open = true;
+                        ctx.request_layout();
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+        self.shown.event(ctx, event, data, env);
+        if self.open {
+            let before = data.clone();
+            self.hidden.event(ctx, event, data, env);
+            if !before.same(data) {
+                self.close(ctx);
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if matches!(event, LifeCycle::WidgetAdded) {
+            self.more.set_font(FontDescriptor::new(FontFamily::SYSTEM_UI));
+            self.more.set_text_alignment(TextAlignment::Center);
+            self.more.set_text_size(env.get(TOGGLE_TEXT_SIZE));
+            self.set_more_color(data, env);
+        }
+        self.shown.lifecycle(ctx, event, data, env);
+        self.hidden.lifecycle(ctx, event, data, env);
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.shown.update(ctx, data, env);
+        self.hidden.update(ctx, data, env);
+        if !old_data.same(data) {
+            self.set_more_color(data, env);
+            ctx.request_layout();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        let width = bc.max().width;
+        let shown_width = self.more_rect(width).map_or(width, |r| r.x0);
+        self.shown.layout(ctx, &BoxConstraints::tight(Size::new(shown_width, self.height)), data, env);
+        self.shown.set_origin(ctx, data, env, Point::ORIGIN);
+
+        let mut height = self.height;
+        if self.open {
+            let bc = BoxConstraints::new(Size::new(width, 0.0), Size::new(width, f64::INFINITY));
+            let hidden = self.hidden.layout(ctx, &bc, data, env);
+            self.hidden.set_origin(ctx, data, env, Point::new(0.0, height));
+            height += hidden.height;
+        }
+        self.more.rebuild_if_needed(ctx.text(), env);
+        Size::new(width, height)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.shown.paint(ctx, data, env);
+        if let Some(rect) = self.more_rect(ctx.size().width) {
+            let active = self.hidden_values.contains(data);
+            let bg = if active { env.get(TOGGLE_ACTIVE_BG) } else { env.get(TOGGLE_INACTIVE_BG) };
+            ctx.fill(rect, &bg);
+            let border = env.get(TOGGLE_BORDER);
+            ctx.stroke(Line::new((rect.x0, rect.y0), (rect.x0, rect.y1)), &border, 1.0);
+            if !active {
+                ctx.stroke(Line::new((rect.x0, 0.5), (rect.x1, 0.5)), &border, 1.0);
+            }
+            let offset = (rect.size().to_vec2() - self.more.size().to_vec2()) / 2.0;
+            self.more.draw(ctx, rect.origin() + offset);
+        }
+        if self.open {
+            self.hidden.paint(ctx, data, env);
+        }
     }
 }
@@ -0,0 +1,30 @@
+//! Runs the built picker with stdout on a pipe, as the extension does.
+
+use std::process::{Command, Stdio};
+
+#[test]
+fn last_line_reaches_a_pipe() {
+    let out = Command::new(env!("CARGO_BIN_EXE_picker"))
+        .arg("--capabilities")
+        .stdout(Stdio::piped())
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.ends_with('\n'), "unterminated: {:?}", stdout);
+    let caps: serde_json::Value = serde_json::from_str(stdout.trim_end()).unwrap();
+    assert!(caps["modes"].as_array().unwrap().iter().any(|m| m == "continuous"));
+}
+
+#[test]
+fn usage_errors_reach_a_pipe() {
+    let out = Command::new(env!("CARGO_BIN_EXE_picker"))
+        .args(["--error-format", "json", "--positon", "over"])
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    let error: serde_json::Value = serde_json::from_str(stderr.trim_end()).unwrap();
+    assert_eq!(error["error"], "usage");
+}